@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use schemat::{format_source, Options};
+use std::hint::black_box;
+
+const ATOM_COUNT: usize = 1000;
+
+fn generate_source(separator: &str) -> String {
+    format!(
+        "(foo {})",
+        (0..ATOM_COUNT)
+            .map(|index| format!("(bar {})", index))
+            .collect::<Vec<_>>()
+            .join(separator)
+    )
+}
+
+fn format_sources(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("format");
+    let options = Options::new();
+
+    for (name, separator) in [("single_line", " "), ("multiple_lines", "\n")] {
+        let source = generate_source(separator);
+
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &source,
+            |bencher, source| bencher.iter(|| black_box(format_source(source, &options).unwrap())),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, format_sources);
+criterion_main!(benches);
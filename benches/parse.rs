@@ -0,0 +1,64 @@
+#![feature(allocator_api)]
+
+use bumpalo::Bump;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use schemat::{parse, Dialect};
+use std::{alloc::Global, hint::black_box};
+
+const WIDTH: usize = 5;
+const MAX_DEPTH: usize = 4;
+
+fn generate_list(width: usize, depth: usize) -> String {
+    let children = (0..width)
+        .map(|index| {
+            if depth == 0 {
+                index.to_string()
+            } else {
+                generate_list(width, depth - 1)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("({})", children.join("\n"))
+}
+
+fn generate_source(depth: usize) -> String {
+    // Keep the same number of atoms in sources of different depths.
+    (0..WIDTH.pow((MAX_DEPTH - depth) as u32))
+        .map(|_| generate_list(WIDTH, depth))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_sources(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("parse");
+
+    for (name, depth) in [("shallow", 0), ("medium", 2), ("deep", MAX_DEPTH)] {
+        let source = generate_source(depth);
+
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("bump", name),
+            &source,
+            |bencher, source| {
+                bencher.iter(|| {
+                    let allocator = Bump::new();
+
+                    black_box(parse(source, Dialect::Scheme, &allocator).unwrap());
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("global", name),
+            &source,
+            |bencher, source| {
+                bencher.iter(|| black_box(parse(source, Dialect::Scheme, Global).unwrap()))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_sources);
+criterion_main!(benches);
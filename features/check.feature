@@ -28,6 +28,23 @@ Feature: Check
     And the stderr should contain "ERROR"
     And the stderr should contain "foo.scm"
 
+  Scenario: Check an invalid file skipping it
+    Given a file named "foo.scm" with:
+      """
+      (
+      """
+    When I successfully run `schemat --check --on-unparseable skip foo.scm`
+    Then the stderr should contain "SKIP"
+    And the stderr should contain "foo.scm"
+
+  Scenario: Check a file with a byte order mark
+    Given a file named "foo.scm" with:
+      """
+      ﻿(foo bar)
+
+      """
+    When I successfully run `schemat --check foo.scm`
+
   Scenario: Check files
     Given a file named "foo.scm" with:
       """
@@ -43,6 +60,23 @@ Feature: Check
     Then the stderr should not contain "foo.scm"
     And the stderr should not contain "bar.scm"
 
+  Scenario: Check files with an unreadable one
+    Given a file named "foo.scm" with mode "0000" and with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+
+      """
+    When I run `schemat --check foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "ERROR\tpermission denied reading foo.scm"
+    And the stderr should contain "FAIL\tbar.scm"
+    And the stderr should contain "2 / 2 file(s) failed"
+
   Scenario: Check files with a glob
     Given a file named "foo.scm" with:
       """
@@ -100,3 +134,521 @@ Feature: Check
       """
       cannot check stdin
       """
+
+  Scenario: Check files with a porcelain option
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    And a file named "baz.scm" with:
+      """
+      (
+      """
+    When I run `schemat --check --porcelain foo.scm bar.scm baz.scm`
+    Then the exit status should not be 0
+    And the stdout should contain exactly:
+      """
+      ok=1 changed=1 error=1
+      """
+
+  Scenario: Check files with a summary on stdout
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    When I run `schemat --check --summary-stdout foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stdout should contain exactly:
+      """
+      1 / 2 file(s) failed
+      """
+    And the stderr should contain "1 / 2 file(s) failed"
+
+  Scenario: Check a formatted file with a summary on stdout
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    When I successfully run `schemat --check --summary-stdout foo.scm`
+    Then the stdout should contain exactly:
+      """
+      0 / 1 file(s) failed
+      """
+    And the stderr should not contain anything
+
+  Scenario: Check files with an ignore pattern
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    When I successfully run `schemat --check --ignore bar.scm *.scm`
+    Then the stderr should not contain "bar.scm"
+
+  Scenario: Check an explicit file matching an ignore pattern
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --check --ignore foo.scm foo.scm`
+    Then the stderr should not contain "foo.scm"
+
+  Scenario: Check an explicit file with a current directory prefix matching an ignore pattern
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --check --ignore foo.scm ./foo.scm`
+    Then the stderr should not contain "foo.scm"
+
+  Scenario: Check files in a current directory matching an ignore pattern
+    Given a file named "foo/bar.scm" with:
+      """
+        bar
+      """
+    When I successfully run `schemat --check --ignore "foo/*.scm" .`
+    Then the stderr should not contain "bar.scm"
+
+  Scenario: Check an explicit file matching an ignore pattern without ignoring explicit files
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I run `schemat --check --ignore foo.scm --no-ignore-explicit foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm"
+
+  Scenario: Check and fix a file not formatted
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I run `schemat --check --fix foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm"
+    And a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+
+  Scenario: Check and fix a file formatted already
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    When I successfully run `schemat --check --fix foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+
+  Scenario: Check files changed since a Git reference
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+      bar
+
+      """
+    And I successfully run `git init`
+    And I successfully run `git add .`
+    And I successfully run `git -c user.name=foo -c user.email=foo@bar.com commit -m foo`
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    When I run `schemat --check --since HEAD foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stderr should not contain "foo.scm"
+    And the stderr should contain "FAIL\tbar.scm"
+
+  Scenario: Warn about long lines in a file
+    Given a file named "foo.scm" with:
+      """
+      (foo bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      (foo
+        "barbazquxquux")
+      """
+    When I successfully run `schemat --warn-long-lines 12 foo.scm bar.scm`
+    Then the stderr should not contain "foo.scm"
+    And the stderr should contain "WARN\tbar.scm:2: 18 columns exceed 12"
+    And a file named "bar.scm" should contain exactly:
+      """
+      (foo
+        "barbazquxquux")
+      """
+
+  Scenario: Warn about long lines of long strings as unavoidable
+    Given a file named "foo.scm" with:
+      """
+      (foo
+        "barbazquxquux"
+        (bar baz qux quux))
+      """
+    When I successfully run `schemat --warn-long-lines 12 foo.scm`
+    Then the stderr should contain "WARN\tfoo.scm:2: 17 columns exceed 12 (unavoidable due to a long string)"
+    And the stderr should contain "WARN\tfoo.scm:3: 21 columns exceed 12"
+    And the stderr should not contain "foo.scm:3: 21 columns exceed 12 (unavoidable"
+
+  Scenario: Fail to check a file indented with tabs
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      	bar)
+      """
+    When I run `schemat --no-tabs foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm:2: tabs in indentation"
+
+  Scenario: Check a file indented with spaces and tabs in strings
+    Given a file named "foo.scm" with:
+      """
+      (foo
+        "bar
+      	baz")
+      """
+    When I successfully run `schemat --no-tabs foo.scm`
+    Then the stderr should not contain "FAIL"
+
+  Scenario: Check a file not formatted tolerantly
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --check --tolerate-unformatted foo.scm`
+    Then the stderr should contain "FAIL\tfoo.scm"
+
+  Scenario: Fail to check a broken file tolerantly
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --check --tolerate-unformatted foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "ERROR"
+
+  Scenario: Check unformatted and broken files
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      (
+      """
+    When I run `schemat --check foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm"
+    And the stderr should contain "ERROR"
+    And the stderr should contain "2 / 2 file(s) failed"
+
+  Scenario: Check unformatted and broken files tolerantly
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      (
+      """
+    When I run `schemat --check --tolerate-unformatted foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm"
+    And the stderr should contain "ERROR"
+    And the stderr should contain "1 / 2 file(s) failed"
+
+  Scenario: Check unformatted and broken files tolerantly skipping broken ones
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      (
+      """
+    When I successfully run `schemat --check --tolerate-unformatted --on-unparseable skip foo.scm bar.scm`
+    Then the stderr should contain "FAIL\tfoo.scm"
+    And the stderr should contain "SKIP"
+
+  Scenario: Check files with unformatted ones at a maximum percentage
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      bar
+
+      """
+    And a file named "baz.scm" with:
+      """
+      baz
+
+      """
+    And a file named "qux.scm" with:
+      """
+      qux
+
+      """
+    When I successfully run `schemat --check --max-unformatted-percent 25 foo.scm bar.scm baz.scm qux.scm`
+    Then the stderr should contain "FAIL\tfoo.scm"
+
+  Scenario: Check files with unformatted ones below a maximum percentage
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      bar
+
+      """
+    And a file named "baz.scm" with:
+      """
+      baz
+
+      """
+    And a file named "qux.scm" with:
+      """
+      qux
+
+      """
+    When I successfully run `schemat --check --max-unformatted-percent 26 foo.scm bar.scm baz.scm qux.scm`
+    Then the stderr should contain "FAIL\tfoo.scm"
+
+  Scenario: Fail to check files with unformatted ones above a maximum percentage
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar.scm" with:
+      """
+      bar
+
+      """
+    And a file named "baz.scm" with:
+      """
+      baz
+
+      """
+    And a file named "qux.scm" with:
+      """
+      qux
+
+      """
+    When I run `schemat --check --max-unformatted-percent 24 foo.scm bar.scm baz.scm qux.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "1 / 4 file(s) failed"
+
+  Scenario: Fail to check a broken file with a maximum percentage
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --check --max-unformatted-percent 100 foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "ERROR"
+
+  Scenario: Parse a file not formatted
+    Given a file named "foo.scm" with:
+      """
+        (foo  bar)
+      """
+    When I successfully run `schemat --parse-only --verbose foo.scm`
+    Then the stderr should contain "OK\tfoo.scm"
+    And a file named "foo.scm" should contain exactly:
+      """
+        (foo  bar)
+      """
+
+  Scenario: Fail to parse a broken file
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --parse-only foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "ERROR"
+
+  Scenario: Fail to check a file nested too deeply
+    Given a file named "foo.scm" with:
+      """
+      (((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((((())))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))))
+
+      """
+    And a file named "bar.scm" with:
+      """
+      (foo (bar))
+
+      """
+    When I run `schemat --check --max-depth 8 foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "ERROR\tmaximum nesting depth exceeded foo.scm:1:9"
+    And the stderr should contain "1 / 2 file(s) failed"
+
+  Scenario: Check files in SARIF
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+      (bar
+      """
+    When I run `schemat --check --format sarif foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stdout should contain:
+      """
+      "version":"2.1.0"
+      """
+    And the stdout should contain:
+      """
+      "ruleId":"unformatted"
+      """
+    And the stdout should contain:
+      """
+      "uri":"foo.scm"
+      """
+    And the stdout should contain:
+      """
+      "ruleId":"error"
+      """
+    And the stdout should contain:
+      """
+      "uri":"bar.scm"
+      """
+
+  Scenario: Check files with GitHub Actions workflow commands
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+      (bar
+      """
+    When I run `schemat --check --format github foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stdout should contain "::warning file=foo.scm,line=1::file not formatted"
+    And the stdout should contain "::error file=bar.scm,line=1,col=4::failed to parse"
+
+  Scenario: Check files with annotations of changed lines
+    Given a file named "foo.scm" with:
+      """
+      (foo
+        bar)
+      (baz  qux)
+
+      (a)
+      (b)
+      (c
+         d)
+
+      """
+    And a file named "bar.scm" with:
+      """
+      (bar
+      """
+    When I run `schemat --check --format annotations foo.scm bar.scm`
+    Then the exit status should not be 0
+    And the stdout should contain exactly:
+      """
+      foo.scm:3: needs formatting
+      foo.scm:8: needs formatting
+      bar.scm:1: failed to parse
+      """
+
+  Scenario: Check files in a directory recursively
+    Given a file named "foo/bar.scm" with:
+      """
+      bar
+      """
+    And a file named "foo/baz/qux.scm" with:
+      """
+        qux
+      """
+    When I run `schemat --check foo`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo/baz/qux.scm"
+    And the stderr should not contain "foo/bar.scm"
+
+  Scenario: Check a file with whitespace differences loosely
+    Given a file named "foo.scm" with:
+      """
+      (foo   bar)
+      """
+    When I successfully run `schemat --check --loose-check foo.scm`
+    Then the stderr should not contain "FAIL"
+
+  Scenario: Check a file with structural differences loosely
+    Given a file named "foo.scm" with:
+      """
+      'foo
+      """
+    When I run `schemat --check --loose-check --quote-style full foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "FAIL\tfoo.scm"
+
+  Scenario: Check a file with a forbidden symbol
+    Given a file named "foo.scm" with:
+      """
+      (define (foo)
+        (let ((x 1))
+          (set! x 2)))
+      """
+    When I run `schemat --check --forbid-symbol set! foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "forbidden symbol foo.scm:3:6: set!"
+
+  Scenario: Check a file without forbidden symbols
+    Given a file named "foo.scm" with:
+      """
+      (define x 1)
+
+      """
+    When I successfully run `schemat --check --forbid-symbol set! foo.scm`
+    Then the stderr should not contain anything
+
+  Scenario: Check files grouping results by directories
+    Given a file named "foo/foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "foo/bar/bar.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar/foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "bar/bar.scm" with:
+      """
+      foo
+
+      """
+    When I run `schemat --check --group-by-dir foo bar`
+    Then the exit status should not be 0
+    And the stderr should contain "foo/: 2 failed"
+    And the stderr should contain "bar/: 1 failed"
@@ -12,6 +12,59 @@ Feature: Format
       foo
       """
 
+  Scenario: Fail to format invalid stdin
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat` interactively
+    And I pipe in the file "foo.scm"
+    Then the exit status should not be 0
+    And the stderr should contain "<stdin>"
+
+  Scenario: Format stdin with a dialect inferred from a file name
+    Given a file named "foo.scm" with:
+      """
+      (foo {bar
+      baz})
+      """
+    When I run `schemat --assume-filename foo.el` interactively
+    And I pipe in the file "foo.scm"
+    Then the exit status should be 0
+    And the stdout should contain exactly:
+      """
+      (foo {bar
+        baz})
+      """
+
+  Scenario: Fail to format invalid stdin with a file name
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --assume-filename bar.rkt` interactively
+    And I pipe in the file "foo.scm"
+    Then the exit status should not be 0
+    And the stderr should contain "bar.rkt:1:5"
+
+  Scenario: Format sources in a server mode
+    Given a file named "requests" with:
+      """
+      7 10
+      foo.scm(foo  bar)7 3
+      bar.scm  a
+      """
+    When I run `schemat --server` interactively
+    And I pipe in the file "requests"
+    Then the exit status should be 0
+    And the stdout should contain exactly:
+      """
+      ok 10
+      (foo bar)
+      ok 2
+      a
+      """
+
   Scenario: Format a file
     Given a file named "foo.scm" with:
       """
@@ -23,6 +76,31 @@ Feature: Format
       foo
       """
 
+  Scenario: Format a file safely
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      bar)
+      """
+    When I successfully run `schemat --safe foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      (foo
+        bar)
+      """
+
+  Scenario: Format a file preserving its permissions
+    Given a file named "foo.scm" with mode "0755" and with:
+      """
+        foo
+      """
+    When I successfully run `schemat foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And the file named "foo.scm" should have permissions "0755"
+
   Scenario: Format files
     Given a file named "foo.scm" with:
       """
@@ -103,3 +181,417 @@ Feature: Format
     And the stderr should contain "FORMAT\tfoo.scm"
     And the stderr should contain "ERROR"
     And the stderr should contain "bar.scm"
+
+  Scenario: Skip files that fail to be parsed
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+      (
+      """
+    When I successfully run `schemat --on-unparseable skip foo.scm bar.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And a file named "bar.scm" should contain exactly:
+      """
+      (
+      """
+    And the stderr should contain "SKIP"
+    And the stderr should contain "bar.scm"
+
+  Scenario: Fail on files that fail to be parsed
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+      (
+      """
+    When I run `schemat --on-unparseable error foo.scm bar.scm`
+    Then the exit status should not be 0
+    And a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And the stderr should contain "ERROR"
+    And the stderr should contain "1 / 2 file(s) failed to format"
+
+  Scenario: Skip files larger than a maximum size
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+        bar bar bar bar bar bar bar bar bar bar bar bar bar bar bar bar
+      """
+    When I successfully run `schemat --max-file-size 16 foo.scm bar.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And a file named "bar.scm" should contain exactly:
+      """
+        bar bar bar bar bar bar bar bar bar bar bar bar bar bar bar bar
+      """
+    And the stderr should contain "SKIP\tbar.scm"
+    And the stderr should contain "exceed maximum file size of 16 bytes"
+
+  Scenario: Format a file preserving its modification time
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And I successfully run `touch -d @1000000000 foo.scm`
+    When I successfully run `schemat --preserve-mtime foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    When I successfully run `stat -c %Y foo.scm`
+    Then the stdout should contain exactly "1000000000"
+
+  Scenario: Format files with a porcelain option
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    When I successfully run `schemat --porcelain foo.scm bar.scm`
+    Then the stdout should contain exactly:
+      """
+      ok=1 changed=1 error=0
+      """
+
+  Scenario: Format a file with a byte order mark
+    Given a file named "foo.scm" with:
+      """
+      ﻿(foo  bar)
+      """
+    When I successfully run `schemat foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      ﻿(foo bar)
+
+      """
+
+  Scenario: Format files with a manifest
+    Given a file named "foo.scm" with:
+      """
+      foo
+
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    And a file named "baz.scm" with:
+      """
+      (
+      """
+    When I run `schemat --manifest manifest.json foo.scm bar.scm baz.scm`
+    Then the exit status should not be 0
+    And a file named "manifest.json" should contain exactly:
+      """
+      {"files":[{"path":"foo.scm","changed":false,"length":4},{"path":"bar.scm","changed":true,"length":4},{"path":"baz.scm","error":"failed to parse"}]}
+
+      """
+
+  Scenario: Format files with a timings option
+    Given a file named "foo.scm" with:
+      """
+      foo
+      """
+    When I successfully run `schemat --timings foo.scm`
+    Then the stderr should contain "foo.scm: "
+    And the stderr should contain " parse, "
+    And the stderr should contain "total: "
+
+  Scenario: Format a file matched by multiple patterns once
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --porcelain foo.scm ./foo.scm *.scm`
+    Then the stdout should contain exactly:
+      """
+      ok=0 changed=1 error=0
+      """
+
+  Scenario: Format files with a thread option
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar.scm" with:
+      """
+        bar
+      """
+    When I successfully run `schemat --threads 1 foo.scm bar.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And a file named "bar.scm" should contain exactly:
+      """
+      bar
+      """
+
+  Scenario: Fail to format files with zero threads
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I run `schemat --threads 0 foo.scm`
+    Then the exit status should not be 0
+    And a file named "foo.scm" should contain exactly:
+      """
+        foo
+      """
+
+  Scenario: Format files into an output directory
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    And a file named "bar/baz.scm" with:
+      """
+        baz
+      """
+    When I successfully run `schemat --out-dir out foo.scm bar/baz.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+        foo
+      """
+    And a file named "out/foo.scm" should contain exactly:
+      """
+      foo
+      """
+    And a file named "out/bar/baz.scm" should contain exactly:
+      """
+      baz
+      """
+
+  Scenario: Format source code given by an option
+    When I successfully run `schemat -e "(foo  bar)"`
+    Then the stdout should contain exactly:
+      """
+      (foo bar)
+      """
+
+  Scenario: Fail to format source code given by an option with paths
+    Given a file named "foo.scm" with:
+      """
+      foo
+      """
+    When I run `schemat -e foo foo.scm`
+    Then the exit status should not be 0
+
+  Scenario: Fail to format files with an invalid glob
+    When I run `schemat "foo["`
+    Then the exit status should not be 0
+    And the stderr should contain "foo[: Pattern syntax error"
+
+  Scenario: Fail to format files with an invalid ignore pattern
+    Given a file named "foo.scm" with:
+      """
+      foo
+      """
+    When I run `schemat --ignore "bar[" foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "bar[: Pattern syntax error"
+
+  Scenario: Format files in a directory recursively
+    Given a file named "foo/bar.scm" with:
+      """
+        bar
+      """
+    And a file named "foo/baz/qux.scm" with:
+      """
+        qux
+      """
+    And a file named "foo/README" with:
+      """
+        README
+      """
+    When I successfully run `schemat foo`
+    Then a file named "foo/bar.scm" should contain exactly:
+      """
+      bar
+      """
+    And a file named "foo/baz/qux.scm" should contain exactly:
+      """
+      qux
+      """
+    And a file named "foo/README" should contain exactly:
+      """
+        README
+      """
+
+  Scenario: Format an empty directory
+    Given a directory named "foo.scm"
+    When I successfully run `schemat foo.scm`
+    Then the stderr should not contain anything
+
+  Scenario: Print a formatted file
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --print foo.scm`
+    Then the stdout should contain exactly:
+      """
+      foo
+      """
+    And a file named "foo.scm" should contain exactly:
+      """
+        foo
+      """
+
+  Scenario: Fail to print an invalid file
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --print foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "foo.scm"
+    And the stdout should not contain anything
+
+  Scenario: Compare a formatted file with expected output
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "foo.expected" with:
+      """
+      (foo bar)
+
+      """
+    When I successfully run `schemat --expect foo.expected foo.scm`
+    Then the stderr should not contain anything
+    And a file named "foo.scm" should contain exactly:
+      """
+      (foo  bar)
+      """
+
+  Scenario: Fail to compare a formatted file with unexpected output
+    Given a file named "foo.scm" with:
+      """
+      (foo  bar)
+      """
+    And a file named "foo.expected" with:
+      """
+      (foo baz)
+
+      """
+    When I run `schemat --expect foo.expected foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "formatted code differs from expected output: foo.scm"
+    And the stderr should contain "-(foo baz)"
+    And the stderr should contain "+(foo bar)"
+
+  Scenario: Fail to compare invalid stdin with expected output with a file name
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    And a file named "foo.expected" with:
+      """
+      (foo)
+
+      """
+    When I run `schemat --expect foo.expected --assume-filename bar.scm` interactively
+    And I pipe in the file "foo.scm"
+    Then the exit status should not be 0
+    And the stderr should contain "bar.scm:1:5"
+
+  Scenario: Format a file with a satisfied version requirement
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I successfully run `schemat --required-version >=0.1 foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      foo
+
+      """
+
+  Scenario: Fail to format a file with an unsatisfied version requirement
+    Given a file named "foo.scm" with:
+      """
+        foo
+      """
+    When I run `schemat --required-version <0.1 foo.scm`
+    Then the exit status should not be 0
+    And the stderr should contain "does not satisfy required version <0.1"
+    And a file named "foo.scm" should contain exactly:
+      """
+        foo
+      """
+
+  Scenario: Format a file in another directory
+    Given a file named "foo/bar.scm" with:
+      """
+        bar
+      """
+    When I successfully run `schemat --cwd foo bar.scm`
+    Then a file named "foo/bar.scm" should contain exactly:
+      """
+      bar
+      """
+
+  Scenario: Format a file with custom string delimiters
+    Given a file named "foo.scm" with:
+      """
+      (foo   «bar ;baz
+        "qux»)
+      """
+    When I successfully run `schemat --string-delimiters « » foo.scm`
+    Then a file named "foo.scm" should contain exactly:
+      """
+      (foo «bar ;baz
+        "qux»)
+
+      """
+
+  Scenario: Print edits of a file
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      bar)
+      (baz)
+
+      """
+    When I successfully run `schemat --edits foo.scm`
+    Then the stdout should contain exactly:
+      """
+      {"edits":[{"start":5,"end":10,"text":"  bar)\n"}]}
+      """
+    And a file named "foo.scm" should contain exactly:
+      """
+      (foo
+      bar)
+      (baz)
+
+      """
+
+  Scenario: Fail to print edits of invalid stdin with a file name
+    Given a file named "foo.scm" with:
+      """
+      (foo
+      """
+    When I run `schemat --edits --assume-filename bar.scm` interactively
+    And I pipe in the file "foo.scm"
+    Then the exit status should not be 0
+    And the stderr should contain "bar.scm:1:5"
@@ -1,7 +1,9 @@
 mod comment;
 mod expression;
 mod hash_directive;
+mod owned_expression;
 
 pub use comment::*;
 pub use expression::*;
 pub use hash_directive::*;
+pub use owned_expression::*;
@@ -1,8 +1,10 @@
 use crate::position::Position;
+use core::fmt::{self, Display, Formatter};
 use std::alloc::Allocator;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Expression<'a, A: Allocator> {
+    DelimitedString(&'a str, &'a str, &'a str, Position),
     List(&'a str, &'a str, Vec<Expression<'a, A>, A>, Position),
     Quote(&'a str, Box<Expression<'a, A>, A>, Position),
     QuotedSymbol(&'a str, Position),
@@ -13,6 +15,7 @@ pub enum Expression<'a, A: Allocator> {
 impl<A: Allocator> Expression<'_, A> {
     pub fn position(&self) -> &Position {
         match self {
+            Self::DelimitedString(_, _, _, position) => position,
             Self::List(_, _, _, position) => position,
             Self::Quote(_, _, position) => position,
             Self::QuotedSymbol(_, position) => position,
@@ -20,12 +23,116 @@ impl<A: Allocator> Expression<'_, A> {
             Self::Symbol(_, position) => position,
         }
     }
+
+    /// Returns `true` if expressions are equal ignoring their positions.
+    pub fn is_equivalent<B: Allocator>(&self, other: &Expression<B>) -> bool {
+        match (self, other) {
+            (
+                Self::DelimitedString(left, right, one, _),
+                Expression::DelimitedString(other_left, other_right, other, _),
+            ) => left == other_left && right == other_right && one == other,
+            (
+                Self::List(left, right, one, _),
+                Expression::List(other_left, other_right, other, _),
+            ) => {
+                left == other_left
+                    && right == other_right
+                    && one.len() == other.len()
+                    && one
+                        .iter()
+                        .zip(other.iter())
+                        .all(|(one, other)| one.is_equivalent(other))
+            }
+            (Self::Quote(sign, one, _), Expression::Quote(other_sign, other, _)) => {
+                sign == other_sign && one.is_equivalent(other)
+            }
+            (Self::QuotedSymbol(one, _), Expression::QuotedSymbol(other, _))
+            | (Self::String(one, _), Expression::String(other, _))
+            | (Self::Symbol(one, _), Expression::Symbol(other, _)) => one == other,
+            _ => false,
+        }
+    }
+
+    /// Visits an expression and its descendants in pre-order.
+    pub fn visit(&self, visitor: &mut impl FnMut(&Self)) {
+        visitor(self);
+
+        match self {
+            Self::List(_, _, expressions, _) => {
+                for expression in expressions {
+                    expression.visit(visitor);
+                }
+            }
+            Self::Quote(_, expression, _) => expression.visit(visitor),
+            Self::DelimitedString(..)
+            | Self::QuotedSymbol(..)
+            | Self::String(..)
+            | Self::Symbol(..) => {}
+        }
+    }
+
+    /// Visits an expression and its descendants mutably in post-order.
+    ///
+    /// A visitor can replace expressions in place after their children are
+    /// visited.
+    pub fn visit_mut(&mut self, visitor: &mut impl FnMut(&mut Self)) {
+        match self {
+            Self::List(_, _, expressions, _) => {
+                for expression in expressions.iter_mut() {
+                    expression.visit_mut(visitor);
+                }
+            }
+            Self::Quote(_, expression, _) => expression.visit_mut(visitor),
+            Self::DelimitedString(..)
+            | Self::QuotedSymbol(..)
+            | Self::String(..)
+            | Self::Symbol(..) => {}
+        }
+
+        visitor(self);
+    }
+}
+
+impl<A: Allocator> Display for Expression<'_, A> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DelimitedString(left, right, string, _) => {
+                write!(formatter, "{}{}{}", left, string, right)
+            }
+            Self::List(left, right, expressions, _) => {
+                write!(formatter, "{}", left)?;
+
+                for (index, expression) in expressions.iter().enumerate() {
+                    if index > 0 {
+                        write!(formatter, " ")?;
+                    }
+
+                    write!(formatter, "{}", expression)?;
+                }
+
+                write!(formatter, "{}", right)
+            }
+            Self::Quote(sign, expression, _) => write!(formatter, "{}{}", sign, expression),
+            Self::QuotedSymbol(symbol, _) => write!(formatter, "|{}|", symbol),
+            Self::String(string, _) => write!(formatter, "\"{}\"", string),
+            Self::Symbol(name, _) => write!(formatter, "{}", name),
+        }
+    }
 }
 
 // TODO Why do we need to do this manually?
 impl<A: Allocator> PartialEq for Expression<'_, A> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            (
+                Self::DelimitedString(left, right, one, position),
+                Self::DelimitedString(other_left, other_right, other, other_position),
+            ) => {
+                left == other_left
+                    && right == other_right
+                    && one == other
+                    && position == other_position
+            }
             (
                 Self::List(left, right, one, position),
                 Self::List(other_left, other_right, other, other_position),
@@ -66,4 +173,237 @@ mod tests {
             Expression::<Global>::Symbol("foo", Position::new(0, 0))
         );
     }
+
+    mod equivalent {
+        use super::*;
+
+        #[test]
+        fn compare_symbols_at_different_positions() {
+            assert!(Expression::<Global>::Symbol("foo", Position::new(0, 3))
+                .is_equivalent(&Expression::<Global>::Symbol("foo", Position::new(4, 7))));
+        }
+
+        #[test]
+        fn compare_different_symbols() {
+            assert!(!Expression::<Global>::Symbol("foo", Position::new(0, 3))
+                .is_equivalent(&Expression::<Global>::Symbol("bar", Position::new(0, 3))));
+        }
+
+        #[test]
+        fn compare_different_kinds() {
+            assert!(!Expression::<Global>::Symbol("foo", Position::new(0, 3))
+                .is_equivalent(&Expression::<Global>::String("foo", Position::new(0, 5))));
+        }
+
+        #[test]
+        fn compare_lists() {
+            let list =
+                |expressions, position| Expression::<Global>::List("(", ")", expressions, position);
+
+            assert!(list(
+                vec![Expression::Symbol("foo", Position::new(1, 4))],
+                Position::new(0, 5)
+            )
+            .is_equivalent(&list(
+                vec![Expression::Symbol("foo", Position::new(2, 5))],
+                Position::new(0, 7)
+            )));
+            assert!(!list(
+                vec![Expression::Symbol("foo", Position::new(1, 4))],
+                Position::new(0, 5)
+            )
+            .is_equivalent(&list(vec![], Position::new(0, 2))));
+        }
+
+        #[test]
+        fn compare_quotes() {
+            assert!(Expression::<Global>::Quote(
+                "'",
+                Expression::Symbol("foo", Position::new(1, 4)).into(),
+                Position::new(0, 4)
+            )
+            .is_equivalent(&Expression::<Global>::Quote(
+                "'",
+                Expression::Symbol("foo", Position::new(3, 6)).into(),
+                Position::new(2, 6)
+            )));
+            assert!(!Expression::<Global>::Quote(
+                "'",
+                Expression::Symbol("foo", Position::new(1, 4)).into(),
+                Position::new(0, 4)
+            )
+            .is_equivalent(&Expression::<Global>::Quote(
+                "`",
+                Expression::Symbol("foo", Position::new(1, 4)).into(),
+                Position::new(0, 4)
+            )));
+        }
+    }
+
+    mod visit {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn nested_expression() -> Expression<'static, Global> {
+            Expression::List(
+                "(",
+                ")",
+                vec![
+                    Expression::Symbol("foo", Position::new(1, 4)),
+                    Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("bar", Position::new(6, 9)),
+                            Expression::String("baz", Position::new(10, 15)),
+                        ],
+                        Position::new(5, 16),
+                    ),
+                    Expression::Quote(
+                        "'",
+                        Expression::Symbol("foo", Position::new(18, 21)).into(),
+                        Position::new(17, 21),
+                    ),
+                ],
+                Position::new(0, 22),
+            )
+        }
+
+        #[test]
+        fn count_symbols() {
+            let mut count = 0;
+
+            nested_expression().visit(&mut |expression| {
+                if let Expression::Symbol(..) = expression {
+                    count += 1;
+                }
+            });
+
+            assert_eq!(count, 3);
+        }
+
+        #[test]
+        fn visit_in_pre_order() {
+            let mut names = vec![];
+
+            nested_expression().visit(&mut |expression| {
+                names.push(match expression {
+                    Expression::DelimitedString(..) => "string",
+                    Expression::List(..) => "list",
+                    Expression::Quote(..) => "quote",
+                    Expression::QuotedSymbol(symbol, _)
+                    | Expression::String(symbol, _)
+                    | Expression::Symbol(symbol, _) => symbol,
+                })
+            });
+
+            assert_eq!(names, ["list", "foo", "list", "bar", "baz", "quote", "foo"]);
+        }
+
+        #[test]
+        fn rename_symbols() {
+            let mut expression = nested_expression();
+
+            expression.visit_mut(&mut |expression| {
+                if let Expression::Symbol(name, _) = expression {
+                    if *name == "foo" {
+                        *name = "qux";
+                    }
+                }
+            });
+
+            assert_eq!(expression.to_string(), "(qux (bar \"baz\") 'qux)");
+        }
+
+        #[test]
+        fn replace_children_before_parents() {
+            let mut expression = nested_expression();
+            let mut lengths = vec![];
+
+            expression.visit_mut(&mut |expression| {
+                if let Expression::List(_, _, expressions, _) = expression {
+                    lengths.push(expressions.len());
+                    expressions.clear();
+                }
+            });
+
+            assert_eq!(lengths, [2, 3]);
+            assert_eq!(expression.to_string(), "()");
+        }
+    }
+
+    mod display {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn display_symbol() {
+            assert_eq!(
+                Expression::<Global>::Symbol("foo", Position::new(0, 0)).to_string(),
+                "foo"
+            );
+        }
+
+        #[test]
+        fn display_quoted_symbol() {
+            assert_eq!(
+                Expression::<Global>::QuotedSymbol("foo bar", Position::new(0, 0)).to_string(),
+                "|foo bar|"
+            );
+        }
+
+        #[test]
+        fn display_string() {
+            assert_eq!(
+                Expression::<Global>::String("foo\\n", Position::new(0, 0)).to_string(),
+                "\"foo\\n\""
+            );
+        }
+
+        #[test]
+        fn display_quote() {
+            assert_eq!(
+                Expression::<Global>::Quote(
+                    ",@",
+                    Expression::Symbol("foo", Position::new(0, 0)).into(),
+                    Position::new(0, 0)
+                )
+                .to_string(),
+                ",@foo"
+            );
+        }
+
+        #[test]
+        fn display_empty_list() {
+            assert_eq!(
+                Expression::<Global>::List("(", ")", vec![], Position::new(0, 0)).to_string(),
+                "()"
+            );
+        }
+
+        #[test]
+        fn display_list() {
+            assert_eq!(
+                Expression::<Global>::List(
+                    "[",
+                    "]",
+                    vec![
+                        Expression::Symbol("foo", Position::new(0, 0)),
+                        Expression::List(
+                            "{",
+                            "}",
+                            vec![
+                                Expression::Symbol("bar", Position::new(0, 0)),
+                                Expression::String("baz", Position::new(0, 0)),
+                            ],
+                            Position::new(0, 0)
+                        ),
+                    ],
+                    Position::new(0, 0)
+                )
+                .to_string(),
+                "[foo {bar \"baz\"}]"
+            );
+        }
+    }
 }
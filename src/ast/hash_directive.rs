@@ -14,4 +14,8 @@ impl<'a> HashDirective<'a> {
     pub fn value(&self) -> &'a str {
         self.value
     }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
 }
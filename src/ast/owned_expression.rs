@@ -0,0 +1,178 @@
+use super::Expression;
+use crate::position::Position;
+use std::alloc::Allocator;
+
+/// An expression owning its contents.
+///
+/// Unlike [`Expression`], it does not borrow a source or an allocator and can
+/// be kept after they are dropped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OwnedExpression {
+    DelimitedString(String, String, String, Position),
+    List(String, String, Vec<OwnedExpression>, Position),
+    Quote(String, Box<OwnedExpression>, Position),
+    QuotedSymbol(String, Position),
+    String(String, Position),
+    Symbol(String, Position),
+}
+
+impl OwnedExpression {
+    pub fn position(&self) -> &Position {
+        match self {
+            Self::DelimitedString(_, _, _, position) => position,
+            Self::List(_, _, _, position) => position,
+            Self::Quote(_, _, position) => position,
+            Self::QuotedSymbol(_, position) => position,
+            Self::String(_, position) => position,
+            Self::Symbol(_, position) => position,
+        }
+    }
+
+    /// Converts an owned expression into a borrowed one in an allocator.
+    pub fn to_expression<A: Allocator + Clone>(&self, allocator: A) -> Expression<'_, A> {
+        match self {
+            Self::DelimitedString(left, right, string, position) => {
+                Expression::DelimitedString(left, right, string, position.clone())
+            }
+            Self::List(left, right, expressions, position) => {
+                let mut vector = Vec::with_capacity_in(expressions.len(), allocator.clone());
+
+                vector.extend(
+                    expressions
+                        .iter()
+                        .map(|expression| expression.to_expression(allocator.clone())),
+                );
+
+                Expression::List(left, right, vector, position.clone())
+            }
+            Self::Quote(sign, expression, position) => Expression::Quote(
+                sign,
+                Box::new_in(expression.to_expression(allocator.clone()), allocator),
+                position.clone(),
+            ),
+            Self::QuotedSymbol(symbol, position) => {
+                Expression::QuotedSymbol(symbol, position.clone())
+            }
+            Self::String(string, position) => Expression::String(string, position.clone()),
+            Self::Symbol(name, position) => Expression::Symbol(name, position.clone()),
+        }
+    }
+}
+
+impl<A: Allocator> From<&Expression<'_, A>> for OwnedExpression {
+    fn from(expression: &Expression<'_, A>) -> Self {
+        match expression {
+            Expression::DelimitedString(left, right, string, position) => Self::DelimitedString(
+                (*left).into(),
+                (*right).into(),
+                (*string).into(),
+                position.clone(),
+            ),
+            Expression::List(left, right, expressions, position) => Self::List(
+                (*left).into(),
+                (*right).into(),
+                expressions.iter().map(Self::from).collect(),
+                position.clone(),
+            ),
+            Expression::Quote(sign, expression, position) => Self::Quote(
+                (*sign).into(),
+                Self::from(&**expression).into(),
+                position.clone(),
+            ),
+            Expression::QuotedSymbol(symbol, position) => {
+                Self::QuotedSymbol((*symbol).into(), position.clone())
+            }
+            Expression::String(string, position) => {
+                Self::String((*string).into(), position.clone())
+            }
+            Expression::Symbol(name, position) => Self::Symbol((*name).into(), position.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::format,
+        options::Dialect,
+        parse::{parse, parse_comments, parse_hash_directives},
+        position_map::PositionMap,
+    };
+    use bumpalo::Bump;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use std::alloc::Global;
+
+    const SOURCE: &str = indoc!(
+        "
+        (define (foo x)
+          `(bar ,@x))
+
+        #|baz|# \"qux\"
+        |quux|
+        "
+    );
+
+    fn parse_owned(source: &str) -> Vec<OwnedExpression> {
+        let allocator = Bump::new();
+        let expressions = parse(source, Dialect::Scheme, &allocator)
+            .unwrap()
+            .iter()
+            .map(OwnedExpression::from)
+            .collect();
+
+        expressions
+    }
+
+    #[test]
+    fn convert_expression() {
+        assert_eq!(
+            OwnedExpression::from(&Expression::<Global>::Quote(
+                "'",
+                Box::new(Expression::Symbol("foo", Position::new(1, 4))),
+                Position::new(0, 4),
+            )),
+            OwnedExpression::Quote(
+                "'".into(),
+                OwnedExpression::Symbol("foo".into(), Position::new(1, 4)).into(),
+                Position::new(0, 4),
+            )
+        );
+    }
+
+    #[test]
+    fn convert_expressions_back() {
+        let expressions = parse(SOURCE, Dialect::Scheme, Global).unwrap();
+
+        assert_eq!(
+            parse_owned(SOURCE)
+                .iter()
+                .map(|expression| expression.to_expression(Global))
+                .collect::<Vec<_>>(),
+            expressions.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn format_owned_expressions() {
+        let expressions = parse_owned(SOURCE);
+        let expressions = expressions
+            .iter()
+            .map(|expression| expression.to_expression(Global))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            format(
+                &expressions,
+                &parse_comments(SOURCE, &Default::default(), Global).unwrap(),
+                &parse_hash_directives(SOURCE, Dialect::Scheme, Global).unwrap(),
+                &PositionMap::new(SOURCE),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            SOURCE
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{
+    ast::Expression,
+    format::{QUOTE_SIGNS, UNQUOTE_SIGNS},
+    format_source,
+    options::Options,
+    parse::{parse_comments, parse_hash_directives, parse_with_options, ParseError},
+    position::Position,
+    position_map::PositionMap,
+    source::{measure_width, normalize_line_endings},
+    FormatError,
+};
+use bumpalo::Bump;
+use std::alloc::Allocator;
+
+/// A result of checking a source.
+#[derive(Debug, PartialEq)]
+pub enum CheckResult {
+    /// A source is formatted already.
+    Formatted,
+    /// A source needs to be formatted into a given string.
+    Unformatted(String),
+    /// A source fails to be formatted.
+    Error(FormatError),
+}
+
+/// Checks sources given with their virtual paths without touching a file
+/// system.
+///
+/// It returns results in the same order as given sources.
+pub fn check_sources<'a>(
+    sources: &[(&'a str, &str)],
+    options: &Options,
+) -> Vec<(&'a str, CheckResult)> {
+    sources
+        .iter()
+        .map(|(path, source)| (*path, check_source(source, options)))
+        .collect()
+}
+
+/// Checks if two sources are equivalent ignoring insignificant whitespace.
+///
+/// Expressions, comments, and hash directives are compared except whitespace
+/// between them.
+pub fn is_equivalent(one: &str, other: &str, options: &Options) -> Result<bool, ParseError> {
+    Ok(normalize_source(one, options)? == normalize_source(other, options)?)
+}
+
+/// Finds lists headed by given symbols in a source.
+///
+/// Lists in quoted data and datum comments are ignored except ones unquoted in
+/// the data. It returns positions of the head symbols in source order.
+pub fn find_head_symbols(
+    source: &str,
+    names: &[&str],
+    options: &Options,
+) -> Result<Vec<Position>, ParseError> {
+    let allocator = Bump::new();
+    let mut positions = vec![];
+
+    for expression in parse_with_options(source, options, &allocator)? {
+        collect_head_symbols(&expression, names, false, &mut positions);
+    }
+
+    Ok(positions)
+}
+
+fn collect_head_symbols<A: Allocator>(
+    expression: &Expression<A>,
+    names: &[&str],
+    data: bool,
+    positions: &mut Vec<Position>,
+) {
+    match expression {
+        Expression::List(_, _, expressions, _) => {
+            if let (false, Some(Expression::Symbol(name, position))) = (data, expressions.first()) {
+                if names.contains(name) {
+                    positions.push(position.clone());
+                }
+            }
+
+            for expression in expressions {
+                collect_head_symbols(expression, names, data, positions);
+            }
+        }
+        Expression::Quote("#;", _, _) => {}
+        Expression::Quote(sign, expression, _) => collect_head_symbols(
+            expression,
+            names,
+            QUOTE_SIGNS.contains(sign) || !UNQUOTE_SIGNS.contains(sign) && data,
+            positions,
+        ),
+        Expression::DelimitedString(..)
+        | Expression::QuotedSymbol(..)
+        | Expression::String(..)
+        | Expression::Symbol(..) => {}
+    }
+}
+
+/// Finds lines where string literals alone are longer than a maximum width in
+/// columns.
+///
+/// Such lines are never shortened by formatting as string literals are not
+/// split. It returns their line indexes in source order.
+pub fn find_long_strings(
+    source: &str,
+    width: usize,
+    options: &Options,
+) -> Result<Vec<usize>, ParseError> {
+    let allocator = Bump::new();
+    let position_map = PositionMap::new(source);
+    let mut lines = vec![];
+
+    for expression in parse_with_options(source, options, &allocator)? {
+        expression.visit(&mut |expression| {
+            if let Expression::String(_, position)
+            | Expression::DelimitedString(_, _, _, position) = expression
+            {
+                let Some(start) = position_map.line_index(position.start()) else {
+                    return;
+                };
+
+                for (index, line) in source[position.start()..position.end()]
+                    .split('\n')
+                    .enumerate()
+                {
+                    if measure_width(line, options.tab_width()) > width {
+                        lines.push(start + index);
+                    }
+                }
+            }
+        });
+    }
+
+    lines.dedup();
+
+    Ok(lines)
+}
+
+/// Finds lines indented with tabs in a source.
+///
+/// Tabs in indentation of lines inside string literals are ignored as they
+/// are part of string contents. It returns line indexes in source order.
+pub fn find_tab_indented_lines(source: &str, options: &Options) -> Result<Vec<usize>, ParseError> {
+    let allocator = Bump::new();
+    let position_map = PositionMap::new(source);
+    let mut string_lines = vec![];
+
+    for expression in parse_with_options(source, options, &allocator)? {
+        expression.visit(&mut |expression| {
+            if let Expression::String(_, position)
+            | Expression::DelimitedString(_, _, _, position) = expression
+            {
+                if let (Some(start), Some(end)) = (
+                    position_map.line_index(position.start()),
+                    position_map.line_index(position.end().saturating_sub(1)),
+                ) {
+                    string_lines.extend(start + 1..=end);
+                }
+            }
+        });
+    }
+
+    Ok(source
+        .lines()
+        .enumerate()
+        .filter(|(index, line)| {
+            line.chars()
+                .take_while(|character| character.is_whitespace())
+                .any(|character| character == '\t')
+                && !string_lines.contains(index)
+        })
+        .map(|(index, _)| index)
+        .collect())
+}
+
+fn normalize_source(source: &str, options: &Options) -> Result<Vec<String>, ParseError> {
+    let allocator = Bump::new();
+    let strings = parse_with_options(source, options, &allocator)?
+        .iter()
+        .map(ToString::to_string)
+        .chain(
+            parse_comments(source, options, &allocator)?
+                .iter()
+                .map(|comment| comment.content().trim().into()),
+        )
+        .chain(
+            parse_hash_directives(source, options.dialect(), &allocator)?
+                .iter()
+                .map(|directive| directive.value().trim().into()),
+        )
+        .collect();
+
+    Ok(strings)
+}
+
+fn check_source(source: &str, options: &Options) -> CheckResult {
+    let normalized_source = normalize_line_endings(source);
+
+    match format_source(normalized_source.as_deref().unwrap_or(source), options) {
+        Ok(formatted) if formatted == source => CheckResult::Formatted,
+        Ok(formatted) => CheckResult::Unformatted(formatted),
+        Err(error) => CheckResult::Error(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    mod equivalent {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn compare_whitespace_differences() {
+            assert_eq!(
+                is_equivalent(
+                    "(foo   bar\n\n\n  baz) ;qux  \n",
+                    "(foo bar\n  baz) ;qux\n",
+                    &Options::new()
+                ),
+                Ok(true)
+            );
+        }
+
+        #[test]
+        fn compare_expression_differences() {
+            assert_eq!(
+                is_equivalent("(foo (bar baz))", "(foo (bar) baz)", &Options::new()),
+                Ok(false)
+            );
+        }
+
+        #[test]
+        fn compare_string_differences() {
+            assert_eq!(
+                is_equivalent("\"foo  bar\"", "\"foo bar\"", &Options::new()),
+                Ok(false)
+            );
+        }
+
+        #[test]
+        fn compare_comment_differences() {
+            assert_eq!(
+                is_equivalent("foo ;bar\n", "foo ;baz\n", &Options::new()),
+                Ok(false)
+            );
+        }
+
+        #[test]
+        fn fail_to_compare_invalid_source() {
+            assert!(is_equivalent("(foo", "(foo)", &Options::new()).is_err());
+        }
+    }
+
+    mod head_symbols {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_nothing() {
+            assert_eq!(
+                find_head_symbols("(foo bar)", &["set!"], &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn find_symbols_in_nested_lists() {
+            assert_eq!(
+                find_head_symbols(
+                    "(define (foo)\n  (let ((x 1))\n    (set! x 2)))\n(set! y 3)",
+                    &["set!"],
+                    &Options::new()
+                ),
+                Ok(vec![Position::new(34, 38), Position::new(47, 51)])
+            );
+        }
+
+        #[test]
+        fn find_multiple_symbols() {
+            assert_eq!(
+                find_head_symbols("(foo (bar) (baz))", &["bar", "baz"], &Options::new()),
+                Ok(vec![Position::new(6, 9), Position::new(12, 15)])
+            );
+        }
+
+        #[test]
+        fn ignore_symbols_not_at_heads() {
+            assert_eq!(
+                find_head_symbols("(foo set! (bar set!))", &["set!"], &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn ignore_symbols_in_datum_comments() {
+            assert_eq!(
+                find_head_symbols("#;(eval x)\n(eval y)", &["eval"], &Options::new()),
+                Ok(vec![Position::new(12, 16)])
+            );
+        }
+
+        #[test]
+        fn ignore_symbols_in_quoted_data() {
+            assert_eq!(
+                find_head_symbols(
+                    "'(eval y)\n`(eval z)\n'((eval x))",
+                    &["eval"],
+                    &Options::new()
+                ),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn find_symbols_in_unquoted_data() {
+            assert_eq!(
+                find_head_symbols("`(foo ,(eval x) ,@(eval y))", &["eval"], &Options::new()),
+                Ok(vec![Position::new(8, 12), Position::new(19, 23)])
+            );
+        }
+
+        #[test]
+        fn fail_to_find_symbols_in_invalid_source() {
+            assert!(find_head_symbols("(foo", &["foo"], &Options::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn check_nothing() {
+        assert_eq!(check_sources(&[], &Options::new()), vec![]);
+    }
+
+    mod long_strings {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_nothing() {
+            assert_eq!(
+                find_long_strings("(foo \"bar\")", 5, &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn find_long_string_in_list() {
+            assert_eq!(
+                find_long_strings("(foo\n  \"barbaz\")", 7, &Options::new()),
+                Ok(vec![1])
+            );
+        }
+
+        #[test]
+        fn ignore_long_line_of_short_strings() {
+            assert_eq!(
+                find_long_strings("(foo \"bar\" \"baz\")", 5, &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn find_long_lines_in_multi_line_string() {
+            assert_eq!(
+                find_long_strings("\"foo\nbarbazqux\nquux\nfoobarbaz\"", 6, &Options::new()),
+                Ok(vec![1, 3])
+            );
+        }
+
+        #[test]
+        fn find_long_strings_in_same_line() {
+            assert_eq!(
+                find_long_strings("(\"foobar\" \"bazqux\")", 5, &Options::new()),
+                Ok(vec![0])
+            );
+        }
+    }
+
+    mod tab_indented_lines {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_nothing() {
+            assert_eq!(
+                find_tab_indented_lines("(foo\n  bar)", &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn find_tab_indented_lines_in_list() {
+            assert_eq!(
+                find_tab_indented_lines("(foo\n\tbar\n \tbaz)", &Options::new()),
+                Ok(vec![1, 2])
+            );
+        }
+
+        #[test]
+        fn ignore_tabs_after_indentation() {
+            assert_eq!(
+                find_tab_indented_lines("(foo\tbar\n  baz\t)", &Options::new()),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn ignore_tabs_in_strings() {
+            assert_eq!(
+                find_tab_indented_lines("(foo \"bar\n\tbaz\")\n\tqux", &Options::new()),
+                Ok(vec![2])
+            );
+        }
+
+        #[test]
+        fn find_tab_indented_line_after_string() {
+            assert_eq!(
+                find_tab_indented_lines("(foo \"bar\"\n\tbaz)", &Options::new()),
+                Ok(vec![1])
+            );
+        }
+
+        #[test]
+        fn fail_to_find_lines_in_invalid_source() {
+            assert!(find_tab_indented_lines("(foo", &Options::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn check_formatted_and_unformatted_sources() {
+        assert_eq!(
+            check_sources(
+                &[("foo.scm", "(foo bar)\n"), ("bar.scm", "(foo  bar)")],
+                &Options::new()
+            ),
+            vec![
+                ("foo.scm", CheckResult::Formatted),
+                ("bar.scm", CheckResult::Unformatted("(foo bar)\n".into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn check_invalid_source() {
+        let results = check_sources(&[("foo.scm", "(foo")], &Options::new());
+
+        let [(path, CheckResult::Error(FormatError::Parse(error)))] = &results[..] else {
+            panic!("unexpected results: {:?}", results);
+        };
+
+        assert_eq!(*path, "foo.scm");
+        assert_eq!(error.offset(), 3);
+    }
+
+    #[test]
+    fn check_whitespace_only_sources() {
+        assert_eq!(
+            check_sources(
+                &[("foo.scm", "\n"), ("bar.scm", ""), ("baz.scm", "  \n\n")],
+                &Options::new()
+            ),
+            vec![
+                ("foo.scm", CheckResult::Formatted),
+                ("bar.scm", CheckResult::Unformatted("\n".into())),
+                ("baz.scm", CheckResult::Unformatted("\n".into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn check_source_with_carriage_returns() {
+        assert_eq!(
+            check_sources(&[("foo.scm", "foo\rbar\r")], &Options::new()),
+            vec![("foo.scm", CheckResult::Unformatted("foo\nbar\n".into()))]
+        );
+    }
+}
@@ -1,23 +1,45 @@
-use crate::{ast::Comment, position::Position, position_map::PositionMap};
+use crate::{ast::Comment, options::Options, position::Position, position_map::PositionMap};
 use mfmt::Builder;
-use std::{alloc::Allocator, collections::VecDeque};
+use std::{
+    alloc::Allocator,
+    cell::Cell,
+    collections::{HashMap, VecDeque},
+};
 
 pub struct Context<'a, A: Allocator + Clone> {
+    // Comments in source order. They are drained only from the front so that
+    // ones on the same line are never reordered.
     comments: VecDeque<&'a Comment<'a>>,
     position_map: &'a PositionMap,
+    // A line index of a last query as most queries are sequential.
+    line_index: Cell<usize>,
+    options: &'a Options,
     builder: Builder<A>,
+    // Whether a next list is prefixed by quote signs indenting it.
+    prefixed: bool,
+    // Estimated columns of expressions and widths following them on the same
+    // lines keyed by their start offsets for reflow.
+    layouts: HashMap<usize, (usize, usize)>,
+    // Widths of expressions on single lines keyed by their start offsets.
+    widths: HashMap<usize, Option<usize>>,
 }
 
 impl<'a, A: Allocator + Clone> Context<'a, A> {
     pub fn new(
         comments: &'a [Comment<'a>],
         position_map: &'a PositionMap,
+        options: &'a Options,
         builder: Builder<A>,
     ) -> Self {
         Self {
             comments: comments.iter().collect(),
             position_map,
+            line_index: Cell::new(0),
+            options,
             builder,
+            prefixed: false,
+            layouts: Default::default(),
+            widths: Default::default(),
         }
     }
 
@@ -25,10 +47,49 @@ impl<'a, A: Allocator + Clone> Context<'a, A> {
         self.position_map
     }
 
+    pub fn line_index(&self, offset: usize) -> usize {
+        let line_index = self
+            .position_map
+            .line_index_near(offset, self.line_index.get())
+            .expect("valid offset");
+
+        self.line_index.set(line_index);
+
+        line_index
+    }
+
+    pub fn options(&self) -> &'a Options {
+        self.options
+    }
+
     pub fn builder(&self) -> &Builder<A> {
         &self.builder
     }
 
+    pub fn prefixed(&self) -> bool {
+        self.prefixed
+    }
+
+    pub fn set_prefixed(&mut self, prefixed: bool) {
+        self.prefixed = prefixed;
+    }
+
+    pub fn layout(&self, start: usize) -> (usize, usize) {
+        self.layouts.get(&start).copied().unwrap_or_default()
+    }
+
+    pub fn set_layout(&mut self, start: usize, column: usize, width: usize) {
+        self.layouts.insert(start, (column, width));
+    }
+
+    pub fn width(&self, start: usize) -> Option<Option<usize>> {
+        self.widths.get(&start).copied()
+    }
+
+    pub fn set_width(&mut self, start: usize, width: Option<usize>) {
+        self.widths.insert(start, width);
+    }
+
     pub fn drain_multi_line_comments(
         &mut self,
         line_index: usize,
@@ -37,7 +98,7 @@ impl<'a, A: Allocator + Clone> Context<'a, A> {
             ..self
                 .comments
                 .iter()
-                .position(|comment| self.line_index(comment.position()) >= line_index)
+                .position(|comment| self.line_index(comment.position().start()) >= line_index)
                 .unwrap_or(self.comments.len()),
         )
     }
@@ -52,7 +113,7 @@ impl<'a, A: Allocator + Clone> Context<'a, A> {
                 .iter()
                 .position(|comment| {
                     !matches!(comment, Comment::Line(_))
-                        || self.line_index(comment.position()) > line_index
+                        || self.line_index(comment.position().start()) > line_index
                 })
                 .unwrap_or(self.comments.len()),
         )
@@ -71,21 +132,56 @@ impl<'a, A: Allocator + Clone> Context<'a, A> {
         )
     }
 
-    pub fn peek_comments(&self, line_index: usize) -> impl Iterator<Item = &Comment> {
+    // Drains inline comments before a position leaving a given number of last
+    // ones.
+    pub fn drain_excess_inline_comments(
+        &mut self,
+        position: &Position,
+        count: usize,
+    ) -> impl Iterator<Item = &'a Comment<'a>> + '_ {
+        self.comments.drain(
+            ..self
+                .comments
+                .iter()
+                .position(|comment| comment.position().end() > position.start())
+                .unwrap_or(self.comments.len())
+                .saturating_sub(count),
+        )
+    }
+
+    pub fn count_comments(&self, position: &Position) -> usize {
+        self.comments
+            .iter()
+            .filter(|comment| {
+                comment.position().start() >= position.start()
+                    && comment.position().start() < position.end()
+            })
+            .count()
+    }
+
+    pub fn has_comments(&self, position: &Position) -> bool {
+        self.comments.iter().any(|comment| {
+            comment.position().start() >= position.start()
+                && comment.position().start() < position.end()
+        })
+    }
+
+    pub fn has_line_comment(&self, line_index: usize) -> bool {
+        self.comments.iter().any(|comment| {
+            matches!(comment, Comment::Line(_))
+                && self.line_index(comment.position().start()) == line_index
+        })
+    }
+
+    pub fn peek_comments(&self, line_index: usize) -> impl Iterator<Item = &Comment<'_>> {
         self.comments
             .range(
                 ..self
                     .comments
                     .iter()
-                    .position(|comment| self.line_index(comment.position()) >= line_index)
+                    .position(|comment| self.line_index(comment.position().start()) >= line_index)
                     .unwrap_or(self.comments.len()),
             )
             .copied()
     }
-
-    fn line_index(&self, position: &Position) -> usize {
-        self.position_map()
-            .line_index(position.start())
-            .expect("valid offset")
-    }
 }
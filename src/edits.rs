@@ -0,0 +1,48 @@
+use crate::json::escape;
+use schemat::TextEdit;
+
+/// Renders edits as JSON.
+///
+/// Each edit replaces a byte range from `start` to `end` in a source with
+/// `text`.
+pub fn render_text_edits(edits: &[TextEdit]) -> String {
+    format!(
+        r#"{{"edits":[{}]}}"#,
+        edits
+            .iter()
+            .map(|edit| format!(
+                r#"{{"start":{},"end":{},"text":"{}"}}"#,
+                edit.position().start(),
+                edit.position().end(),
+                escape(edit.text())
+            ))
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use schemat::Position;
+
+    #[test]
+    fn render_no_edit() {
+        assert_eq!(render_text_edits(&[]), r#"{"edits":[]}"#);
+    }
+
+    #[test]
+    fn render_edits() {
+        assert_eq!(
+            render_text_edits(&[
+                TextEdit::new(Position::new(4, 9), "bar\n"),
+                TextEdit::new(Position::new(12, 12), "\"baz\"\n"),
+            ]),
+            concat!(
+                r#"{"edits":[{"start":4,"end":9,"text":"bar\n"},"#,
+                r#"{"start":12,"end":12,"text":"\"baz\"\n"}]}"#,
+            )
+        );
+    }
+}
@@ -4,15 +4,93 @@ use core::{
     fmt::{Display, Formatter},
 };
 use glob::{GlobError, PatternError};
-use std::io;
+use schemat::{ParseError, Position, PositionMap};
+use std::{io, path::PathBuf};
 
 #[derive(Debug)]
 pub enum ApplicationError {
+    FileTooLarge {
+        path: PathBuf,
+        size: u64,
+        max_size: u64,
+    },
+    ForbiddenSymbols {
+        name: String,
+        source: String,
+        positions: Vec<Position>,
+        tab_width: usize,
+    },
     Format(fmt::Error),
     Glob(GlobError),
     Io(io::Error),
-    Parse(String),
-    Pattern(PatternError),
+    Mismatch(String),
+    Parse {
+        error: ParseError,
+        name: String,
+        line: String,
+        line_index: usize,
+        column_index: usize,
+    },
+    Pattern {
+        error: PatternError,
+        pattern: String,
+    },
+    PermissionDenied(PathBuf),
+    Unexpected {
+        name: String,
+        diff: String,
+    },
+}
+
+impl ApplicationError {
+    /// Creates a parse error keeping only a line where it occurs in a source.
+    pub fn parse(error: ParseError, name: &str, source: &str, tab_width: usize) -> Self {
+        let position_map = PositionMap::new(source);
+        let offset = error.offset();
+
+        Self::Parse {
+            name: name.into(),
+            line: source[position_map.line_range(offset).expect("valid offset")]
+                .trim_end()
+                .into(),
+            line_index: position_map.line_index(offset).expect("valid offset"),
+            column_index: position_map
+                .column_index(source, offset, tab_width)
+                .expect("valid offset"),
+            error,
+        }
+    }
+
+    /// Returns line and column indexes in a source where an error occurs.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        let (offset, source, tab_width) = match self {
+            Self::ForbiddenSymbols {
+                source,
+                positions,
+                tab_width,
+                ..
+            } => (positions.first()?.start(), source, tab_width),
+            Self::Parse {
+                line_index,
+                column_index,
+                ..
+            } => return Some((*line_index, *column_index)),
+            _ => return None,
+        };
+        let position_map = PositionMap::new(source);
+
+        Some((
+            position_map.line_index(offset)?,
+            position_map.column_index(source, offset, *tab_width)?,
+        ))
+    }
+}
+
+/// Returns `true` if an error is caused by a closed pipe of stdout.
+pub fn is_broken_pipe(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe)
 }
 
 impl Error for ApplicationError {}
@@ -20,11 +98,89 @@ impl Error for ApplicationError {}
 impl Display for ApplicationError {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            Self::FileTooLarge {
+                path,
+                size,
+                max_size,
+            } => write!(
+                formatter,
+                "{}: {} bytes exceed maximum file size of {} bytes",
+                path.display(),
+                size,
+                max_size
+            ),
+            Self::ForbiddenSymbols {
+                name,
+                source,
+                positions,
+                tab_width,
+            } => {
+                let position_map = PositionMap::new(source);
+
+                for (index, position) in positions.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(formatter)?;
+                    }
+
+                    write!(
+                        formatter,
+                        "forbidden symbol {}:{}:{}: {}",
+                        name,
+                        position_map
+                            .line_index(position.start())
+                            .ok_or(fmt::Error)?
+                            + 1,
+                        position_map
+                            .column_index(source, position.start(), *tab_width)
+                            .ok_or(fmt::Error)?
+                            + 1,
+                        &source[position.start()..position.end()]
+                    )?;
+                }
+
+                Ok(())
+            }
             Self::Format(error) => error.fmt(formatter),
             Self::Glob(error) => error.fmt(formatter),
             Self::Io(error) => error.fmt(formatter),
-            Self::Parse(error) => error.fmt(formatter),
-            Self::Pattern(error) => error.fmt(formatter),
+            Self::Mismatch(name) => {
+                write!(
+                    formatter,
+                    "formatted code differs from source code: {}",
+                    name
+                )
+            }
+            Self::Parse {
+                error,
+                name,
+                line,
+                line_index,
+                column_index,
+            } => write!(
+                formatter,
+                "{}{} {}:{}:{}: {}",
+                error.message(),
+                error
+                    .context()
+                    .map(|context| format!(" (expected {})", context))
+                    .unwrap_or_default(),
+                name,
+                line_index + 1,
+                column_index + 1,
+                line
+            ),
+            Self::Pattern { error, pattern } => {
+                write!(formatter, "{}: {}", pattern, error)
+            }
+            Self::PermissionDenied(path) => {
+                write!(formatter, "permission denied reading {}", path.display())
+            }
+            Self::Unexpected { name, diff } => write!(
+                formatter,
+                "formatted code differs from expected output: {}\n{}",
+                name,
+                diff.trim_end_matches('\n')
+            ),
         }
     }
 }
@@ -47,8 +203,168 @@ impl From<io::Error> for ApplicationError {
     }
 }
 
-impl From<PatternError> for ApplicationError {
-    fn from(error: PatternError) -> Self {
-        Self::Pattern(error)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use schemat::{format_source, FormatError, Options};
+
+    fn parse_error(source: &str) -> ParseError {
+        let Err(FormatError::Parse(error)) = format_source(source, &Options::new()) else {
+            unreachable!()
+        };
+
+        error
+    }
+
+    #[test]
+    fn display_parse_error() {
+        let source = "(foo";
+
+        assert_eq!(
+            ApplicationError::parse(parse_error(source), "foo.scm", source, 8).to_string(),
+            "failed to parse (expected list) foo.scm:1:4: (foo"
+        );
+    }
+
+    #[test]
+    fn keep_parse_error_structure() {
+        let source = "(foo";
+        let error = ApplicationError::parse(parse_error(source), "foo.scm", source, 8);
+
+        let ApplicationError::Parse { error, name, .. } = error else {
+            unreachable!()
+        };
+
+        assert_eq!(error.message(), "failed to parse");
+        assert_eq!(error.offset(), 3);
+        assert_eq!(name, "foo.scm");
+    }
+
+    #[test]
+    fn keep_only_parse_error_line() {
+        let source = "(foo\n  (bar\n";
+
+        let ApplicationError::Parse { line, .. } =
+            ApplicationError::parse(parse_error(source), "foo.scm", source, 8)
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(line, "  (bar");
+    }
+
+    #[test]
+    fn display_pattern_error() {
+        assert_eq!(
+            ApplicationError::Pattern {
+                error: glob::Pattern::new("[a").unwrap_err(),
+                pattern: "[a".into(),
+            }
+            .to_string(),
+            "[a: Pattern syntax error near position 0: invalid range pattern"
+        );
+    }
+
+    #[test]
+    fn locate_parse_error() {
+        let source = "(foo\n  (bar";
+
+        assert_eq!(
+            ApplicationError::parse(parse_error(source), "foo.scm", source, 8).location(),
+            Some((1, 5))
+        );
+    }
+
+    #[test]
+    fn display_forbidden_symbols() {
+        assert_eq!(
+            ApplicationError::ForbiddenSymbols {
+                name: "foo.scm".into(),
+                source: "(foo\n  (set! x 1)\n\t(set! y 2))".into(),
+                positions: vec![Position::new(8, 12), Position::new(20, 24)],
+                tab_width: 8,
+            }
+            .to_string(),
+            "forbidden symbol foo.scm:2:4: set!\nforbidden symbol foo.scm:3:10: set!"
+        );
+    }
+
+    #[test]
+    fn locate_forbidden_symbols() {
+        assert_eq!(
+            ApplicationError::ForbiddenSymbols {
+                name: "foo.scm".into(),
+                source: "(foo\n  (set! x 1))".into(),
+                positions: vec![Position::new(8, 12)],
+                tab_width: 8,
+            }
+            .location(),
+            Some((1, 3))
+        );
+    }
+
+    #[test]
+    fn display_file_too_large_error() {
+        assert_eq!(
+            ApplicationError::FileTooLarge {
+                path: "foo.scm".into(),
+                size: 42,
+                max_size: 8,
+            }
+            .to_string(),
+            "foo.scm: 42 bytes exceed maximum file size of 8 bytes"
+        );
+    }
+
+    #[test]
+    fn display_permission_denied_error() {
+        assert_eq!(
+            ApplicationError::PermissionDenied("foo.scm".into()).to_string(),
+            "permission denied reading foo.scm"
+        );
+    }
+
+    #[test]
+    fn display_mismatch_error() {
+        assert_eq!(
+            ApplicationError::Mismatch("foo.scm".into()).to_string(),
+            "formatted code differs from source code: foo.scm"
+        );
+    }
+
+    #[test]
+    fn display_unexpected_error() {
+        assert_eq!(
+            ApplicationError::Unexpected {
+                name: "foo.scm".into(),
+                diff: "@@ line 1 @@\n-foo\n+bar\n".into(),
+            }
+            .to_string(),
+            "formatted code differs from expected output: foo.scm\n@@ line 1 @@\n-foo\n+bar"
+        );
+    }
+
+    #[test]
+    fn check_broken_pipe() {
+        let error: Box<dyn Error> = io::Error::from(io::ErrorKind::BrokenPipe).into();
+
+        assert!(is_broken_pipe(&*error));
+    }
+
+    #[test]
+    fn check_other_errors() {
+        let error: Box<dyn Error> = io::Error::other("foo").into();
+
+        assert!(!is_broken_pipe(&*error));
+        assert!(!is_broken_pipe(&*Box::<dyn Error>::from("foo")));
+    }
+
+    #[test]
+    fn locate_io_error() {
+        assert_eq!(
+            ApplicationError::Io(io::Error::other("foo")).location(),
+            None
+        );
     }
 }
@@ -0,0 +1,802 @@
+use crate::{error::ApplicationError, git::changed_paths};
+use glob::{Pattern, PatternError};
+use schemat::Dialect;
+use std::{
+    collections::HashSet,
+    env::current_dir,
+    fs::{canonicalize, read_dir, File},
+    io,
+    os::unix::fs::MetadataExt,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+use tokio::{
+    fs::{metadata, read_to_string, remove_file, rename, set_permissions, write},
+    task::spawn_blocking,
+};
+
+const TEMPORARY_FILE_SUFFIX: &str = ".schemat.tmp";
+static TEMPORARY_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+const SOURCE_EXTENSIONS: &[&str] = &["el", "lisp", "rkt", "scm", "sld", "sls", "sps", "ss"];
+
+/// Reads paths of files matched by glob patterns.
+///
+/// Directories are searched recursively for source files. Symbolic links to
+/// directories in them are followed only if `follow_symlinks` is `true`.
+pub async fn read_paths(
+    paths: &[String],
+    ignore_patterns: &[String],
+    ignore_explicit: bool,
+    follow_symlinks: bool,
+    since: Option<&str>,
+) -> Result<impl Iterator<Item = PathBuf>, ApplicationError> {
+    let ignore_patterns = ignore_patterns
+        .iter()
+        .map(|pattern| Pattern::new(pattern).map_err(|error| pattern_error(error, pattern)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let changed_paths = if let Some(reference) = since {
+        Some(changed_paths(reference).await?)
+    } else {
+        None
+    };
+    let mut canonical_paths = HashSet::new();
+
+    Ok(paths
+        .iter()
+        .map(|path| {
+            Ok::<_, ApplicationError>(
+                expand_path(path, follow_symlinks)?
+                    .into_iter()
+                    .filter(|(path, explicit)| {
+                        *explicit && !ignore_explicit
+                            || !ignore_patterns.iter().any(|pattern| {
+                                pattern.matches_path(path)
+                                    || pattern.matches_path(&normalize_path(path))
+                            })
+                    })
+                    .map(|(path, _)| path)
+                    .filter(|path| {
+                        changed_paths.as_ref().is_none_or(|paths| {
+                            canonicalize(path).is_ok_and(|path| paths.contains(&path))
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        // Skip files matched by multiple patterns.
+        .filter(move |path| canonical_paths.insert(canonicalize(path).unwrap_or(path.clone()))))
+}
+
+fn expand_path(
+    pattern: &str,
+    follow_symlinks: bool,
+) -> Result<Vec<(PathBuf, bool)>, ApplicationError> {
+    let explicit = Pattern::escape(pattern) == pattern;
+    let mut paths = vec![];
+
+    for path in glob::glob(pattern).map_err(|error| pattern_error(error, pattern))? {
+        let path = path?;
+
+        if path.is_dir() {
+            search_directory(&path, follow_symlinks, &mut HashSet::new(), &mut paths)?;
+        } else {
+            paths.push((path, explicit));
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Normalizes a path relative to a current directory for ignore patterns.
+///
+/// It strips a current directory prefix and `.` components.
+fn normalize_path(path: &Path) -> PathBuf {
+    current_dir()
+        .ok()
+        .and_then(|directory| path.strip_prefix(directory).ok())
+        .unwrap_or(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+fn pattern_error(error: PatternError, pattern: &str) -> ApplicationError {
+    ApplicationError::Pattern {
+        error,
+        pattern: pattern.into(),
+    }
+}
+
+fn search_directory(
+    directory: &Path,
+    follow_symlinks: bool,
+    directories: &mut HashSet<PathBuf>,
+    paths: &mut Vec<(PathBuf, bool)>,
+) -> Result<(), io::Error> {
+    // Skip directories visited already to avoid cycles of symbolic links.
+    if !directories.insert(canonicalize(directory)?) {
+        return Ok(());
+    }
+
+    let mut entries = read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if follow_symlinks || !path.is_symlink() {
+                search_directory(&path, follow_symlinks, directories, paths)?;
+            }
+        } else if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| SOURCE_EXTENSIONS.iter().any(|other| extension == *other))
+        {
+            paths.push((path, false));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a file atomically by renaming a temporary file in the same directory.
+///
+/// Symbolic links are written through into their targets. Files with multiple
+/// hard links are written in place not to unlink them from the others.
+pub async fn write_file(path: &Path, content: &str) -> Result<(), io::Error> {
+    let path = &tokio::fs::canonicalize(path).await?;
+    let metadata = metadata(path).await?;
+
+    if metadata.is_file() && metadata.nlink() > 1 {
+        return write(path, content).await;
+    }
+
+    let temporary_path = write_temporary_file(path, content).await?;
+
+    if let Err(error) = rename(&temporary_path, path).await {
+        // A cleanup failure should not hide an original error.
+        let _ = remove_file(&temporary_path).await;
+
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Reads a source file if its size does not exceed a maximum one.
+///
+/// Non-regular files, such as named pipes, are read fully before their sizes
+/// are checked.
+pub async fn read_source(path: &Path, max_size: Option<u64>) -> Result<String, ApplicationError> {
+    if let Some(max_size) = max_size {
+        let metadata = metadata(path)
+            .await
+            .map_err(|error| convert_read_error(path, error))?;
+
+        // Sizes of non-regular files are unknown until they are read.
+        if metadata.is_file() {
+            check_size(path, metadata.len(), max_size)?;
+        }
+    }
+
+    let source = read_to_string(path)
+        .await
+        .map_err(|error| convert_read_error(path, error))?;
+
+    if let Some(max_size) = max_size {
+        check_size(path, source.len() as u64, max_size)?;
+    }
+
+    Ok(source)
+}
+
+fn check_size(path: &Path, size: u64, max_size: u64) -> Result<(), ApplicationError> {
+    if size > max_size {
+        Err(ApplicationError::FileTooLarge {
+            path: path.into(),
+            size,
+            max_size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn convert_read_error(path: &Path, error: io::Error) -> ApplicationError {
+    if error.kind() == io::ErrorKind::PermissionDenied {
+        ApplicationError::PermissionDenied(path.into())
+    } else {
+        error.into()
+    }
+}
+
+/// Sets a modification time of a file.
+pub async fn set_modified_time(path: &Path, time: SystemTime) -> Result<(), io::Error> {
+    let path = path.to_owned();
+
+    spawn_blocking(move || File::options().write(true).open(path)?.set_modified(time)).await?
+}
+
+/// Infers a dialect from an extension of a path.
+pub fn infer_dialect(path: &Path) -> Option<Dialect> {
+    let extension = path.extension()?;
+
+    if extension == "el" {
+        Some(Dialect::Elisp)
+    } else if SOURCE_EXTENSIONS.iter().any(|other| extension == *other) {
+        Some(Dialect::Scheme)
+    } else {
+        None
+    }
+}
+
+/// Returns a top-level directory of a path relative to a current directory.
+///
+/// It returns `.` for files directly in a current directory.
+pub fn top_directory(path: &Path) -> PathBuf {
+    let path = current_dir()
+        .ok()
+        .and_then(|directory| path.strip_prefix(directory).ok())
+        .unwrap_or(path);
+    let mut components = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir));
+
+    match (components.next(), components.next()) {
+        (Some(component), Some(_)) => component.as_os_str().into(),
+        _ => ".".into(),
+    }
+}
+
+/// Resolves a path of a file mirrored into a directory.
+///
+/// The path needs to be under a current directory.
+pub fn mirror_path(directory: &Path, path: &Path) -> Result<PathBuf, io::Error> {
+    let current_directory = current_dir()?;
+    let relative_path = if path.is_absolute() {
+        path.strip_prefix(&current_directory)
+            .map_err(|_| outside_path_error(path))?
+    } else {
+        path
+    };
+    let mut mirrored_path = directory.to_owned();
+
+    for component in relative_path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(name) => mirrored_path.push(name),
+            Component::ParentDir | Component::Prefix(_) | Component::RootDir => {
+                return Err(outside_path_error(path))
+            }
+        }
+    }
+
+    Ok(mirrored_path)
+}
+
+fn outside_path_error(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("path outside current directory: {}", path.display()),
+    )
+}
+
+async fn write_temporary_file(path: &Path, content: &str) -> Result<PathBuf, io::Error> {
+    let metadata = metadata(path).await?;
+
+    // Renaming a temporary file would replace a non-regular file, such as a
+    // named pipe, with a regular one.
+    if !metadata.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("non-regular file cannot be overwritten: {}", path.display()),
+        ));
+    }
+
+    let permissions = metadata.permissions();
+    let temporary_path = temporary_path(path);
+
+    if let Err(error) = async {
+        write(&temporary_path, content).await?;
+        set_permissions(&temporary_path, permissions).await
+    }
+    .await
+    {
+        let _ = remove_file(&temporary_path).await;
+
+        return Err(error);
+    }
+
+    Ok(temporary_path)
+}
+
+// Temporary paths are unique for every write not to collide with each other in
+// concurrent runs.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}",
+        process::id(),
+        TEMPORARY_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    name.push(TEMPORARY_FILE_SUFFIX);
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{
+        env::temp_dir,
+        fs,
+        os::unix::fs::{symlink, FileTypeExt, PermissionsExt},
+        process::Command,
+        thread,
+        time::Duration,
+    };
+
+    fn create_file(name: &str, content: &str, mode: u32) -> PathBuf {
+        let path = temp_dir().join(format!("schemat-{}-{}", std::process::id(), name));
+
+        fs::write(&path, content).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+
+        path
+    }
+
+    fn has_temporary_file(path: &Path) -> bool {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        fs::read_dir(path.parent().unwrap()).unwrap().any(|entry| {
+            let other = entry.unwrap().file_name().to_string_lossy().into_owned();
+
+            other.starts_with(&name) && other.ends_with(TEMPORARY_FILE_SUFFIX)
+        })
+    }
+
+    #[tokio::test]
+    async fn read_small_source() {
+        let path = create_file("read_small_source.scm", "foo", 0o644);
+
+        assert_eq!(read_source(&path, Some(3)).await.unwrap(), "foo");
+        assert_eq!(read_source(&path, None).await.unwrap(), "foo");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_to_read_source_without_permission() {
+        let path = create_file("fail_to_read_source_without_permission.scm", "foo", 0o000);
+
+        // Privileged users can read files regardless of permissions.
+        if fs::File::open(&path).is_err() {
+            assert!(matches!(
+                read_source(&path, None).await,
+                Err(ApplicationError::PermissionDenied(other)) if other == path
+            ));
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn convert_permission_denied_error() {
+        assert!(matches!(
+            convert_read_error(
+                Path::new("foo.scm"),
+                io::Error::from(io::ErrorKind::PermissionDenied)
+            ),
+            ApplicationError::PermissionDenied(path) if path == Path::new("foo.scm")
+        ));
+    }
+
+    #[test]
+    fn convert_other_read_error() {
+        assert!(matches!(
+            convert_read_error(
+                Path::new("foo.scm"),
+                io::Error::from(io::ErrorKind::NotFound)
+            ),
+            ApplicationError::Io(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn fail_to_read_large_source() {
+        let path = create_file("fail_to_read_large_source.scm", "foo", 0o644);
+
+        assert!(matches!(
+            read_source(&path, Some(2)).await,
+            Err(ApplicationError::FileTooLarge {
+                size: 3,
+                max_size: 2,
+                ..
+            })
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    fn create_fifo(name: &str, content: &'static str) -> (PathBuf, thread::JoinHandle<()>) {
+        let path = temp_dir().join(format!("schemat-{}-{}", std::process::id(), name));
+
+        assert!(Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap()
+            .success());
+
+        let writer = thread::spawn({
+            let path = path.clone();
+            move || fs::write(path, content).unwrap()
+        });
+
+        (path, writer)
+    }
+
+    #[tokio::test]
+    async fn read_source_from_named_pipe() {
+        let (path, writer) = create_fifo("read_source_from_named_pipe.scm", "foo");
+
+        assert_eq!(read_source(&path, Some(3)).await.unwrap(), "foo");
+
+        writer.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_to_read_large_source_from_named_pipe() {
+        let (path, writer) = create_fifo("fail_to_read_large_source_from_named_pipe.scm", "foo");
+
+        assert!(matches!(
+            read_source(&path, Some(2)).await,
+            Err(ApplicationError::FileTooLarge {
+                size: 3,
+                max_size: 2,
+                ..
+            })
+        ));
+
+        writer.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_to_overwrite_named_pipe() {
+        let (path, writer) = create_fifo("fail_to_overwrite_named_pipe.scm", "foo");
+
+        assert_eq!(
+            write_file(&path, "bar").await.unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(fs::metadata(&path).unwrap().file_type().is_fifo());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+
+        writer.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_content() {
+        let path = create_file("write_content.scm", "foo", 0o644);
+
+        write_file(&path, "bar").await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+        assert!(!has_temporary_file(&path));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_through_symbolic_link() {
+        let path = create_file("write_through_symbolic_link.scm", "foo", 0o644);
+        let link = path.with_file_name(format!(
+            "schemat-{}-write_through_symbolic_link_link.scm",
+            std::process::id()
+        ));
+        symlink(&path, &link).unwrap();
+
+        write_file(&link, "bar").await.unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+        assert!(!has_temporary_file(&path));
+
+        fs::remove_file(link).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_through_hard_link() {
+        let path = create_file("write_through_hard_link.scm", "foo", 0o644);
+        let link = path.with_file_name(format!(
+            "schemat-{}-write_through_hard_link_link.scm",
+            std::process::id()
+        ));
+        fs::hard_link(&path, &link).unwrap();
+
+        write_file(&link, "bar").await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+        assert_eq!(fs::metadata(&link).unwrap().nlink(), 2);
+        assert!(!has_temporary_file(&path));
+
+        fs::remove_file(link).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn generate_unique_temporary_paths() {
+        let path = Path::new("foo.scm");
+
+        assert_ne!(temporary_path(path), temporary_path(path));
+    }
+
+    #[tokio::test]
+    async fn preserve_permissions() {
+        let path = create_file("preserve_permissions.scm", "foo", 0o750);
+
+        write_file(&path, "bar").await.unwrap();
+
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o750
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keep_original_on_interrupted_write() {
+        let path = create_file("keep_original.scm", "foo", 0o644);
+
+        // Simulate a crash between writing a temporary file and renaming it.
+        let temporary_path = write_temporary_file(&path, "bar").await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+        assert_eq!(fs::read_to_string(&temporary_path).unwrap(), "bar");
+
+        fs::remove_file(temporary_path).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restore_modified_time() {
+        let path = create_file("restore_modified_time.scm", "foo", 0o644);
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+        write_file(&path, "bar").await.unwrap();
+        set_modified_time(&path, time).await.unwrap();
+
+        let modified_time = fs::metadata(&path).unwrap().modified().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+        assert!(
+            modified_time
+                .duration_since(time)
+                .unwrap_or_else(|error| error.duration())
+                < Duration::from_secs(1)
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn infer_dialects() {
+        assert_eq!(infer_dialect(Path::new("foo.el")), Some(Dialect::Elisp));
+        assert_eq!(
+            infer_dialect(Path::new("foo/bar.scm")),
+            Some(Dialect::Scheme)
+        );
+        assert_eq!(infer_dialect(Path::new("foo.rkt")), Some(Dialect::Scheme));
+        assert_eq!(infer_dialect(Path::new("foo.txt")), None);
+        assert_eq!(infer_dialect(Path::new("foo")), None);
+    }
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize_path(Path::new("foo.scm")), Path::new("foo.scm"));
+        assert_eq!(normalize_path(Path::new("./foo.scm")), Path::new("foo.scm"));
+        assert_eq!(
+            normalize_path(Path::new("./foo/./bar.scm")),
+            Path::new("foo/bar.scm")
+        );
+        assert_eq!(
+            normalize_path(&current_dir().unwrap().join("foo/bar.scm")),
+            Path::new("foo/bar.scm")
+        );
+        assert_eq!(
+            normalize_path(Path::new("/foo/bar.scm")),
+            Path::new("/foo/bar.scm")
+        );
+    }
+
+    #[test]
+    fn find_top_directories() {
+        assert_eq!(top_directory(Path::new("foo.scm")), Path::new("."));
+        assert_eq!(top_directory(Path::new("./foo.scm")), Path::new("."));
+        assert_eq!(top_directory(Path::new("foo/bar.scm")), Path::new("foo"));
+        assert_eq!(
+            top_directory(Path::new("./foo/bar/baz.scm")),
+            Path::new("foo")
+        );
+        assert_eq!(
+            top_directory(&current_dir().unwrap().join("foo/bar.scm")),
+            Path::new("foo")
+        );
+    }
+
+    #[test]
+    fn mirror_relative_path() {
+        assert_eq!(
+            mirror_path(Path::new("out"), Path::new("foo/bar.scm")).unwrap(),
+            Path::new("out/foo/bar.scm")
+        );
+        assert_eq!(
+            mirror_path(Path::new("out"), Path::new("./foo/bar.scm")).unwrap(),
+            Path::new("out/foo/bar.scm")
+        );
+    }
+
+    #[test]
+    fn mirror_absolute_path() {
+        assert_eq!(
+            mirror_path(
+                Path::new("out"),
+                &current_dir().unwrap().join("foo/bar.scm")
+            )
+            .unwrap(),
+            Path::new("out/foo/bar.scm")
+        );
+    }
+
+    #[test]
+    fn fail_to_mirror_outside_path() {
+        assert!(mirror_path(Path::new("out"), Path::new("../foo.scm")).is_err());
+        assert!(mirror_path(Path::new("out"), Path::new("/foo.scm")).is_err());
+    }
+
+    #[tokio::test]
+    async fn read_paths_in_directory() {
+        let directory = temp_dir().join(format!("schemat-{}-read_paths", std::process::id()));
+
+        fs::create_dir_all(directory.join("foo")).unwrap();
+        fs::write(directory.join("bar.scm"), "").unwrap();
+        fs::write(directory.join("foo/baz.sld"), "").unwrap();
+        fs::write(directory.join("foo/README"), "").unwrap();
+
+        let mut paths = read_paths(&[directory.display().to_string()], &[], true, false, None)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![directory.join("bar.scm"), directory.join("foo/baz.sld")]
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn ignore_paths_in_directory() {
+        let directory = temp_dir().join(format!("schemat-{}-ignore_paths", std::process::id()));
+
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("foo.scm"), "").unwrap();
+        fs::write(directory.join("bar.scm"), "").unwrap();
+
+        assert_eq!(
+            read_paths(
+                &[directory.display().to_string()],
+                &["**/bar.scm".into()],
+                true,
+                false,
+                None
+            )
+            .await
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec![directory.join("foo.scm")]
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_duplicate_paths_once() {
+        let directory = temp_dir().join(format!("schemat-{}-duplicate_paths", std::process::id()));
+
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("foo.scm"), "").unwrap();
+        fs::write(directory.join("bar.scm"), "").unwrap();
+
+        assert_eq!(
+            read_paths(
+                &[
+                    directory.join("foo.scm").display().to_string(),
+                    directory.join("*.scm").display().to_string(),
+                    directory.join(".").join("foo.scm").display().to_string(),
+                    directory.display().to_string(),
+                ],
+                &[],
+                true,
+                false,
+                None
+            )
+            .await
+            .unwrap()
+            .count(),
+            2
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    async fn read_symlinked_paths(name: &str, follow_symlinks: bool) -> Vec<PathBuf> {
+        let base = temp_dir().join(format!("schemat-{}-{}", std::process::id(), name));
+        let directory = base.join("foo");
+
+        fs::create_dir_all(directory.join("bar")).unwrap();
+        fs::create_dir_all(base.join("baz")).unwrap();
+        fs::write(directory.join("bar/qux.scm"), "").unwrap();
+        fs::write(base.join("baz/quux.scm"), "").unwrap();
+        symlink(base.join("baz"), directory.join("link")).unwrap();
+        // Create a cycle.
+        symlink(&directory, base.join("baz/cycle")).unwrap();
+
+        let mut paths = read_paths(
+            &[directory.display().to_string()],
+            &[],
+            true,
+            follow_symlinks,
+            None,
+        )
+        .await
+        .unwrap()
+        .map(|path| path.strip_prefix(&directory).unwrap().to_owned())
+        .collect::<Vec<_>>();
+        paths.sort();
+
+        fs::remove_dir_all(base).unwrap();
+
+        paths
+    }
+
+    #[tokio::test]
+    async fn skip_symlinked_directory() {
+        assert_eq!(
+            read_symlinked_paths("skip_symlinked_directory", false).await,
+            vec![Path::new("bar/qux.scm")]
+        );
+    }
+
+    #[tokio::test]
+    async fn follow_symlinked_directory() {
+        assert_eq!(
+            read_symlinked_paths("follow_symlinked_directory", true).await,
+            vec![Path::new("bar/qux.scm"), Path::new("link/quux.scm")]
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_read_paths_with_invalid_ignore_pattern() {
+        let Err(error) = read_paths(&["foo.scm".into()], &["bar[".into()], true, false, None).await
+        else {
+            unreachable!()
+        };
+
+        assert!(error.to_string().starts_with("bar[: "));
+    }
+}
@@ -1,32 +1,45 @@
+mod error;
+
+pub use self::error::FormatError;
 use crate::{
     ast::{Comment, Expression, HashDirective},
     context::Context,
+    options::{HexCase, Options, QuoteIndent, QuoteStyle},
     position::Position,
     position_map::PositionMap,
+    source::measure_width,
 };
 use core::fmt;
 use mfmt::{empty, line, sequence, utility::is_empty, Builder, Document, FormatOptions};
-use std::alloc::Allocator;
+use std::{alloc::Allocator, iter::repeat_n};
 
 const COMMENT_PREFIX: &str = ";";
-const QUOTE_SIGNS: &[&str] = &["'", "`", "#"];
-const UNQUOTE_SIGNS: &[&str] = &[","];
+pub const QUOTE_SIGNS: &[&str] = &["'", "`", "#"];
+pub const UNQUOTE_SIGNS: &[&str] = &[",", ",@"];
+const QUOTE_NAMES: &[(&str, &str)] = &[
+    ("'", "quote"),
+    ("`", "quasiquote"),
+    (",", "unquote"),
+    (",@", "unquote-splicing"),
+];
 
 pub fn format<A: Allocator + Clone>(
     module: &[Expression<A>],
     comments: &[Comment],
     hash_directives: &[HashDirective],
     position_map: &PositionMap,
+    options: &Options,
     allocator: A,
 ) -> Result<String, fmt::Error> {
     let mut string = Default::default();
     let document = compile_module(
-        &mut Context::new(comments, position_map, Builder::new(allocator)),
+        &mut Context::new(comments, position_map, options, Builder::new(allocator)),
         module,
         hash_directives,
     );
 
     mfmt::format(
+        // Format an empty or whitespace-only source into a single newline.
         &if is_empty(&document) {
             line()
         } else {
@@ -44,18 +57,16 @@ fn compile_module<'a, A: Allocator + Clone + 'a>(
     module: &'a [Expression<'a, A>],
     hash_directives: &[HashDirective],
 ) -> Document<'a> {
-    [
-        if hash_directives.is_empty() {
-            empty()
-        } else {
-            context.builder().sequence([context.builder().sequence(
-                hash_directives
-                    .iter()
-                    .map(|directive| compile_hash_directive(context, directive)),
-            )])
-        },
+    let gap = hash_directive_gap(context, module, hash_directives);
+    let document = [
         {
-            let expressions = compile_expressions(context, module, false);
+            let expressions = compile_expression_sequence(
+                context,
+                module,
+                false,
+                context.options().definition_blank_lines(),
+                false,
+            );
 
             if is_empty(&expressions) {
                 empty()
@@ -79,7 +90,56 @@ fn compile_module<'a, A: Allocator + Clone + 'a>(
                 document,
             ])
         }
-    })
+    });
+
+    if hash_directives.is_empty() {
+        document
+    } else {
+        context.builder().sequence([
+            context.builder().sequence(
+                hash_directives
+                    .iter()
+                    .map(|directive| compile_hash_directive(context, directive)),
+            ),
+            if is_empty(&document) {
+                empty()
+            } else {
+                context
+                    .builder()
+                    .sequence([compile_blank_lines(context, gap), document])
+            },
+        ])
+    }
+}
+
+// A line gap between the last hash directive and the first expression or comment.
+fn hash_directive_gap<A: Allocator + Clone>(
+    context: &Context<A>,
+    module: &[Expression<A>],
+    hash_directives: &[HashDirective],
+) -> usize {
+    let Some(directive) = hash_directives.last() else {
+        return 0;
+    };
+
+    [
+        module
+            .first()
+            .map(|expression| expression.position().start()),
+        context
+            .peek_comments(usize::MAX)
+            .next()
+            .map(|comment| comment.position().start()),
+    ]
+    .into_iter()
+    .flatten()
+    .min()
+    .map(|start| line_index(context, start))
+    .unwrap_or_default()
+    .saturating_sub(line_index(
+        context,
+        directive.position().end().saturating_sub(1),
+    ))
 }
 
 fn compile_hash_directive<'a, A: Allocator + Clone + 'a>(
@@ -99,22 +159,246 @@ fn compile_expression<'a, A: Allocator + Clone + 'a>(
 ) -> Document<'a> {
     compile_comment(context, expression.position(), |context| match expression {
         Expression::List(left, right, expressions, position) => {
-            compile_list(context, expressions, position, left, right, data)
+            if let Some((sign, quoted)) = abbreviate_quote(context, left, right, expressions) {
+                shift_layout(context, expression, quoted, sign.len(), 0);
+                compile_quote(context, sign, quoted, data)
+            } else {
+                compile_list(context, expressions, position, left, right, data)
+            }
+        }
+        Expression::Quote(sign, quoted, _) => {
+            if let Some((name, sign, quoted)) = expand_quote(context, sign, quoted) {
+                let builder = context.builder().clone();
+
+                shift_layout(context, expression, quoted, name.len() + 2, 1);
+
+                builder.sequence([
+                    builder.strings(["(", name, " "]),
+                    compile_quoted_expression(context, sign, quoted, data),
+                    ")".into(),
+                ])
+            } else {
+                shift_layout(context, expression, quoted, sign.len(), 0);
+                compile_quote(context, sign, quoted, data)
+            }
+        }
+        Expression::QuotedSymbol(symbol, _) => {
+            let builder = context.builder();
+
+            builder.sequence([
+                "|".into(),
+                compile_quoted_symbol(context, symbol).unwrap_or_else(|| (*symbol).into()),
+                "|".into(),
+            ])
+        }
+        // Strings are kept verbatim and never indented as their line breaks are part of
+        // their contents.
+        Expression::DelimitedString(left, right, string, _) => {
+            context.builder().sequence([*left, *string, *right])
         }
-        Expression::Quote(sign, expression, _) => context.builder().clone().sequence([
-            (*sign).into(),
-            compile_expression(
-                context,
-                expression,
-                QUOTE_SIGNS.contains(sign) || !UNQUOTE_SIGNS.contains(sign) && data,
-            ),
-        ]),
-        Expression::QuotedSymbol(symbol, _) => context.builder().sequence(["|", *symbol, "|"]),
         Expression::String(string, _) => context.builder().sequence(["\"", *string, "\""]),
         Expression::Symbol(name, _) => (*name).into(),
     })
 }
 
+// Estimates a layout of a quoted expression from a layout of its quote.
+fn shift_layout<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    expression: &Expression<A>,
+    quoted: &Expression<A>,
+    column: usize,
+    width: usize,
+) {
+    if context.options().reflow() {
+        let (outer_column, outer_width) = context.layout(expression.position().start());
+
+        context.set_layout(
+            quoted.position().start(),
+            outer_column + column,
+            outer_width + width,
+        );
+    }
+}
+
+fn compile_quote<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    sign: &'a str,
+    expression: &'a Expression<'a, A>,
+    data: bool,
+) -> Document<'a> {
+    let builder = context.builder().clone();
+    // Only the first one of consecutive quote signs sets a base column.
+    let offside = context.options().quote_indent() == QuoteIndent::Prefix && !context.prefixed();
+
+    if context.options().quote_indent() == QuoteIndent::Prefix {
+        context.set_prefixed(true);
+    }
+
+    let document = builder.sequence([
+        sign.into(),
+        // Keep an unquote of a symbol starting with `@` from becoming a splicing unquote.
+        match expression {
+            Expression::Quote(name, _, _) | Expression::Symbol(name, _)
+                if sign == "," && name.starts_with('@') =>
+            {
+                " ".into()
+            }
+            _ => empty(),
+        },
+        match expression {
+            Expression::Symbol(name, _) if sign == "#" => compile_character(context, name)
+                .unwrap_or_else(|| compile_quoted_expression(context, sign, expression, data)),
+            _ => compile_quoted_expression(context, sign, expression, data),
+        },
+    ]);
+
+    if offside {
+        context.set_prefixed(false);
+        builder.offside(document, false)
+    } else {
+        document
+    }
+}
+
+// Normalizes a hexadecimal character literal like `#\x41` without touching
+// named ones like `#\space`.
+fn compile_character<'a, A: Allocator + Clone + 'a>(
+    context: &Context<A>,
+    name: &str,
+) -> Option<Document<'a>> {
+    let digits = name.strip_prefix("\\x")?;
+
+    if digits.is_empty()
+        || !digits
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+    {
+        return None;
+    }
+
+    let digits = match context.options().hex_case() {
+        HexCase::Preserve => return None,
+        HexCase::Lower => digits.to_ascii_lowercase(),
+        HexCase::Upper => digits.to_ascii_uppercase(),
+    };
+
+    Some(context.builder().strings(["\\x", &digits]))
+}
+
+// Replaces unnecessary escapes in a quoted symbol with literal characters while
+// keeping escaped bars, backslashes, and whitespace ones.
+fn compile_quoted_symbol<'a, A: Allocator + Clone + 'a>(
+    context: &Context<'a, A>,
+    symbol: &str,
+) -> Option<Document<'a>> {
+    if !context.options().minimal_symbol_escapes() || !symbol.contains('\\') {
+        return None;
+    }
+
+    let mut string = String::with_capacity(symbol.len());
+    let mut rest = symbol;
+
+    while let Some(index) = rest.find('\\') {
+        string.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let length = escape_length(rest);
+
+        if let Some(character) = unescape_symbol_character(&rest[..length]) {
+            string.push(character);
+        } else {
+            string.push_str(&rest[..length]);
+        }
+
+        rest = &rest[length..];
+    }
+
+    string.push_str(rest);
+
+    (string != symbol).then(|| context.builder().strings([string.as_str()]))
+}
+
+// Returns a length of an escape sequence at the start of a string.
+fn escape_length(string: &str) -> usize {
+    if let Some(index) = string
+        .strip_prefix("\\x")
+        .and_then(|digits| digits.find(';'))
+        .filter(|&index| {
+            string[2..2 + index]
+                .chars()
+                .all(|character| character.is_ascii_hexdigit())
+        })
+    {
+        return 3 + index;
+    }
+
+    1 + string[1..]
+        .chars()
+        .next()
+        .map(char::len_utf8)
+        .unwrap_or_default()
+}
+
+fn unescape_symbol_character(escape: &str) -> Option<char> {
+    let character = match escape {
+        "\\;" => ';',
+        "\\'" => '\'',
+        _ => char::from_u32(
+            u32::from_str_radix(escape.strip_prefix("\\x")?.strip_suffix(';')?, 16).ok()?,
+        )?,
+    };
+
+    (character.is_ascii_graphic() && !matches!(character, '|' | '\\')).then_some(character)
+}
+
+fn compile_quoted_expression<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    sign: &str,
+    expression: &'a Expression<'a, A>,
+    data: bool,
+) -> Document<'a> {
+    compile_expression(
+        context,
+        expression,
+        QUOTE_SIGNS.contains(&sign) || !UNQUOTE_SIGNS.contains(&sign) && data,
+    )
+}
+
+fn abbreviate_quote<'a, A: Allocator + Clone>(
+    context: &Context<A>,
+    left: &str,
+    right: &str,
+    expressions: &'a [Expression<'a, A>],
+) -> Option<(&'static str, &'a Expression<'a, A>)> {
+    if context.options().quote_style() != QuoteStyle::Abbreviation || (left, right) != ("(", ")") {
+        return None;
+    }
+
+    let [Expression::Symbol(name, _), expression] = expressions else {
+        return None;
+    };
+
+    QUOTE_NAMES
+        .iter()
+        .find(|(_, other)| other == name)
+        .map(|(sign, _)| (*sign, expression))
+}
+
+fn expand_quote<'a, A: Allocator + Clone>(
+    context: &Context<A>,
+    sign: &str,
+    expression: &'a Expression<'a, A>,
+) -> Option<(&'static str, &'static str, &'a Expression<'a, A>)> {
+    if context.options().quote_style() != QuoteStyle::Full {
+        return None;
+    }
+
+    QUOTE_NAMES
+        .iter()
+        .find(|(other, _)| *other == sign)
+        .map(|(sign, name)| (*name, *sign, expression))
+}
+
 fn compile_list<'a, A: Allocator + Clone + 'a>(
     context: &mut Context<'a, A>,
     expressions: &'a [Expression<'a, A>],
@@ -123,16 +407,31 @@ fn compile_list<'a, A: Allocator + Clone + 'a>(
     right: &'a str,
     data: bool,
 ) -> Document<'a> {
-    let index = line_index(context, position.start());
+    let prefixed = context.prefixed();
+    context.set_prefixed(false);
 
-    let index = expressions
-        .iter()
-        .position(|expression| line_index(context, expression.position().start()) > index)
-        .unwrap_or(expressions.len());
+    let index = if context.options().preserve_breaks() {
+        break_index(context, expressions, position)
+    } else {
+        list_index(context, expressions, position, left, right, data)
+    };
     let first = &expressions[..index];
     let last = &expressions[index..];
 
+    if context.options().reflow() {
+        set_element_layouts(context, expressions, position, left, right, index, data);
+    }
+
     let builder = context.builder().clone();
+    let space = if context.options().brace_space()
+        && left == "{"
+        && right == "}"
+        && !expressions.is_empty()
+    {
+        " "
+    } else {
+        ""
+    };
 
     builder.sequence([
         compile_comment(
@@ -140,36 +439,32 @@ fn compile_list<'a, A: Allocator + Clone + 'a>(
             &position.set_end(position.start() + left.len()),
             |_| left.into(),
         ),
-        builder.indent(
-            builder.offside(
-                builder.sequence(
-                    [builder.flatten(compile_expressions(context, first, data))]
-                        .into_iter()
-                        .chain(match (first.last(), last.first()) {
-                            (Some(first), Some(last)) if line_gap(context, first, last) > 1 => {
-                                Some(line())
-                            }
-                            _ => None,
-                        })
-                        .chain(if last.is_empty() {
-                            None
-                        } else {
-                            Some(
-                                builder.r#break(
-                                    builder.sequence([
-                                        line(),
-                                        compile_expressions(context, last, data),
-                                    ]),
-                                ),
-                            )
-                        }),
-                ),
-                !data,
-            ),
-        ),
+        space.into(),
+        builder.indent({
+            let body = if is_aligned(context, first, data) {
+                let head = builder.flatten(compile_expressions(context, &first[..1], data));
+
+                builder.sequence([
+                    head,
+                    " ".into(),
+                    builder.offside(compile_list_body(context, &first[1..], last, data), false),
+                ])
+            } else {
+                compile_list_body(context, first, last, data)
+            };
+
+            // Lists prefixed by quote signs are indented from the signs.
+            if prefixed {
+                body
+            } else {
+                builder.offside(body, !data)
+            }
+        }),
         {
-            let inline_comment =
-                compile_inline_comment(context, &position.set_start(position.end() - right.len()));
+            let inline_comment = compile_inline_comment(
+                context,
+                &right_delimiter_position(position, expressions, right),
+            );
 
             builder.sequence([
                 if is_empty(&inline_comment) || expressions.is_empty() {
@@ -177,401 +472,2020 @@ fn compile_list<'a, A: Allocator + Clone + 'a>(
                 } else {
                     " ".into()
                 },
-                inline_comment,
+                builder.indent(inline_comment),
+                space.into(),
                 right.into(),
             ])
         },
     ])
 }
 
-fn compile_expressions<'a, A: Allocator + Clone + 'a>(
-    context: &mut Context<'a, A>,
-    expressions: &'a [Expression<'a, A>],
-    data: bool,
-) -> Document<'a> {
-    let mut documents =
-        Vec::with_capacity_in(2 * expressions.len(), context.builder().allocator().clone());
-    let mut last_expression = None;
+// Returns an index of a first element on a different line from its previous
+// one in a source.
+fn break_index<A: Allocator + Clone>(
+    context: &Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+) -> usize {
+    let mut previous = line_index(context, position.start());
+    let mut end = position.start();
 
-    for expression in expressions {
-        if let Some(last_expression) = last_expression {
-            documents.push(line());
+    expressions
+        .iter()
+        .position(|expression| {
+            let index = expression_line_index(context, end, expression);
+            let broken = index > previous;
+            previous = line_index(context, expression.position().end() - 1);
+            end = expression.position().end();
+            broken
+        })
+        .unwrap_or(expressions.len())
+}
 
-            if line_gap(context, last_expression, expression) > 1 {
-                documents.push(line());
-            }
-        }
+fn list_index<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+    left: &str,
+    right: &str,
+    data: bool,
+) -> usize {
+    let index =
+        reflow_index(context, expressions, position, left, right, data).unwrap_or_else(|| {
+            let index = line_index(context, position.start());
+            let mut end = position.start();
 
-        documents.push(compile_expression(context, expression, data));
+            expressions
+                .iter()
+                .position(|expression| {
+                    let broken = expression_line_index(context, end, expression) > index;
+                    end = expression.position().end();
+                    broken
+                })
+                .unwrap_or(expressions.len())
+        });
+    let index = match context.options().max_inline_elements() {
+        Some(count) if expressions.len() > count => index.min(1),
+        _ => index,
+    };
 
-        last_expression = Some(expression);
+    match argument_count(context, expressions, data) {
+        // Keep comments between arguments in place.
+        Some(count)
+            if index < expressions.len()
+                && !context.has_comments(
+                    &position.set_end(
+                        expressions[count.min(expressions.len() - 1)]
+                            .position()
+                            .start(),
+                    ),
+                ) =>
+        {
+            (count + 1).min(expressions.len())
+        }
+        _ => index,
     }
+}
 
-    sequence(documents.leak())
+// Returns a line index of an expression regarding it as on a next line if more
+// inline comments than allowed precede it since excess ones are moved onto their
+// own lines.
+fn expression_line_index<A: Allocator + Clone>(
+    context: &Context<A>,
+    start: usize,
+    expression: &Expression<A>,
+) -> usize {
+    line_index(context, expression.position().start())
+        + usize::from(
+            context
+                .options()
+                .max_inline_comments()
+                .is_some_and(|count| {
+                    context.count_comments(&Position::new(start, expression.position().start()))
+                        > count.max(1)
+                }),
+        )
 }
 
-fn compile_comment<'a, A: Allocator + Clone + 'a>(
-    context: &mut Context<'a, A>,
+// A right delimiter never starts before the end of the last element even if a
+// list position is broken.
+fn right_delimiter_position<A: Allocator>(
     position: &Position,
-    document: impl Fn(&mut Context<'a, A>) -> Document<'a>,
-) -> Document<'a> {
-    let block_comment = compile_block_comment(context, position);
-    let inline_comment = compile_inline_comment(context, position);
-    let inline_space = if is_empty(&inline_comment) {
-        empty()
-    } else {
-        " ".into()
-    };
-    let document = document(context);
-    let suffix_comment = compile_suffix_comment(context, position);
+    expressions: &[Expression<A>],
+    right: &str,
+) -> Position {
+    let start = position.end().saturating_sub(right.len());
 
-    context.builder().sequence([
-        block_comment,
-        inline_comment,
-        inline_space,
-        document,
-        suffix_comment,
-    ])
+    position.set_start(
+        Position::span(expressions.iter().map(Expression::position))
+            .map_or(start, |span| span.end().max(start)),
+    )
 }
 
-fn compile_inline_comment<'a, A: Allocator + Clone + 'a>(
+fn compile_list_body<'a, A: Allocator + Clone + 'a>(
     context: &mut Context<'a, A>,
-    position: &Position,
+    first: &'a [Expression<'a, A>],
+    last: &'a [Expression<'a, A>],
+    data: bool,
 ) -> Document<'a> {
     let builder = context.builder().clone();
 
     builder.sequence(
-        context
-            .drain_inline_comments(position)
-            .map(|comment| builder.sequence(["#|", comment.content(), "|#"])),
+        [builder.flatten(compile_expressions(context, first, data))]
+            .into_iter()
+            .chain(match (first.last(), last.first()) {
+                (Some(first), Some(last)) => {
+                    Some(compile_blank_lines(context, line_gap(context, first, last)))
+                }
+                _ => None,
+            })
+            .chain(if last.is_empty() {
+                None
+            } else {
+                Some(
+                    builder.r#break(
+                        builder.sequence([line(), compile_expressions(context, last, data)]),
+                    ),
+                )
+            }),
     )
 }
 
-fn compile_suffix_comment<'a, A: Allocator + Clone + 'a>(
-    context: &mut Context<A>,
-    position: &Position,
-) -> Document<'a> {
-    let builder = context.builder().clone();
+// Arguments are aligned to a first argument in a style unless a list is
+// headed by a special form.
+fn is_aligned<A: Allocator + Clone>(
+    context: &Context<A>,
+    first: &[Expression<A>],
+    data: bool,
+) -> bool {
+    let (Some(style), [Expression::Symbol(name, _), _, ..]) = (context.options().style(), first)
+    else {
+        return false;
+    };
 
-    builder.sequence(
-        context
-            .drain_current_line_comment(line_index(context, position.start()))
-            .map(|comment| {
-                builder.line_suffixes([" ", COMMENT_PREFIX, comment.content().trim_end()])
-            }),
-    )
+    !data && !style.is_special_form(name)
 }
 
-fn compile_block_comment<'a, A: Allocator + Clone + 'a>(
-    context: &mut Context<'a, A>,
-    position: &Position,
-) -> Document<'a> {
-    let builder = context.builder().clone();
-    let comments = builder
-        .allocate_slice(context.drain_multi_line_comments(line_index(context, position.start())));
+fn is_special_form<A: Allocator + Clone>(
+    context: &Context<A>,
+    expressions: &[Expression<A>],
+    data: bool,
+) -> bool {
+    let (Some(style), [Expression::Symbol(name, _), ..]) = (context.options().style(), expressions)
+    else {
+        return false;
+    };
 
-    compile_all_comments(
-        context,
-        comments,
-        Some(line_index(context, position.start())),
-    )
+    !data && style.is_special_form(name)
 }
 
-fn compile_remaining_block_comment<'a, A: Allocator + Clone + 'a>(
-    context: &mut Context<'a, A>,
-) -> Document<'a> {
-    let builder = context.builder().clone();
-    let comments = builder.allocate_slice(context.drain_multi_line_comments(usize::MAX));
+fn argument_count<A: Allocator + Clone>(
+    context: &Context<A>,
+    expressions: &[Expression<A>],
+    data: bool,
+) -> Option<usize> {
+    let (true, Some(style), [Expression::Symbol(name, _), ..]) = (
+        context.options().keep_arguments(),
+        context.options().style(),
+        expressions,
+    ) else {
+        return None;
+    };
 
-    compile_all_comments(context, comments, None)
+    if data {
+        None
+    } else {
+        style.argument_count(name)
+    }
 }
 
-fn compile_all_comments<'a, A: Allocator + Clone + 'a>(
+// Reflow is based on a width of a list at its estimated column including
+// trailing right delimiters on the same line.
+fn reflow_index<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+    left: &str,
+    right: &str,
+    data: bool,
+) -> Option<usize> {
+    if !context.options().reflow() || expressions.is_empty() {
+        return None;
+    }
+
+    let single_line = !is_multi_line(context, expressions, position);
+    let (column, width) = context.layout(position.start());
+    let fits = measure_list_width(context, left, right, expressions)
+        .is_some_and(|list_width| column + list_width + width <= context.options().max_width());
+
+    if single_line && !fits {
+        // Keep the first arguments of special forms, such as bindings of `let`, on
+        // the first lines.
+        Some(if is_special_form(context, expressions, data) {
+            expressions.len().min(2)
+        } else {
+            1
+        })
+    } else if !single_line && fits && is_joinable(context, expressions, position) {
+        Some(expressions.len())
+    } else {
+        None
+    }
+}
+
+// A right delimiter on its own line is joined with a last element anyway.
+fn is_multi_line<A: Allocator + Clone>(
+    context: &Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+) -> bool {
+    expressions.last().is_some_and(|expression| {
+        line_index(context, position.start())
+            != line_index(context, expression.position().end() - 1)
+    })
+}
+
+// Lists with comments or blank lines are never joined into single lines.
+fn is_joinable<A: Allocator + Clone>(
+    context: &Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+) -> bool {
+    // Line gaps are not measured with comments as they may not be drained yet.
+    !context.has_comments(position)
+        && !context.has_line_comment(line_index(context, position.end() - 1))
+        && expressions.windows(2).all(|expressions| {
+            line_index(context, expressions[1].position().start())
+                - line_index(context, expressions[0].position().end() - 1)
+                <= 1
+        })
+}
+
+// Measures a width of a list on a single line. Widths of elements are cached
+// so that each of them is measured only once. Lists staying on multiple lines
+// have no widths.
+fn measure_list_width<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    left: &str,
+    right: &str,
+    expressions: &[Expression<A>],
+) -> Option<usize> {
+    expressions.iter().try_fold(
+        left.len() + right.len() + expressions.len().saturating_sub(1),
+        |width, expression| Some(width + measure_expression_width(context, expression)?),
+    )
+}
+
+fn measure_expression_width<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    expression: &Expression<A>,
+) -> Option<usize> {
+    let start = expression.position().start();
+
+    if let Some(width) = context.width(start) {
+        return width;
+    }
+
+    let width = match expression {
+        Expression::List(left, right, expressions, position) => {
+            if is_multi_line(context, expressions, position)
+                && !is_joinable(context, expressions, position)
+            {
+                None
+            } else {
+                measure_list_width(context, left, right, expressions)
+            }
+        }
+        Expression::Quote(sign, expression, _) => {
+            Some(sign.len() + measure_expression_width(context, expression)?)
+        }
+        expression => {
+            let string = expression.to_string();
+
+            (!string.contains('\n')).then(|| measure_width(&string, context.options().tab_width()))
+        }
+    };
+
+    context.set_width(start, width);
+
+    width
+}
+
+// Estimates columns of list elements and widths following them on the same
+// lines. Columns of elements on broken lines are their upper bounds.
+fn set_element_layouts<A: Allocator + Clone>(
+    context: &mut Context<A>,
+    expressions: &[Expression<A>],
+    position: &Position,
+    left: &str,
+    right: &str,
+    index: usize,
+    data: bool,
+) {
+    let (column, width) = context.layout(position.start());
+    let widths = expressions
+        .iter()
+        .map(|expression| measure_expression_width(context, expression).unwrap_or_default())
+        .collect::<Vec<_>>();
+    let body_column = column
+        + if is_aligned(context, &expressions[..index], data) {
+            left.len() + widths[0] + 1
+        } else if data {
+            left.len()
+        } else {
+            left.len().max(2)
+        };
+    let last_width = right.len() + width;
+    let mut element_column = column + left.len();
+
+    for (element_index, expression) in expressions.iter().enumerate() {
+        let start = expression.position().start();
+
+        if element_index < index {
+            let rest = &widths[element_index + 1..index];
+
+            context.set_layout(
+                start,
+                element_column,
+                rest.iter().sum::<usize>()
+                    + rest.len()
+                    + if index == expressions.len() {
+                        last_width
+                    } else {
+                        0
+                    },
+            );
+            element_column += widths[element_index] + 1;
+        } else {
+            context.set_layout(
+                start,
+                body_column,
+                if element_index + 1 == expressions.len() {
+                    last_width
+                } else {
+                    0
+                },
+            );
+        }
+    }
+}
+
+fn compile_expressions<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    expressions: &'a [Expression<'a, A>],
+    data: bool,
+) -> Document<'a> {
+    compile_expression_sequence(
+        context,
+        expressions,
+        data,
+        None,
+        context.options().preserve_breaks(),
+    )
+}
+
+// Compiles expressions with a fixed number of blank lines between consecutive
+// definitions if any. Expressions on the same lines are kept on them if line
+// breaks are preserved.
+fn compile_expression_sequence<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    expressions: &'a [Expression<'a, A>],
+    data: bool,
+    definition_blank_lines: Option<usize>,
+    preserve_breaks: bool,
+) -> Document<'a> {
+    let mut documents =
+        Vec::with_capacity_in(2 * expressions.len(), context.builder().allocator().clone());
+    let mut last_expression: Option<&Expression<A>> = None;
+
+    for expression in expressions {
+        if let Some(last_expression) = last_expression {
+            if preserve_breaks
+                && line_index(context, last_expression.position().end() - 1)
+                    == expression_line_index(context, last_expression.position().end(), expression)
+            {
+                documents.push(" ".into());
+            } else {
+                documents.push(line());
+                documents.push(match definition_blank_lines {
+                    Some(count)
+                        if is_definition(context, last_expression)
+                            && is_definition(context, expression) =>
+                    {
+                        context.builder().sequence(repeat_n(line(), count))
+                    }
+                    _ => {
+                        compile_blank_lines(context, line_gap(context, last_expression, expression))
+                    }
+                });
+            }
+        }
+
+        documents.push(compile_expression(context, expression, data));
+
+        last_expression = Some(expression);
+    }
+
+    sequence(documents.leak())
+}
+
+fn is_definition<A: Allocator + Clone>(context: &Context<A>, expression: &Expression<A>) -> bool {
+    matches!(
+        expression,
+        Expression::List(_, _, expressions, _)
+            if matches!(
+                expressions.first(),
+                Some(Expression::Symbol(name, _))
+                    if context.options().definition_symbols().iter().any(|symbol| symbol == name)
+            )
+    )
+}
+
+fn compile_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    position: &Position,
+    document: impl Fn(&mut Context<'a, A>) -> Document<'a>,
+) -> Document<'a> {
+    let block_comment = compile_block_comment(context, position);
+    let excess_comment = compile_excess_inline_comment(context, position);
+    let inline_comment = compile_inline_comment(context, position);
+    let inline_space = if is_empty(&inline_comment) {
+        empty()
+    } else {
+        " ".into()
+    };
+    let document = document(context);
+    let suffix_comment = compile_suffix_comment(context, position);
+
+    context.builder().sequence([
+        block_comment,
+        excess_comment,
+        inline_comment,
+        inline_space,
+        document,
+        suffix_comment,
+    ])
+}
+
+fn compile_inline_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    position: &Position,
+) -> Document<'a> {
+    let builder = context.builder().clone();
+    let count = context
+        .options()
+        .max_inline_comments()
+        .map(|count| count.max(1));
+
+    builder.sequence(
+        context
+            .drain_inline_comments(position)
+            .enumerate()
+            .map(|(index, comment)| {
+                builder.sequence([
+                    if index == 0 {
+                        empty()
+                    } else if count.is_some_and(|count| index % count == 0) {
+                        builder.r#break(line())
+                    } else {
+                        " ".into()
+                    },
+                    builder.sequence(["#|", comment.content(), "|#"]),
+                ])
+            }),
+    )
+}
+
+// Moves inline comments more than allowed on a line onto their own lines.
+fn compile_excess_inline_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    position: &Position,
+) -> Document<'a> {
+    let Some(count) = context.options().max_inline_comments() else {
+        return empty();
+    };
+    let builder = context.builder().clone();
+    let comments =
+        builder.allocate_slice(context.drain_excess_inline_comments(position, count.max(1)));
+
+    compile_all_comments(
+        context,
+        comments,
+        Some(line_index(context, position.start())),
+    )
+}
+
+fn compile_suffix_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<A>,
+    position: &Position,
+) -> Document<'a> {
+    let builder = context.builder().clone();
+    let options = context.options();
+
+    builder.sequence(
+        context
+            .drain_current_line_comment(line_index(context, position.start()))
+            .map(|comment| {
+                builder.line_suffixes(
+                    [" ", COMMENT_PREFIX]
+                        .into_iter()
+                        .chain(split_line_comment(options, comment.content())),
+                )
+            }),
+    )
+}
+
+fn compile_block_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+    position: &Position,
+) -> Document<'a> {
+    let builder = context.builder().clone();
+    let comments = builder
+        .allocate_slice(context.drain_multi_line_comments(line_index(context, position.start())));
+
+    compile_all_comments(
+        context,
+        comments,
+        Some(line_index(context, position.start())),
+    )
+}
+
+fn compile_remaining_block_comment<'a, A: Allocator + Clone + 'a>(
+    context: &mut Context<'a, A>,
+) -> Document<'a> {
+    let builder = context.builder().clone();
+    let comments = builder.allocate_slice(context.drain_multi_line_comments(usize::MAX));
+
+    compile_all_comments(context, comments, None)
+}
+
+fn compile_all_comments<'a, A: Allocator + Clone + 'a>(
     context: &Context<A>,
     comments: &'a [&'a Comment<'a>],
     last_line_index: Option<usize>,
 ) -> Document<'a> {
-    context.builder().sequence(
-        comments
-            .iter()
-            .zip(
-                comments
-                    .iter()
-                    .skip(1)
-                    .map(|comment| line_index(context, comment.position().start()))
-                    .chain([last_line_index.unwrap_or(0)]),
-            )
-            .map(|(comment, next_line_index)| match comment {
+    let max_inline_count = context.options().max_inline_comments();
+    // A number of one-line block comments preceding a current one on a line.
+    let mut inline_count = 0;
+
+    context
+        .builder()
+        .sequence(comments.iter().enumerate().map(move |(index, comment)| {
+            let next_comment = comments.get(index + 1);
+            let next_line_index = next_comment
+                .map(|comment| line_index(context, comment.position().start()))
+                .unwrap_or(last_line_index.unwrap_or(0));
+            let gap =
+                next_line_index.saturating_sub(line_index(context, comment.position().end() - 1));
+            let blank_line = compile_blank_lines(
+                context,
+                if next_comment.is_some_and(|comment| is_header_comment(context, comment)) {
+                    gap.max(2)
+                } else {
+                    gap
+                },
+            );
+
+            match comment {
+                // One-line block comments are kept on single lines up to a limit on
+                // each line if inline comments are limited.
+                Comment::Block(comment)
+                    if max_inline_count.is_some() && !comment.content().contains('\n') =>
+                {
+                    let joined = next_comment.is_some_and(|next| {
+                        matches!(next, Comment::Block(next) if !next.content().contains('\n'))
+                            && line_index(context, next.position().start())
+                                == line_index(context, comment.position().end() - 1)
+                    }) && max_inline_count
+                        .is_some_and(|count| inline_count + 1 < count);
+
+                    inline_count = if joined { inline_count + 1 } else { 0 };
+
+                    context.builder().sequence([
+                        context.builder().strings(["#|", comment.content(), "|#"]),
+                        if joined {
+                            " ".into()
+                        } else {
+                            context.builder().sequence([line(), blank_line])
+                        },
+                    ])
+                }
                 Comment::Block(comment) => context.builder().sequence([
                     "#|".into(),
                     line(),
-                    comment.content().trim().into(),
+                    compile_block_comment_content(
+                        context,
+                        comment.content(),
+                        comment.position().start(),
+                    ),
                     line(),
                     "|#".into(),
                     line(),
-                    if line_index(context, comment.position().end() - 1) + 1 < next_line_index {
-                        line()
-                    } else {
-                        empty()
-                    },
+                    blank_line,
                 ]),
                 Comment::Line(comment) => context.builder().sequence([
-                    COMMENT_PREFIX.into(),
-                    comment.content().trim_end().into(),
+                    context.builder().strings(
+                        [COMMENT_PREFIX]
+                            .into_iter()
+                            .chain(split_line_comment(context.options(), comment.content())),
+                    ),
                     context.builder().r#break(line()),
-                    if line_index(context, comment.position().end() - 1) + 1 < next_line_index {
-                        line()
-                    } else {
-                        empty()
-                    },
+                    blank_line,
                 ]),
-            }),
-    )
+            }
+        }))
 }
 
-fn line_gap<A: Allocator + Clone>(
+// Splits content of a line comment into leading semicolons, a space, and the
+// rest if a space is normalized.
+fn split_line_comment<'a>(options: &Options, content: &'a str) -> [&'a str; 3] {
+    if !options.comment_space() {
+        return [content.trim_end(), "", ""];
+    }
+
+    let rest = content.trim_start_matches(';');
+    let semicolons = &content[..content.len() - rest.len()];
+    let rest = rest.trim();
+
+    [semicolons, if rest.is_empty() { "" } else { " " }, rest]
+}
+
+fn compile_block_comment_content<'a, A: Allocator + Clone + 'a>(
     context: &Context<A>,
-    last_expression: &Expression<A>,
-    expression: &Expression<A>,
-) -> usize {
-    let index = line_index(context, expression.position().start());
+    content: &'a str,
+    start: usize,
+) -> Document<'a> {
+    if context.options().verbatim_block_comments() {
+        return compile_verbatim_block_comment_content(context, content, start);
+    }
+
+    let content = content.trim();
+
+    if context.options().indent_block_comments() {
+        compile_indented_block_comment_content(context, content)
+    } else if context.options().trim_block_comments() {
+        context.builder().strings(
+            content
+                .lines()
+                .flat_map(|line| ["\n", line.trim_end()])
+                .skip(1),
+        )
+    } else {
+        content.into()
+    }
+}
+
+// Re-indents lines in a block comment to its column removing their common
+// indentation.
+fn compile_indented_block_comment_content<'a, A: Allocator + Clone + 'a>(
+    context: &Context<A>,
+    content: &'a str,
+) -> Document<'a> {
+    let indent = content
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
 
     context
-        .peek_comments(index)
-        .next()
-        .map(|comment| line_index(context, comment.position().start()))
-        .unwrap_or(index)
-        .saturating_sub(line_index(context, last_expression.position().end() - 1))
+        .builder()
+        .sequence(content.lines().enumerate().map(|(index, string)| {
+            let string = if index == 0 {
+                string
+            } else {
+                string.get(indent..).unwrap_or_else(|| string.trim_start())
+            };
+
+            context.builder().sequence([
+                if index == 0 { empty() } else { line() },
+                if context.options().trim_block_comments() {
+                    string.trim_end()
+                } else {
+                    string
+                }
+                .into(),
+            ])
+        }))
 }
 
-fn line_index<A: Allocator + Clone>(context: &Context<A>, offset: usize) -> usize {
+// Keeps lines in a block comment verbatim except their common indentation up
+// to its column so that they are indented relative to its new column.
+fn compile_verbatim_block_comment_content<'a, A: Allocator + Clone + 'a>(
+    context: &Context<A>,
+    content: &'a str,
+    start: usize,
+) -> Document<'a> {
+    let column = start
+        - context
+            .position_map()
+            .line_range(start)
+            .expect("valid offset")
+            .start;
+    // A first line is not indented if it follows `#|` on the same line.
+    let inline = content
+        .split('\n')
+        .next()
+        .is_some_and(|line| !line.trim().is_empty());
+    let content = trim_blank_lines(content);
+    let indent = content
+        .split('\n')
+        .skip(usize::from(inline))
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default()
+        .min(column);
+
     context
-        .position_map()
-        .line_index(offset)
-        .expect("valid offset")
+        .builder()
+        .sequence(content.split('\n').enumerate().map(|(index, string)| {
+            let string = if index == 0 && inline {
+                string
+            } else {
+                string.get(indent..).unwrap_or_else(|| string.trim_start())
+            };
+
+            context.builder().sequence([
+                if index == 0 { empty() } else { line() },
+                if context.options().trim_block_comments() {
+                    string.trim_end()
+                } else {
+                    string
+                }
+                .into(),
+            ])
+        }))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        ast::{BlockComment, LineComment},
-        position::Position,
-        position_map::PositionMap,
-    };
-    use indoc::indoc;
-    use pretty_assertions::assert_eq;
-    use std::alloc::Global;
+fn trim_blank_lines(mut content: &str) -> &str {
+    while let Some((line, rest)) = content.split_once('\n') {
+        if !line.trim().is_empty() {
+            break;
+        }
+
+        content = rest;
+    }
+
+    while let Some((rest, line)) = content.rsplit_once('\n') {
+        if !line.trim().is_empty() {
+            break;
+        }
+
+        content = rest;
+    }
+
+    if content.trim().is_empty() {
+        ""
+    } else {
+        content
+    }
+}
+
+fn compile_blank_lines<'a, A: Allocator + Clone + 'a>(
+    context: &Context<A>,
+    line_gap: usize,
+) -> Document<'a> {
+    context.builder().sequence(repeat_n(
+        line(),
+        line_gap
+            .saturating_sub(1)
+            .min(context.options().max_blank_lines()),
+    ))
+}
+
+fn line_gap<A: Allocator + Clone>(
+    context: &Context<A>,
+    last_expression: &Expression<A>,
+    expression: &Expression<A>,
+) -> usize {
+    let index = line_index(context, expression.position().start());
+    let comment = context.peek_comments(index).next();
+    let gap = comment
+        .map(|comment| line_index(context, comment.position().start()))
+        .unwrap_or(index)
+        .saturating_sub(line_index(context, last_expression.position().end() - 1));
+
+    if comment.is_some_and(|comment| is_header_comment(context, comment)) {
+        gap.max(2)
+    } else {
+        gap
+    }
+}
+
+// A section header comment starts with exactly three semicolons.
+fn is_header_comment<A: Allocator + Clone>(context: &Context<A>, comment: &Comment) -> bool {
+    context.options().header_blank_line()
+        && matches!(
+            comment,
+            Comment::Line(comment)
+                if comment.content().starts_with(";;") && !comment.content().starts_with(";;;")
+        )
+}
+
+fn line_index<A: Allocator + Clone>(context: &Context<A>, offset: usize) -> usize {
+    context.line_index(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{BlockComment, LineComment},
+        position::Position,
+        position_map::PositionMap,
+    };
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use std::alloc::Global;
+
+    #[test]
+    fn format_empty() {
+        assert_eq!(
+            format(
+                &[],
+                &[],
+                &[],
+                &PositionMap::new("\n"),
+                &Default::default(),
+                Global
+            )
+            .unwrap(),
+            "\n"
+        );
+    }
+
+    #[test]
+    fn format_list() {
+        assert_eq!(
+            format(
+                &[Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(0, 2)),
+                        Expression::Symbol("bar", Position::new(0, 2))
+                    ],
+                    Position::new(0, 2)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("(foo bar)"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "(foo bar)\n"
+        );
+    }
+
+    #[test]
+    fn format_list_with_split_lines() {
+        assert_eq!(
+            format(
+                &[Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(1, 4)),
+                        Expression::Symbol("bar", Position::new(5, 8))
+                    ],
+                    Position::new(0, 9)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("(foo\nbar)"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            indoc!(
+                "
+                (foo
+                  bar)
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_list_with_split_lines_and_multiple_elements() {
+        assert_eq!(
+            format(
+                &[Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(0, 1)),
+                        Expression::Symbol("bar", Position::new(0, 1)),
+                        Expression::Symbol("baz", Position::new(2, 3)),
+                        Expression::Symbol("qux", Position::new(2, 3)),
+                    ],
+                    Position::new(0, 1)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("a\nb"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            indoc!(
+                "
+                (foo bar
+                  baz
+                  qux)
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_quote() {
+        assert_eq!(
+            format(
+                &[Expression::Quote(
+                    "'",
+                    Expression::Symbol("foo", Position::new(0, 3)).into(),
+                    Position::new(0, 3)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("'foo"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "'foo\n"
+        );
+    }
+
+    #[test]
+    fn format_unquote() {
+        assert_eq!(
+            format(
+                &[Expression::Quote(
+                    ",",
+                    Expression::Symbol("foo", Position::new(0, 3)).into(),
+                    Position::new(0, 3)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("'foo"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            ",foo\n"
+        );
+    }
+
+    #[test]
+    fn format_string() {
+        assert_eq!(
+            format(
+                &[Expression::String("foo", Position::new(0, 3))],
+                &[],
+                &[],
+                &PositionMap::new("\"foo\""),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "\"foo\"\n"
+        );
+    }
+
+    #[test]
+    fn format_multi_line_string() {
+        assert_eq!(
+            format(
+                &[Expression::String("a\\\nb", Position::new(0, 6))],
+                &[],
+                &[],
+                &PositionMap::new("\"a\\\nb\""),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "\"a\\\nb\"\n"
+        );
+    }
+
+    #[test]
+    fn format_multi_line_string_in_nested_list() {
+        assert_eq!(
+            format(
+                &[Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(1, 4)),
+                        Expression::List(
+                            "(",
+                            ")",
+                            vec![
+                                Expression::Symbol("bar", Position::new(6, 9)),
+                                Expression::String("a\n  b", Position::new(10, 17)),
+                            ],
+                            Position::new(5, 18)
+                        ),
+                    ],
+                    Position::new(0, 19)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("(foo\n(bar\n\"a\n  b\"))"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            indoc!(
+                "
+                (foo
+                  (bar
+                    \"a
+                  b\"))
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_symbol() {
+        assert_eq!(
+            format(
+                &[Expression::Symbol("foo", Position::new(0, 3))],
+                &[],
+                &[],
+                &PositionMap::new("foo"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "foo\n"
+        );
+    }
+
+    #[test]
+    fn format_quoted_symbol() {
+        assert_eq!(
+            format(
+                &[Expression::QuotedSymbol("foo", Position::new(0, 3))],
+                &[],
+                &[],
+                &PositionMap::new("foo"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "|foo|\n"
+        );
+    }
+
+    #[test]
+    fn format_vector() {
+        assert_eq!(
+            format(
+                &[Expression::List(
+                    "[",
+                    "]",
+                    vec![
+                        Expression::Symbol("foo", Position::new(0, 2)),
+                        Expression::Symbol("bar", Position::new(0, 2))
+                    ],
+                    Position::new(0, 2)
+                )],
+                &[],
+                &[],
+                &PositionMap::new("[foo bar]"),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "[foo bar]\n"
+        );
+    }
+
+    mod brace_space {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_list(left: &str, right: &str, source: &str, options: &Options) -> String {
+            format(
+                &[Expression::List(
+                    left,
+                    right,
+                    vec![
+                        Expression::Symbol("foo", Position::new(1, 4)),
+                        Expression::Symbol("bar", Position::new(5, 8)),
+                    ],
+                    Position::new(0, 9),
+                )],
+                &[],
+                &[],
+                &PositionMap::new(source),
+                options,
+                Global,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn format_map_without_space() {
+            assert_eq!(
+                format_list("{", "}", "{foo bar}", &Options::new()),
+                "{foo bar}\n"
+            );
+        }
+
+        #[test]
+        fn format_map_with_space() {
+            assert_eq!(
+                format_list("{", "}", "{foo bar}", &Options::new().set_brace_space(true)),
+                "{ foo bar }\n"
+            );
+        }
+
+        #[test]
+        fn format_broken_map_with_space() {
+            assert_eq!(
+                format_list(
+                    "{",
+                    "}",
+                    "{foo\nbar}",
+                    &Options::new().set_brace_space(true)
+                ),
+                indoc!(
+                    "
+                    { foo
+                      bar }
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_empty_map_with_space() {
+            assert_eq!(
+                format(
+                    &[Expression::List("{", "}", vec![], Position::new(0, 2))],
+                    &[],
+                    &[],
+                    &PositionMap::new("{}"),
+                    &Options::new().set_brace_space(true),
+                    Global,
+                )
+                .unwrap(),
+                "{}\n"
+            );
+        }
+
+        #[test]
+        fn format_list_with_space() {
+            assert_eq!(
+                format_list("(", ")", "(foo bar)", &Options::new().set_brace_space(true)),
+                "(foo bar)\n"
+            );
+        }
+    }
+
+    mod comment_space {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_comment(content: &str, comment_space: bool) -> String {
+            format(
+                &[],
+                &[LineComment::new(content, Position::new(0, content.len() + 1)).into()],
+                &[],
+                &PositionMap::new(&format!(";{}\n", content)),
+                &Options::new().set_comment_space(comment_space),
+                Global,
+            )
+            .unwrap()
+        }
+
+        fn format_suffix_comment(content: &str, comment_space: bool) -> String {
+            format(
+                &[Expression::Symbol("foo", Position::new(0, 3))],
+                &[LineComment::new(content, Position::new(4, content.len() + 5)).into()],
+                &[],
+                &PositionMap::new(&format!("foo ;{}\n", content)),
+                &Options::new().set_comment_space(comment_space),
+                Global,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn preserve_spaces() {
+            assert_eq!(format_comment("foo", false), ";foo\n");
+            assert_eq!(format_comment(" foo", false), "; foo\n");
+            assert_eq!(format_comment("", false), ";\n");
+        }
+
+        #[test]
+        fn insert_space() {
+            assert_eq!(format_comment("foo", true), "; foo\n");
+            assert_eq!(format_comment(" foo", true), "; foo\n");
+            assert_eq!(format_comment("  \tfoo", true), "; foo\n");
+        }
+
+        #[test]
+        fn insert_space_after_semicolons() {
+            assert_eq!(format_comment(";;foo", true), ";;; foo\n");
+            assert_eq!(format_comment(";; foo", true), ";;; foo\n");
+        }
+
+        #[test]
+        fn keep_empty_comment() {
+            assert_eq!(format_comment("", true), ";\n");
+            assert_eq!(format_comment(";;", true), ";;;\n");
+            assert_eq!(format_comment("  ", true), ";\n");
+        }
+
+        #[test]
+        fn insert_space_in_suffix_comment() {
+            assert_eq!(format_suffix_comment("foo", false), "foo ;foo\n");
+            assert_eq!(format_suffix_comment("foo", true), "foo ; foo\n");
+            assert_eq!(format_suffix_comment(" foo", true), "foo ; foo\n");
+            assert_eq!(format_suffix_comment("", true), "foo ;\n");
+        }
+    }
+
+    mod right_delimiter_position {
+        use super::*;
+        use crate::{options::Dialect, parse::parse};
+        use pretty_assertions::assert_eq;
+
+        fn find_right_position(source: &str) -> (Position, Position) {
+            let expressions = parse(source, Dialect::Scheme, Global).unwrap();
+            let mut expression = &expressions[0];
+
+            while let Expression::Quote(_, quoted, _) = expression {
+                expression = quoted;
+            }
+
+            let Expression::List(_, right, expressions, position) = expression else {
+                panic!("list expected");
+            };
+
+            (
+                right_delimiter_position(position, expressions, right),
+                position.clone(),
+            )
+        }
+
+        #[test]
+        fn find_parenthesis() {
+            assert_eq!(
+                find_right_position("(foo bar)"),
+                (Position::new(8, 9), Position::new(0, 9))
+            );
+        }
+
+        #[test]
+        fn find_parenthesis_of_empty_list() {
+            assert_eq!(
+                find_right_position("()"),
+                (Position::new(1, 2), Position::new(0, 2))
+            );
+        }
+
+        #[test]
+        fn find_parenthesis_after_comment() {
+            assert_eq!(
+                find_right_position("(foo #|bar|# )"),
+                (Position::new(13, 14), Position::new(0, 14))
+            );
+        }
+
+        #[test]
+        fn find_parenthesis_of_vector() {
+            assert_eq!(
+                find_right_position("#(foo bar)"),
+                (Position::new(9, 10), Position::new(1, 10))
+            );
+        }
+
+        #[test]
+        fn find_bracket() {
+            assert_eq!(
+                find_right_position("[foo (bar)]"),
+                (Position::new(10, 11), Position::new(0, 11))
+            );
+        }
+
+        #[test]
+        fn never_overlap_last_element() {
+            assert_eq!(
+                right_delimiter_position(
+                    &Position::new(0, 5),
+                    &[Expression::<Global>::Symbol("foo", Position::new(1, 5))],
+                    "))"
+                ),
+                Position::new(5, 5)
+            );
+        }
+    }
+
+    mod hex_case {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_character(name: &str, hex_case: HexCase) -> String {
+            format(
+                &[Expression::Quote(
+                    "#",
+                    Expression::Symbol(name, Position::new(1, 1 + name.len())).into(),
+                    Position::new(0, 1 + name.len()),
+                )],
+                &[],
+                &[],
+                &PositionMap::new(&format!("#{}", name)),
+                &Options::new().set_hex_case(hex_case),
+                Global,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn preserve_hex_character() {
+            assert_eq!(format_character("\\x4a", HexCase::Preserve), "#\\x4a\n");
+            assert_eq!(format_character("\\x4A", HexCase::Preserve), "#\\x4A\n");
+        }
+
+        #[test]
+        fn lowercase_hex_character() {
+            assert_eq!(format_character("\\x4A", HexCase::Lower), "#\\x4a\n");
+        }
+
+        #[test]
+        fn uppercase_hex_character() {
+            assert_eq!(format_character("\\x4a", HexCase::Upper), "#\\x4A\n");
+            assert_eq!(format_character("\\x41", HexCase::Upper), "#\\x41\n");
+        }
+
+        #[test]
+        fn keep_named_characters() {
+            for name in ["\\space", "\\x", "\\xyz", "\\a"] {
+                for hex_case in [HexCase::Lower, HexCase::Upper] {
+                    assert_eq!(format_character(name, hex_case), format!("#{}\n", name));
+                }
+            }
+        }
+
+        #[test]
+        fn keep_hex_number() {
+            assert_eq!(format_character("xAb", HexCase::Lower), "#xAb\n");
+        }
+    }
+
+    mod quote_style {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_quote(sign: &str, quote_style: QuoteStyle) -> String {
+            format(
+                &[Expression::Quote(
+                    sign,
+                    Expression::Symbol("foo", Position::new(0, 1)).into(),
+                    Position::new(0, 1),
+                )],
+                &[],
+                &[],
+                &PositionMap::new("foo"),
+                &Options::new().set_quote_style(quote_style),
+                Global,
+            )
+            .unwrap()
+        }
+
+        fn format_full_quote(name: &str, quote_style: QuoteStyle) -> String {
+            format(
+                &[Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol(name, Position::new(0, 1)),
+                        Expression::Symbol("foo", Position::new(0, 1)),
+                    ],
+                    Position::new(0, 1),
+                )],
+                &[],
+                &[],
+                &PositionMap::new("foo"),
+                &Options::new().set_quote_style(quote_style),
+                Global,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn preserve_quotes() {
+            assert_eq!(format_quote("'", QuoteStyle::Preserve), "'foo\n");
+            assert_eq!(
+                format_full_quote("quote", QuoteStyle::Preserve),
+                "(quote foo)\n"
+            );
+        }
+
+        #[test]
+        fn expand_quotes() {
+            for (sign, name) in QUOTE_NAMES {
+                assert_eq!(
+                    format_quote(sign, QuoteStyle::Full),
+                    std::format!("({} foo)\n", name)
+                );
+            }
+        }
+
+        #[test]
+        fn expand_splicing_unquote() {
+            assert_eq!(
+                format(
+                    &[Expression::Quote(
+                        ",@",
+                        Expression::Symbol("foo", Position::new(0, 1)).into(),
+                        Position::new(0, 1),
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("foo"),
+                    &Options::new().set_quote_style(QuoteStyle::Full),
+                    Global,
+                )
+                .unwrap(),
+                "(unquote-splicing foo)\n"
+            );
+        }
+
+        #[test]
+        fn abbreviate_quotes() {
+            for (sign, name) in QUOTE_NAMES {
+                assert_eq!(
+                    format_full_quote(name, QuoteStyle::Abbreviation),
+                    std::format!("{}foo\n", sign)
+                );
+            }
+        }
+
+        #[test]
+        fn keep_other_quotes() {
+            assert_eq!(format_quote("#", QuoteStyle::Full), "#foo\n");
+            assert_eq!(
+                format_full_quote("foo", QuoteStyle::Abbreviation),
+                "(foo foo)\n"
+            );
+        }
+
+        #[test]
+        fn keep_quote_with_multiple_arguments() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("quote", Position::new(0, 1)),
+                            Expression::Symbol("foo", Position::new(0, 1)),
+                            Expression::Symbol("bar", Position::new(0, 1)),
+                        ],
+                        Position::new(0, 1),
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("foo"),
+                    &Options::new().set_quote_style(QuoteStyle::Abbreviation),
+                    Global,
+                )
+                .unwrap(),
+                "(quote foo bar)\n"
+            );
+        }
 
-    #[test]
-    fn format_empty() {
-        assert_eq!(
-            format(&[], &[], &[], &PositionMap::new("\n"), Global).unwrap(),
-            "\n"
-        );
+        #[test]
+        fn keep_quote_in_brackets() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "[",
+                        "]",
+                        vec![
+                            Expression::Symbol("quote", Position::new(0, 1)),
+                            Expression::Symbol("foo", Position::new(0, 1)),
+                        ],
+                        Position::new(0, 1),
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("foo"),
+                    &Options::new().set_quote_style(QuoteStyle::Abbreviation),
+                    Global,
+                )
+                .unwrap(),
+                "[quote foo]\n"
+            );
+        }
     }
 
-    #[test]
-    fn format_list() {
-        assert_eq!(
+    mod style {
+        use super::*;
+        use crate::options::Style;
+        use pretty_assertions::assert_eq;
+
+        fn format_list(head: &str, style: Option<Style>) -> String {
+            let source = format!("({} foo\nbar)", head);
+            let end = source.len();
+
             format(
                 &[Expression::List(
                     "(",
                     ")",
                     vec![
-                        Expression::Symbol("foo", Position::new(0, 2)),
-                        Expression::Symbol("bar", Position::new(0, 2))
+                        Expression::Symbol(head, Position::new(1, head.len() + 1)),
+                        Expression::Symbol("foo", Position::new(end - 8, end - 5)),
+                        Expression::Symbol("bar", Position::new(end - 4, end - 1)),
                     ],
-                    Position::new(0, 2)
+                    Position::new(0, end),
                 )],
                 &[],
                 &[],
-                &PositionMap::new("(foo bar)"),
+                &PositionMap::new(&source),
+                &Options::new().set_style(style),
                 Global,
             )
-            .unwrap(),
-            "(foo bar)\n"
-        );
+            .unwrap()
+        }
+
+        #[test]
+        fn indent_without_style() {
+            assert_eq!(format_list("f", None), "(f foo\n  bar)\n");
+        }
+
+        #[test]
+        fn align_arguments() {
+            assert_eq!(format_list("f", Some(Style::R7rs)), "(f foo\n   bar)\n");
+        }
+
+        #[test]
+        fn indent_special_form() {
+            assert_eq!(format_list("let", Some(Style::R7rs)), "(let foo\n  bar)\n");
+            assert_eq!(format_list("let", Some(Style::Guile)), "(let foo\n  bar)\n");
+        }
+
+        #[test]
+        fn indent_special_form_in_preset() {
+            assert_eq!(
+                format_list("define-public", Some(Style::Guile)),
+                "(define-public foo\n  bar)\n"
+            );
+            assert_eq!(
+                format_list("define-public", Some(Style::R7rs)),
+                "(define-public foo\n               bar)\n"
+            );
+        }
+
+        #[test]
+        fn indent_data() {
+            let source = "'(f foo\nbar)";
+
+            assert_eq!(
+                format(
+                    &[Expression::Quote(
+                        "'",
+                        Expression::List(
+                            "(",
+                            ")",
+                            vec![
+                                Expression::Symbol("f", Position::new(2, 3)),
+                                Expression::Symbol("foo", Position::new(4, 7)),
+                                Expression::Symbol("bar", Position::new(8, 11)),
+                            ],
+                            Position::new(1, 12),
+                        )
+                        .into(),
+                        Position::new(0, 12),
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new(source),
+                    &Options::new().set_style(Some(Style::R7rs)),
+                    Global,
+                )
+                .unwrap(),
+                "'(f foo\n  bar)\n"
+            );
+        }
     }
 
-    #[test]
-    fn format_list_with_split_lines() {
-        assert_eq!(
+    mod reflow {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_list(source: &str, options: &Options) -> String {
             format(
                 &[Expression::List(
                     "(",
                     ")",
                     vec![
                         Expression::Symbol("foo", Position::new(1, 4)),
-                        Expression::Symbol("bar", Position::new(5, 8))
+                        Expression::Symbol("bar", Position::new(5, 8)),
+                        Expression::Symbol("baz", Position::new(9, 12)),
                     ],
-                    Position::new(0, 9)
+                    Position::new(0, 13),
                 )],
                 &[],
                 &[],
-                &PositionMap::new("(foo\nbar)"),
+                &PositionMap::new(source),
+                options,
                 Global,
             )
-            .unwrap(),
-            indoc!(
-                "
-                (foo
-                  bar)
-                "
-            )
-        );
+            .unwrap()
+        }
+
+        #[test]
+        fn keep_long_list_without_reflow() {
+            assert_eq!(
+                format_list("(foo bar baz)", &Options::new().set_max_width(10)),
+                "(foo bar baz)\n"
+            );
+        }
+
+        #[test]
+        fn break_long_list() {
+            assert_eq!(
+                format_list(
+                    "(foo bar baz)",
+                    &Options::new().set_reflow(true).set_max_width(10)
+                ),
+                indoc!(
+                    "
+                    (foo
+                      bar
+                      baz)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_short_list() {
+            assert_eq!(
+                format_list(
+                    "(foo bar baz)",
+                    &Options::new().set_reflow(true).set_max_width(13)
+                ),
+                "(foo bar baz)\n"
+            );
+        }
+
+        #[test]
+        fn join_short_list() {
+            assert_eq!(
+                format_list(
+                    "(foo\nbar\nbaz)",
+                    &Options::new().set_reflow(true).set_max_width(13)
+                ),
+                "(foo bar baz)\n"
+            );
+        }
+
+        #[test]
+        fn keep_long_broken_list() {
+            assert_eq!(
+                format_list(
+                    "(foo\nbar\nbaz)",
+                    &Options::new().set_reflow(true).set_max_width(10)
+                ),
+                indoc!(
+                    "
+                    (foo
+                      bar
+                      baz)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_short_list_with_comment() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("foo", Position::new(1, 4)),
+                            Expression::Symbol("bar", Position::new(10, 13)),
+                        ],
+                        Position::new(0, 14),
+                    )],
+                    &[LineComment::new(" baz", Position::new(5, 9)).into()],
+                    &[],
+                    &PositionMap::new("(foo ;baz\nbar)"),
+                    &Options::new().set_reflow(true),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    (foo ; baz
+                      bar)
+                    "
+                )
+            );
+        }
     }
 
-    #[test]
-    fn format_list_with_split_lines_and_multiple_elements() {
-        assert_eq!(
+    mod max_inline_elements {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_list(options: &Options) -> String {
             format(
                 &[Expression::List(
                     "(",
                     ")",
                     vec![
-                        Expression::Symbol("foo", Position::new(0, 1)),
-                        Expression::Symbol("bar", Position::new(0, 1)),
-                        Expression::Symbol("baz", Position::new(2, 3)),
-                        Expression::Symbol("qux", Position::new(2, 3)),
+                        Expression::Symbol("foo", Position::new(1, 4)),
+                        Expression::Symbol("bar", Position::new(5, 8)),
+                        Expression::Symbol("baz", Position::new(9, 12)),
                     ],
-                    Position::new(0, 1)
+                    Position::new(0, 13),
                 )],
                 &[],
                 &[],
-                &PositionMap::new("a\nb"),
+                &PositionMap::new("(foo bar baz)"),
+                options,
                 Global,
             )
-            .unwrap(),
-            indoc!(
-                "
-                (foo bar
-                  baz
-                  qux)
-                "
-            )
-        );
-    }
+            .unwrap()
+        }
 
-    #[test]
-    fn format_quote() {
-        assert_eq!(
-            format(
-                &[Expression::Quote(
-                    "'",
-                    Expression::Symbol("foo", Position::new(0, 3)).into(),
-                    Position::new(0, 3)
-                )],
-                &[],
-                &[],
-                &PositionMap::new("'foo"),
-                Global,
-            )
-            .unwrap(),
-            "'foo\n"
-        );
-    }
+        #[test]
+        fn keep_list_without_limit() {
+            assert_eq!(format_list(&Options::new()), "(foo bar baz)\n");
+        }
 
-    #[test]
-    fn format_unquote() {
-        assert_eq!(
-            format(
-                &[Expression::Quote(
-                    ",",
-                    Expression::Symbol("foo", Position::new(0, 3)).into(),
-                    Position::new(0, 3)
-                )],
-                &[],
-                &[],
-                &PositionMap::new("'foo"),
-                Global,
-            )
-            .unwrap(),
-            ",foo\n"
-        );
-    }
+        #[test]
+        fn keep_list_at_limit() {
+            assert_eq!(
+                format_list(&Options::new().set_max_inline_elements(Some(3))),
+                "(foo bar baz)\n"
+            );
+        }
 
-    #[test]
-    fn format_string() {
-        assert_eq!(
-            format(
-                &[Expression::String("foo", Position::new(0, 3))],
-                &[],
-                &[],
-                &PositionMap::new("\"foo\""),
-                Global,
-            )
-            .unwrap(),
-            "\"foo\"\n"
-        );
+        #[test]
+        fn break_list_over_limit() {
+            assert_eq!(
+                format_list(&Options::new().set_max_inline_elements(Some(2))),
+                indoc!(
+                    "
+                    (foo
+                      bar
+                      baz)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn break_list_over_limit_with_reflow() {
+            assert_eq!(
+                format_list(
+                    &Options::new()
+                        .set_reflow(true)
+                        .set_max_inline_elements(Some(2))
+                ),
+                indoc!(
+                    "
+                    (foo
+                      bar
+                      baz)
+                    "
+                )
+            );
+        }
     }
 
-    #[test]
-    fn format_multi_line_string() {
-        assert_eq!(
-            format(
-                &[Expression::String("a\\\nb", Position::new(0, 6))],
-                &[],
-                &[],
-                &PositionMap::new("\"a\\\nb\""),
-                Global,
-            )
-            .unwrap(),
-            "\"a\\\nb\"\n"
-        );
-    }
+    mod preserve_breaks {
+        use super::*;
+        use crate::{
+            options::{Dialect, Style},
+            parse::parse,
+        };
+        use pretty_assertions::assert_eq;
 
-    #[test]
-    fn format_symbol() {
-        assert_eq!(
+        fn format_source(source: &str, options: Options) -> String {
             format(
-                &[Expression::Symbol("foo", Position::new(0, 3))],
+                &parse(source, Dialect::Scheme, Global).unwrap(),
                 &[],
                 &[],
-                &PositionMap::new("foo"),
+                &PositionMap::new(source),
+                &options.set_preserve_breaks(true),
                 Global,
             )
-            .unwrap(),
-            "foo\n"
-        );
-    }
+            .unwrap()
+        }
 
-    #[test]
-    fn format_quoted_symbol() {
-        assert_eq!(
-            format(
-                &[Expression::QuotedSymbol("foo", Position::new(0, 3))],
-                &[],
-                &[],
-                &PositionMap::new("foo"),
-                Global,
-            )
-            .unwrap(),
-            "|foo|\n"
-        );
+        #[test]
+        fn keep_list_on_single_line() {
+            assert_eq!(
+                format_source("(foo  bar baz)", Options::new()),
+                "(foo bar baz)\n"
+            );
+        }
+
+        #[test]
+        fn keep_elements_on_same_lines() {
+            assert_eq!(
+                format_source("(foo bar\n baz qux\n quux)", Options::new()),
+                indoc!(
+                    "
+                    (foo bar
+                      baz qux
+                      quux)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_element_after_multi_line_element() {
+            assert_eq!(
+                format_source("(foo (bar\n baz) qux\n quux)", Options::new()),
+                indoc!(
+                    "
+                    (foo (bar
+                          baz) qux
+                      quux)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_blank_lines() {
+            assert_eq!(
+                format_source("(foo\n\n bar baz)", Options::new()),
+                indoc!(
+                    "
+                    (foo
+
+                      bar baz)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_long_list_with_reflow() {
+            let source = "(foo bar baz qux)\n";
+
+            assert_eq!(
+                format_source(source, Options::new().set_reflow(true).set_max_width(8)),
+                source
+            );
+        }
+
+        #[test]
+        fn keep_broken_short_list_with_reflow() {
+            let source = "(foo\n  bar)\n";
+
+            assert_eq!(
+                format_source(source, Options::new().set_reflow(true)),
+                source
+            );
+        }
+
+        #[test]
+        fn keep_list_over_inline_element_limit() {
+            let source = "(foo bar baz)\n";
+
+            assert_eq!(
+                format_source(source, Options::new().set_max_inline_elements(Some(2))),
+                source
+            );
+        }
+
+        #[test]
+        fn keep_arguments_on_their_own_lines() {
+            let source = "(define\n  (foo x)\n  x)\n";
+
+            assert_eq!(
+                format_source(
+                    source,
+                    Options::new()
+                        .set_style(Some(Style::R7rs))
+                        .set_keep_arguments(true)
+                ),
+                source
+            );
+        }
     }
 
-    #[test]
-    fn format_vector() {
-        assert_eq!(
+    mod quote_indent {
+        use super::*;
+        use crate::{options::Dialect, parse::parse};
+        use pretty_assertions::assert_eq;
+
+        fn format_source(source: &str, quote_indent: QuoteIndent) -> String {
             format(
-                &[Expression::List(
-                    "[",
-                    "]",
-                    vec![
-                        Expression::Symbol("foo", Position::new(0, 2)),
-                        Expression::Symbol("bar", Position::new(0, 2))
-                    ],
-                    Position::new(0, 2)
-                )],
+                &parse(source, Dialect::Scheme, Global).unwrap(),
                 &[],
                 &[],
-                &PositionMap::new("[foo bar]"),
+                &PositionMap::new(source),
+                &Options::new().set_quote_indent(quote_indent),
                 Global,
             )
-            .unwrap(),
-            "[foo bar]\n"
-        );
+            .unwrap()
+        }
+
+        #[test]
+        fn indent_unquote_splicing_from_datum() {
+            assert_eq!(
+                format_source("`(a ,@(foo\nbar))", QuoteIndent::Datum),
+                indoc!(
+                    "
+                    `(a ,@(foo
+                           bar))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn indent_unquote_splicing_from_prefix() {
+            assert_eq!(
+                format_source("`(a ,@(foo\nbar))", QuoteIndent::Prefix),
+                indoc!(
+                    "
+                    `(a ,@(foo
+                          bar))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn indent_nested_quotes_from_datum() {
+            assert_eq!(
+                format_source("''(foo\nbar)", QuoteIndent::Datum),
+                indoc!(
+                    "
+                    ''(foo
+                       bar)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn indent_nested_quotes_from_prefix() {
+            assert_eq!(
+                format_source("''(foo\nbar)", QuoteIndent::Prefix),
+                indoc!(
+                    "
+                    ''(foo
+                      bar)
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn indent_vector_from_prefix() {
+            assert_eq!(
+                format_source("(foo #u8(1\n2))", QuoteIndent::Prefix),
+                indoc!(
+                    "
+                    (foo #u8(1
+                           2))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn indent_inner_list_from_itself() {
+            assert_eq!(
+                format_source(",@(foo (bar\nbaz))", QuoteIndent::Prefix),
+                indoc!(
+                    "
+                    ,@(foo (bar
+                            baz))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn keep_quoted_symbol() {
+            assert_eq!(format_source("'foo", QuoteIndent::Prefix), "'foo\n");
+        }
     }
 
     mod list {
@@ -599,6 +2513,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("\n\n\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -627,6 +2542,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -655,6 +2571,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("(foo\n\nbar)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -692,6 +2609,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("((foo\nbar)\n\nbaz)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -719,6 +2637,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("(\nfoo)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -751,50 +2670,150 @@ mod tests {
                     )],
                     &[],
                     &[],
-                    &PositionMap::new("((foo\nbar))"),
+                    &PositionMap::new("((foo\nbar))"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    ((foo
+                        bar))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_broken_nested_lists_with_offside() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("foo", Position::new(0, 1)),
+                            Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("bar", Position::new(0, 1)),
+                                    Expression::Symbol("baz", Position::new(10, 11))
+                                ],
+                                Position::new(0, 1)
+                            ),
+                        ],
+                        Position::new(0, 1)
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("((foo bar\nbaz))"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    (foo (bar
+                          baz))
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_hash_semicolon_quote_in_list() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("a", Position::new(1, 2)),
+                            Expression::Quote(
+                                "#;",
+                                Expression::Symbol("b", Position::new(5, 6)).into(),
+                                Position::new(3, 6)
+                            ),
+                            Expression::Symbol("c", Position::new(7, 8))
+                        ],
+                        Position::new(0, 9)
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("(a #;b c)"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                "(a #;b c)\n"
+            );
+        }
+
+        #[test]
+        fn format_hash_semicolon_quote_in_broken_list() {
+            assert_eq!(
+                format(
+                    &[Expression::List(
+                        "(",
+                        ")",
+                        vec![
+                            Expression::Symbol("a", Position::new(1, 2)),
+                            Expression::Quote(
+                                "#;",
+                                Expression::Symbol("b", Position::new(5, 6)).into(),
+                                Position::new(3, 6)
+                            ),
+                            Expression::Symbol("c", Position::new(7, 8))
+                        ],
+                        Position::new(0, 9)
+                    )],
+                    &[],
+                    &[],
+                    &PositionMap::new("(a #;b\nc)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
                 indoc!(
                     "
-                    ((foo
-                        bar))
+                    (a #;b
+                      c)
                     "
                 )
             );
         }
 
         #[test]
-        fn format_broken_nested_lists_with_offside() {
+        fn format_hash_semicolon_quote_after_line_break() {
             assert_eq!(
                 format(
                     &[Expression::List(
                         "(",
                         ")",
                         vec![
-                            Expression::Symbol("foo", Position::new(0, 1)),
-                            Expression::List(
-                                "(",
-                                ")",
-                                vec![
-                                    Expression::Symbol("bar", Position::new(0, 1)),
-                                    Expression::Symbol("baz", Position::new(10, 11))
-                                ],
-                                Position::new(0, 1)
+                            Expression::Symbol("a", Position::new(1, 2)),
+                            Expression::Quote(
+                                "#;",
+                                Expression::Symbol("b", Position::new(5, 6)).into(),
+                                Position::new(3, 6)
                             ),
+                            Expression::Symbol("c", Position::new(7, 8))
                         ],
-                        Position::new(0, 1)
+                        Position::new(0, 9)
                     )],
                     &[],
                     &[],
-                    &PositionMap::new("((foo bar\nbaz))"),
+                    &PositionMap::new("(a\n#;b c)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
                 indoc!(
                     "
-                    (foo (bar
-                          baz))
+                    (a
+                      #;b
+                      c)
                     "
                 )
             );
@@ -816,6 +2835,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -839,13 +2859,92 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("\n\na"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    foo
+
+                    bar
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_only_hash_directives() {
+            assert_eq!(
+                format(
+                    &[],
+                    &[],
+                    &[HashDirective::new("!/bin/sh", Position::new(0, 10))],
+                    &PositionMap::new("#!/bin/sh\n\n"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                "#!/bin/sh\n"
+            );
+        }
+
+        #[test]
+        fn format_only_comments_after_blank_lines() {
+            assert_eq!(
+                format(
+                    &[],
+                    &[LineComment::new("foo", Position::new(2, 6)).into()],
+                    &[],
+                    &PositionMap::new("\n\n;foo\n"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                ";foo\n"
+            );
+        }
+
+        #[test]
+        fn format_block_comment_after_blank_line() {
+            assert_eq!(
+                format(
+                    &[Expression::Symbol("bar", Position::new(9, 12))],
+                    &[BlockComment::new("foo", Position::new(1, 8)).into()],
+                    &[],
+                    &PositionMap::new("\n#|foo|#\nbar"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
                 indoc!(
                     "
+                    #|
                     foo
+                    |#
+                    bar
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_hash_directive_comment_and_expression() {
+            assert_eq!(
+                format(
+                    &[Expression::Symbol("bar", Position::new(16, 19))],
+                    &[LineComment::new("foo", Position::new(11, 15)).into()],
+                    &[HashDirective::new("!/bin/sh", Position::new(0, 10))],
+                    &PositionMap::new("#!/bin/sh\n\n;foo\nbar"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    #!/bin/sh
 
+                    ;foo
                     bar
                     "
                 )
@@ -857,6 +2956,220 @@ mod tests {
         use super::*;
         use pretty_assertions::assert_eq;
 
+        mod max_blank_lines {
+            use super::*;
+            use pretty_assertions::assert_eq;
+
+            fn format_comments(options: &Options) -> String {
+                format(
+                    &[Expression::Symbol("baz", Position::new(13, 16))],
+                    &[
+                        LineComment::new("foo", Position::new(0, 4)).into(),
+                        LineComment::new("bar", Position::new(7, 11)).into(),
+                    ],
+                    &[],
+                    &PositionMap::new(";foo\n\n\n;bar\n\nbaz"),
+                    options,
+                    Global,
+                )
+                .unwrap()
+            }
+
+            #[test]
+            fn collapse_blank_lines_between_comments() {
+                assert_eq!(
+                    format_comments(&Options::new()),
+                    indoc!(
+                        "
+                        ;foo
+
+                        ;bar
+
+                        baz
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn keep_blank_lines_between_comments() {
+                assert_eq!(
+                    format_comments(&Options::new().set_max_blank_lines(2)),
+                    indoc!(
+                        "
+                        ;foo
+
+
+                        ;bar
+
+                        baz
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn remove_blank_lines_between_comments() {
+                assert_eq!(
+                    format_comments(&Options::new().set_max_blank_lines(0)),
+                    indoc!(
+                        "
+                        ;foo
+                        ;bar
+                        baz
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn keep_blank_lines_between_expressions() {
+                assert_eq!(
+                    format(
+                        &[
+                            Expression::Symbol("foo", Position::new(0, 3)),
+                            Expression::Symbol("bar", Position::new(7, 10))
+                        ],
+                        &[],
+                        &[],
+                        &PositionMap::new("foo\n\n\n\nbar"),
+                        &Options::new().set_max_blank_lines(2),
+                        Global,
+                    )
+                    .unwrap(),
+                    indoc!(
+                        "
+                        foo
+
+
+                        bar
+                        "
+                    )
+                );
+            }
+        }
+
+        mod header {
+            use super::*;
+            use pretty_assertions::assert_eq;
+
+            fn format_header(comments: &[Comment], source: &str, options: &Options) -> String {
+                format(
+                    &[
+                        Expression::Symbol("a", Position::new(0, 1)),
+                        Expression::Symbol("b", Position::new(source.len() - 1, source.len())),
+                    ],
+                    comments,
+                    &[],
+                    &PositionMap::new(source),
+                    options,
+                    Global,
+                )
+                .unwrap()
+            }
+
+            #[test]
+            fn keep_header_without_option() {
+                assert_eq!(
+                    format_header(
+                        &[LineComment::new(";; foo", Position::new(2, 9)).into()],
+                        "a\n;;; foo\nb",
+                        &Options::new()
+                    ),
+                    "a\n;;; foo\nb\n"
+                );
+            }
+
+            #[test]
+            fn insert_blank_line_before_header() {
+                assert_eq!(
+                    format_header(
+                        &[LineComment::new(";; foo", Position::new(2, 9)).into()],
+                        "a\n;;; foo\nb",
+                        &Options::new().set_header_blank_line(true)
+                    ),
+                    indoc!(
+                        "
+                        a
+
+                        ;;; foo
+                        b
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn insert_blank_line_before_header_after_comment() {
+                assert_eq!(
+                    format_header(
+                        &[
+                            LineComment::new("; foo", Position::new(2, 8)).into(),
+                            LineComment::new(";; bar", Position::new(9, 16)).into()
+                        ],
+                        "a\n;; foo\n;;; bar\nb",
+                        &Options::new().set_header_blank_line(true)
+                    ),
+                    indoc!(
+                        "
+                        a
+                        ;; foo
+
+                        ;;; bar
+                        b
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn keep_blank_line_before_header() {
+                assert_eq!(
+                    format_header(
+                        &[LineComment::new(";; foo", Position::new(3, 10)).into()],
+                        "a\n\n;;; foo\nb",
+                        &Options::new().set_header_blank_line(true)
+                    ),
+                    indoc!(
+                        "
+                        a
+
+                        ;;; foo
+                        b
+                        "
+                    )
+                );
+            }
+
+            #[test]
+            fn skip_comment_with_four_semicolons() {
+                assert_eq!(
+                    format_header(
+                        &[LineComment::new(";;; foo", Position::new(2, 10)).into()],
+                        "a\n;;;; foo\nb",
+                        &Options::new().set_header_blank_line(true)
+                    ),
+                    "a\n;;;; foo\nb\n"
+                );
+            }
+
+            #[test]
+            fn format_header_at_start() {
+                assert_eq!(
+                    format(
+                        &[Expression::Symbol("a", Position::new(8, 9))],
+                        &[LineComment::new(";; foo", Position::new(0, 7)).into()],
+                        &[],
+                        &PositionMap::new(";;; foo\na"),
+                        &Options::new().set_header_blank_line(true),
+                        Global,
+                    )
+                    .unwrap(),
+                    ";;; foo\na\n"
+                );
+            }
+        }
+
         #[test]
         fn format_multi_line_comment() {
             assert_eq!(
@@ -865,6 +3178,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(0, 1)).into()],
                     &[],
                     &PositionMap::new("\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -885,6 +3199,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(0, 1)).into()],
                     &[],
                     &PositionMap::new("\n\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -909,6 +3224,7 @@ mod tests {
                     ],
                     &[],
                     &PositionMap::new("\n\n\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -934,6 +3250,7 @@ mod tests {
                     ],
                     &[],
                     &PositionMap::new("\n\n\n\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -960,6 +3277,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(1, 2)).into()],
                     &[],
                     &PositionMap::new("\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -989,6 +3307,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(1, 2)).into()],
                     &[],
                     &PositionMap::new("\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1010,6 +3329,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(0, 1)).into()],
                     &[],
                     &PositionMap::new("\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1032,6 +3352,7 @@ mod tests {
                     ],
                     &[],
                     &PositionMap::new("\na"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1056,6 +3377,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(1, 5)).into()],
                     &[],
                     &PositionMap::new("(;bar\nfoo)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1084,6 +3406,7 @@ mod tests {
                     &[LineComment::new("foo", Position::new(1, 2)).into()],
                     &[],
                     &PositionMap::new("\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1105,6 +3428,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(1, 2)).into()],
                     &[],
                     &PositionMap::new("\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1129,6 +3453,7 @@ mod tests {
                     ],
                     &[],
                     &PositionMap::new("\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1154,6 +3479,7 @@ mod tests {
                     ],
                     &[],
                     &PositionMap::new("\n\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1183,13 +3509,101 @@ mod tests {
                             &[Expression::Symbol("bar", Position::new(8, 11))],
                             &[BlockComment::new("foo", Position::new(0, 8)).into(),],
                             &[],
-                            &PositionMap::new("#|foo|#bar"),
+                            &PositionMap::new("#|foo|#bar"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            #|foo|# bar
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn format_before_first_expression() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("a", Position::new(7, 8)),
+                                    Expression::Symbol("b", Position::new(9, 10)),
+                                ],
+                                Position::new(0, 11)
+                            ),],
+                            &[BlockComment::new("x", Position::new(1, 6)).into(),],
+                            &[],
+                            &PositionMap::new("(#|x|# a b)"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (#|x|# a b)
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn format_before_first_expression_of_multi_line_list() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("a", Position::new(7, 8)),
+                                    Expression::Symbol("b", Position::new(10, 11)),
+                                ],
+                                Position::new(0, 12)
+                            ),],
+                            &[BlockComment::new("x", Position::new(1, 6)).into(),],
+                            &[],
+                            &PositionMap::new("(#|x|# a\n b)"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (#|x|# a
+                              b)
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn format_unlike_line_comment_before_first_expression() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("a", Position::new(5, 6)),
+                                    Expression::Symbol("b", Position::new(7, 8)),
+                                ],
+                                Position::new(0, 9)
+                            ),],
+                            &[LineComment::new("x", Position::new(1, 4)).into(),],
+                            &[],
+                            &PositionMap::new("(;x\na b)"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
                         indoc!(
                             "
-                            #|foo|# bar
+                            ( ;x
+                              a
+                              b)
                             "
                         )
                     );
@@ -1211,6 +3625,7 @@ mod tests {
                             &[BlockComment::new("bar", Position::new(4, 11)).into(),],
                             &[],
                             &PositionMap::new("(foo#|bar|#baz)"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1239,6 +3654,7 @@ mod tests {
                             &[BlockComment::new("baz", Position::new(8, 15)).into(),],
                             &[],
                             &PositionMap::new("(foo bar#|baz|#qux)"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1267,6 +3683,7 @@ mod tests {
                             &[BlockComment::new("baz", Position::new(12, 19)).into(),],
                             &[],
                             &PositionMap::new("(foo bar qux#|baz|#)"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1278,6 +3695,125 @@ mod tests {
                     );
                 }
 
+                #[test]
+                fn format_consecutive_comments() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("foo", Position::new(1, 4)),
+                                    Expression::Symbol("bar", Position::new(17, 20)),
+                                ],
+                                Position::new(0, 21)
+                            ),],
+                            &[
+                                BlockComment::new("a", Position::new(5, 10)).into(),
+                                BlockComment::new("b", Position::new(11, 16)).into(),
+                            ],
+                            &[],
+                            &PositionMap::new("(foo #|a|# #|b|# bar)"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (foo #|a|# #|b|# bar)
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn format_between_expressions_on_first_line() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("foo", Position::new(1, 4)),
+                                    Expression::Symbol("bar", Position::new(11, 14)),
+                                    Expression::Symbol("baz", Position::new(16, 19)),
+                                ],
+                                Position::new(0, 20)
+                            ),],
+                            &[BlockComment::new("x", Position::new(5, 10)).into(),],
+                            &[],
+                            &PositionMap::new("(foo #|x|# bar\n baz)"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (foo #|x|# bar
+                              baz)
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn format_before_expression_on_broken_line() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("foo", Position::new(1, 4)),
+                                    Expression::Symbol("bar", Position::new(12, 15)),
+                                ],
+                                Position::new(0, 16)
+                            ),],
+                            &[BlockComment::new("x", Position::new(6, 11)).into(),],
+                            &[],
+                            &PositionMap::new("(foo\n #|x|# bar)"),
+                            &Default::default(),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (foo
+                              #|x|# bar)
+                            "
+                        )
+                    );
+                }
+
+                #[test]
+                fn keep_list_with_inline_comment_broken_on_reflow() {
+                    assert_eq!(
+                        format(
+                            &[Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("foo", Position::new(1, 4)),
+                                    Expression::Symbol("bar", Position::new(12, 15)),
+                                ],
+                                Position::new(0, 16)
+                            ),],
+                            &[BlockComment::new("x", Position::new(6, 11)).into(),],
+                            &[],
+                            &PositionMap::new("(foo\n #|x|# bar)"),
+                            &Options::new().set_reflow(true),
+                            Global,
+                        )
+                        .unwrap(),
+                        indoc!(
+                            "
+                            (foo
+                              #|x|# bar)
+                            "
+                        )
+                    );
+                }
+
                 #[test]
                 fn format_in_empty_list() {
                     assert_eq!(
@@ -1286,6 +3822,7 @@ mod tests {
                             &[BlockComment::new("foo", Position::new(1, 8)).into(),],
                             &[],
                             &PositionMap::new("(#|foo|#)"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1302,6 +3839,85 @@ mod tests {
                 use super::*;
                 use pretty_assertions::assert_eq;
 
+                fn format_indented_comment(options: &Options) -> String {
+                    format(
+                        &[],
+                        &[BlockComment::new(
+                            "\n\n    foo\n  bar\n      baz  \n\n",
+                            Position::new(0, 30),
+                        )
+                        .into()],
+                        &[],
+                        &PositionMap::new("#|\n\n    foo\n  bar\n      baz  \n\n|#"),
+                        options,
+                        Global,
+                    )
+                    .unwrap()
+                }
+
+                #[test]
+                fn trim_indented_comment() {
+                    assert_eq!(
+                        format_indented_comment(&Default::default()),
+                        "#|\nfoo\n  bar\n      baz\n|#\n"
+                    );
+                }
+
+                #[test]
+                fn keep_indented_comment_verbatim() {
+                    assert_eq!(
+                        format_indented_comment(&Options::new().set_verbatim_block_comments(true)),
+                        "#|\n    foo\n  bar\n      baz  \n|#\n"
+                    );
+                }
+
+                #[test]
+                fn keep_one_line_comment_verbatim() {
+                    assert_eq!(
+                        format(
+                            &[],
+                            &[BlockComment::new("  foo ", Position::new(0, 10)).into()],
+                            &[],
+                            &PositionMap::new("#|  foo |#"),
+                            &Options::new().set_verbatim_block_comments(true),
+                            Global,
+                        )
+                        .unwrap(),
+                        "#|\n  foo \n|#\n"
+                    );
+                }
+
+                fn format_with_trailing_spaces(options: &Options) -> String {
+                    format(
+                        &[],
+                        &[
+                            BlockComment::new("\nfoo  \n  bar \nbaz\n", Position::new(0, 21))
+                                .into(),
+                        ],
+                        &[],
+                        &PositionMap::new("#|\nfoo  \n  bar \nbaz\n|#"),
+                        options,
+                        Global,
+                    )
+                    .unwrap()
+                }
+
+                #[test]
+                fn keep_trailing_spaces() {
+                    assert_eq!(
+                        format_with_trailing_spaces(&Default::default()),
+                        "#|\nfoo  \n  bar \nbaz\n|#\n"
+                    );
+                }
+
+                #[test]
+                fn trim_trailing_spaces() {
+                    assert_eq!(
+                        format_with_trailing_spaces(&Options::new().set_trim_block_comments(true)),
+                        "#|\nfoo\n  bar\nbaz\n|#\n"
+                    );
+                }
+
                 #[test]
                 fn format_with_no_blank_line() {
                     assert_eq!(
@@ -1313,6 +3929,7 @@ mod tests {
                             &[BlockComment::new("foo", Position::new(4, 5)).into(),],
                             &[],
                             &PositionMap::new("foo\n#|foo|#\nbar"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1339,6 +3956,7 @@ mod tests {
                             &[BlockComment::new("foo", Position::new(5, 6)).into(),],
                             &[],
                             &PositionMap::new("foo\n\n#|foo|#\n\nbar"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1367,6 +3985,7 @@ mod tests {
                             &[BlockComment::new("foo", Position::new(6, 7)).into(),],
                             &[],
                             &PositionMap::new("foo\n\n\n#|foo|#\n\n\nbar"),
+                            &Default::default(),
                             Global,
                         )
                         .unwrap(),
@@ -1399,6 +4018,7 @@ mod tests {
                     &[],
                     &[HashDirective::new("foo", Position::new(0, 0))],
                     &PositionMap::new("\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1421,6 +4041,7 @@ mod tests {
                         HashDirective::new("bar", Position::new(2, 2))
                     ],
                     &PositionMap::new("\n\n\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1437,10 +4058,54 @@ mod tests {
         fn format_hash_directive_with_expression() {
             assert_eq!(
                 format(
-                    &[Expression::Symbol("bar", Position::new(0, 0))],
+                    &[Expression::Symbol("bar", Position::new(6, 9))],
                     &[],
-                    &[HashDirective::new("foo", Position::new(0, 0))],
-                    &PositionMap::new("\n"),
+                    &[HashDirective::new("foo", Position::new(0, 4))],
+                    &PositionMap::new("#foo\n\nbar\n"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    #foo
+
+                    bar
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_hash_directive_followed_by_expression() {
+            assert_eq!(
+                format(
+                    &[Expression::Symbol("bar", Position::new(5, 8))],
+                    &[],
+                    &[HashDirective::new("foo", Position::new(0, 4))],
+                    &PositionMap::new("#foo\nbar\n"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    #foo
+                    bar
+                    "
+                )
+            );
+        }
+
+        #[test]
+        fn format_hash_directive_with_blank_lines() {
+            assert_eq!(
+                format(
+                    &[Expression::Symbol("bar", Position::new(8, 11))],
+                    &[],
+                    &[HashDirective::new("foo", Position::new(0, 4))],
+                    &PositionMap::new("#foo\n\n\n\nbar\n"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1453,12 +4118,99 @@ mod tests {
                 )
             );
         }
+
+        #[test]
+        fn format_hash_directive_followed_by_comment() {
+            assert_eq!(
+                format(
+                    &[Expression::Symbol("bar", Position::new(10, 13))],
+                    &[LineComment::new("baz", Position::new(5, 9)).into()],
+                    &[HashDirective::new("foo", Position::new(0, 4))],
+                    &PositionMap::new("#foo\n;baz\nbar\n"),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap(),
+                indoc!(
+                    "
+                    #foo
+                    ;baz
+                    bar
+                    "
+                )
+            );
+        }
     }
 
     mod data {
         use super::*;
         use pretty_assertions::assert_eq;
 
+        mod stacked_quote {
+            use super::*;
+            use pretty_assertions::assert_eq;
+
+            fn format_stacked_quote(signs: &[&'static str]) -> String {
+                let length = signs.iter().map(|sign| sign.len()).sum::<usize>();
+                let source = format!("{}(foo\nbar)", signs.concat());
+                let list = Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(length + 1, length + 4)),
+                        Expression::Symbol("bar", Position::new(length + 5, length + 8)),
+                    ],
+                    Position::new(length, length + 9),
+                );
+                let mut offset = length;
+                let expression = signs.iter().rev().fold(list, |expression, sign| {
+                    offset -= sign.len();
+                    Expression::Quote(sign, expression.into(), Position::new(offset, length + 9))
+                });
+
+                format(
+                    &[expression],
+                    &[],
+                    &[],
+                    &PositionMap::new(&source),
+                    &Default::default(),
+                    Global,
+                )
+                .unwrap()
+            }
+
+            #[test]
+            fn format_one_quote() {
+                assert_eq!(format_stacked_quote(&["'"]), "'(foo\n  bar)\n");
+            }
+
+            #[test]
+            fn format_two_quotes() {
+                assert_eq!(format_stacked_quote(&["'", "'"]), "''(foo\n   bar)\n");
+            }
+
+            #[test]
+            fn format_four_quotes() {
+                assert_eq!(
+                    format_stacked_quote(&["'", "'", "'", "'"]),
+                    "''''(foo\n     bar)\n"
+                );
+            }
+
+            #[test]
+            fn format_stacked_quasi_quotes() {
+                assert_eq!(
+                    format_stacked_quote(&["`", "`", "`"]),
+                    "```(foo\n    bar)\n"
+                );
+            }
+
+            #[test]
+            fn format_unquote_splicing_in_quasi_quote() {
+                assert_eq!(format_stacked_quote(&["`", ",@"]), "`,@(foo\n    bar)\n");
+            }
+        }
+
         #[test]
         fn format_list() {
             assert_eq!(
@@ -1480,6 +4232,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("(foo bar)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1508,6 +4261,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("'(foo\nbar)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1541,6 +4295,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("'(\nfoo\nbar)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1572,6 +4327,7 @@ mod tests {
                     &[LineComment::new("bar", Position::new(2, 6)).into()],
                     &[],
                     &PositionMap::new("'(;bar\nfoo)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1602,6 +4358,7 @@ mod tests {
                     &[],
                     &[],
                     &PositionMap::new("'(\nfoo)"),
+                    &Default::default(),
                     Global,
                 )
                 .unwrap(),
@@ -1644,6 +4401,7 @@ mod tests {
                         &[],
                         &[],
                         &PositionMap::new("'((foo bar))"),
+                        &Default::default(),
                         Global,
                     )
                     .unwrap(),
@@ -1681,6 +4439,7 @@ mod tests {
                         &[],
                         &[],
                         &PositionMap::new("'((foo\nbar))"),
+                        &Default::default(),
                         Global,
                     )
                     .unwrap(),
@@ -1719,6 +4478,7 @@ mod tests {
                         &[],
                         &[],
                         &PositionMap::new(",((foo\nbar))"),
+                        &Default::default(),
                         Global,
                     )
                     .unwrap(),
@@ -1732,32 +4492,47 @@ mod tests {
             }
 
             #[test]
-            fn format_splicing_unquote() {
+            fn format_unquote_of_symbol_with_at_sign() {
                 assert_eq!(
                     format(
                         &[Expression::Quote(
                             ",",
-                            Expression::Quote(
-                                "@",
-                                Expression::List(
-                                    "(",
-                                    ")",
-                                    vec![
-                                        Expression::Symbol("foo", Position::new(3, 6)),
-                                        Expression::List(
-                                            "(",
-                                            ")",
-                                            vec![
-                                                Expression::Symbol("bar", Position::new(8, 11)),
-                                                Expression::Symbol("baz", Position::new(12, 15)),
-                                            ],
-                                            Position::new(7, 16)
-                                        ),
-                                    ],
-                                    Position::new(2, 17)
-                                )
-                                .into(),
-                                Position::new(1, 17)
+                            Expression::Symbol("@foo", Position::new(2, 6)).into(),
+                            Position::new(0, 6)
+                        )],
+                        &[],
+                        &[],
+                        &PositionMap::new(", @foo"),
+                        &Default::default(),
+                        Global,
+                    )
+                    .unwrap(),
+                    ", @foo\n"
+                );
+            }
+
+            #[test]
+            fn format_splicing_unquote() {
+                assert_eq!(
+                    format(
+                        &[Expression::Quote(
+                            ",@",
+                            Expression::List(
+                                "(",
+                                ")",
+                                vec![
+                                    Expression::Symbol("foo", Position::new(3, 6)),
+                                    Expression::List(
+                                        "(",
+                                        ")",
+                                        vec![
+                                            Expression::Symbol("bar", Position::new(8, 11)),
+                                            Expression::Symbol("baz", Position::new(12, 15)),
+                                        ],
+                                        Position::new(7, 16)
+                                    ),
+                                ],
+                                Position::new(2, 17)
                             )
                             .into(),
                             Position::new(0, 17)
@@ -1765,6 +4540,7 @@ mod tests {
                         &[],
                         &[],
                         &PositionMap::new(",@(foo\n(bar\nbaz))"),
+                        &Default::default(),
                         Global,
                     )
                     .unwrap(),
@@ -0,0 +1,41 @@
+use crate::parse::ParseError;
+use core::{
+    error::Error,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+/// A format error.
+#[derive(Debug, PartialEq)]
+pub enum FormatError {
+    /// A formatting error.
+    Format(fmt::Error),
+    /// A mismatch between expressions in source code and formatted one.
+    Mismatch,
+    /// A parse error.
+    Parse(ParseError),
+}
+
+impl Error for FormatError {}
+
+impl Display for FormatError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format(error) => error.fmt(formatter),
+            Self::Mismatch => write!(formatter, "formatted code differs from source code"),
+            Self::Parse(error) => write!(formatter, "{} at {}", error.message(), error.offset()),
+        }
+    }
+}
+
+impl From<fmt::Error> for FormatError {
+    fn from(error: fmt::Error) -> Self {
+        Self::Format(error)
+    }
+}
+
+impl From<ParseError> for FormatError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
@@ -0,0 +1,96 @@
+use futures::future::join_all;
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+use tokio::{fs::canonicalize, process::Command};
+
+/// Lists canonical paths of files changed since a Git reference.
+///
+/// Deleted files are excluded and renamed files are listed by their new paths.
+/// Untracked files are not listed.
+pub async fn changed_paths(reference: &str) -> Result<HashSet<PathBuf>, io::Error> {
+    let directory = run(&["rev-parse", "--show-toplevel"]).await?;
+    let directory = Path::new(OsStr::from_bytes(directory.trim_ascii_end()));
+    // Verify a reference first not to interpret it as an option.
+    let commit = run(&[
+        "rev-parse",
+        "--verify",
+        "--end-of-options",
+        &format!("{reference}^{{commit}}"),
+    ])
+    .await?;
+    let commit = String::from_utf8_lossy(commit.trim_ascii_end()).into_owned();
+
+    Ok(join_all(
+        parse_paths(
+            &run(&[
+                "diff",
+                "--name-only",
+                "--no-relative",
+                "--diff-filter=d",
+                "-z",
+                &commit,
+                "--",
+            ])
+            .await?,
+        )
+        .map(|path| canonicalize(directory.join(path))),
+    )
+    .await
+    .into_iter()
+    // Skip files removed since listing them.
+    .filter_map(Result::ok)
+    .collect())
+}
+
+fn parse_paths(output: &[u8]) -> impl Iterator<Item = &Path> {
+    output
+        .split(|&byte| byte == b'\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| Path::new(OsStr::from_bytes(path)))
+}
+
+async fn run(arguments: &[&str]) -> Result<Vec<u8>, io::Error> {
+    let output = Command::new("git").args(arguments).output().await?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(io::Error::other(format!(
+            "git {} failed: {}",
+            arguments[0],
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_no_path() {
+        assert_eq!(parse_paths(b"").collect::<Vec<_>>(), Vec::<&Path>::new());
+    }
+
+    #[test]
+    fn parse_paths_separated_by_nul() {
+        assert_eq!(
+            parse_paths(b"foo.scm\0bar/baz.scm\0").collect::<Vec<_>>(),
+            [Path::new("foo.scm"), Path::new("bar/baz.scm")]
+        );
+    }
+
+    #[test]
+    fn parse_path_of_invalid_utf8() {
+        assert_eq!(
+            parse_paths(b"foo\xff.scm\0").collect::<Vec<_>>(),
+            [Path::new(OsStr::from_bytes(b"foo\xff.scm"))]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::sarif::SarifResult;
+
+/// Renders a result as a workflow command of GitHub Actions.
+pub fn render_github(result: &SarifResult) -> String {
+    let lines = result.lines();
+
+    format!(
+        "::{} file={},line={}{}{}::{}",
+        result.level(),
+        escape_property(result.path()),
+        lines.start + 1,
+        if lines.len() > 1 {
+            format!(",endLine={}", lines.end)
+        } else {
+            Default::default()
+        },
+        result
+            .column()
+            .map(|column| format!(",col={}", column + 1))
+            .unwrap_or_default(),
+        escape_data(result.message()),
+    )
+}
+
+fn escape_data(string: &str) -> String {
+    string
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(string: &str) -> String {
+    escape_data(string).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApplicationError;
+    use pretty_assertions::assert_eq;
+    use schemat::{validate_source, Options};
+    use std::io;
+
+    #[test]
+    fn render_unformatted_file() {
+        assert_eq!(
+            render_github(&SarifResult::unformatted("foo.scm", 1..2)),
+            "::warning file=foo.scm,line=2::file not formatted"
+        );
+    }
+
+    #[test]
+    fn render_unformatted_lines() {
+        assert_eq!(
+            render_github(&SarifResult::unformatted("foo.scm", 1..4)),
+            "::warning file=foo.scm,line=2,endLine=4::file not formatted"
+        );
+    }
+
+    #[test]
+    fn render_parse_error() {
+        let source = "(foo\n  (bar";
+
+        assert_eq!(
+            render_github(&SarifResult::error(
+                "foo.scm",
+                &ApplicationError::parse(
+                    validate_source(source, &Options::new()).unwrap_err(),
+                    "foo.scm",
+                    source,
+                    8,
+                )
+            )),
+            "::error file=foo.scm,line=2,col=6::failed to parse"
+        );
+    }
+
+    #[test]
+    fn render_error_without_location() {
+        assert_eq!(
+            render_github(&SarifResult::error(
+                "foo.scm",
+                &io::Error::other("foo\nbar").into()
+            )),
+            "::error file=foo.scm,line=1::foo%0Abar"
+        );
+    }
+
+    #[test]
+    fn escape_path() {
+        assert_eq!(
+            render_github(&SarifResult::unformatted("a:b,c%d.scm", 0..1)),
+            "::warning file=a%3Ab%2Cc%25d.scm,line=1::file not formatted"
+        );
+    }
+}
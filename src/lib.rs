@@ -0,0 +1,934 @@
+//! A code formatter for Scheme, Lisp, and any S-expressions.
+
+#![feature(allocator_api)]
+
+mod ast;
+mod check;
+mod context;
+mod format;
+mod options;
+mod parse;
+mod position;
+mod position_map;
+mod source;
+mod text_edit;
+mod timings;
+
+pub use self::{
+    ast::{Expression, OwnedExpression},
+    check::{
+        check_sources, find_head_symbols, find_long_strings, find_tab_indented_lines,
+        is_equivalent, CheckResult,
+    },
+    format::FormatError,
+    options::{Dialect, HexCase, Options, QuoteIndent, QuoteStyle, Style},
+    parse::{parse, ParseError},
+    position::Position,
+    position_map::PositionMap,
+    source::{
+        find_changed_lines, find_changed_regions, find_long_lines, find_text_edits, measure_width,
+        normalize_line_endings, render_diff, split_byte_order_mark,
+    },
+    text_edit::{apply_text_edits, TextEdit},
+    timings::Timings,
+};
+use bumpalo::Bump;
+use std::{alloc::Allocator, time::Instant};
+
+/// Formats a source.
+pub fn format_source(source: &str, options: &Options) -> Result<String, FormatError> {
+    format_source_with_timings(source, options).map(|(string, _)| string)
+}
+
+/// Formats a source measuring durations of parsing and formatting.
+pub fn format_source_with_timings(
+    source: &str,
+    options: &Options,
+) -> Result<(String, Timings), FormatError> {
+    let allocator = Bump::new();
+    let start = Instant::now();
+    let expressions = parse::parse_with_options(source, options, &allocator)?;
+    let comments = parse::parse_comments(source, options, &allocator)?;
+    let hash_directives = parse::parse_hash_directives(source, options.dialect(), &allocator)?;
+    let parse_duration = start.elapsed();
+
+    let start = Instant::now();
+    let string = format::format(
+        &expressions,
+        &comments,
+        &hash_directives,
+        &PositionMap::new(source),
+        options,
+        &allocator,
+    )?;
+
+    if options.safe() {
+        verify_expressions(&expressions, &string, options)?;
+    }
+
+    Ok((
+        split_byte_order_mark(source).0.to_owned() + &string,
+        Timings::new(parse_duration, start.elapsed()),
+    ))
+}
+
+fn verify_expressions<A: Allocator>(
+    expressions: &[Expression<A>],
+    formatted: &str,
+    options: &Options,
+) -> Result<(), FormatError> {
+    let allocator = Bump::new();
+    let other = parse::parse_with_options(formatted, options, &allocator)
+        .map_err(|_| FormatError::Mismatch)?;
+
+    if expressions.len() == other.len()
+        && expressions
+            .iter()
+            .zip(other.iter())
+            .all(|(one, other)| one.is_equivalent(other))
+    {
+        Ok(())
+    } else {
+        Err(FormatError::Mismatch)
+    }
+}
+
+/// Validates a source by parsing it without formatting.
+pub fn validate_source(source: &str, options: &Options) -> Result<(), ParseError> {
+    let allocator = Bump::new();
+
+    parse::parse_with_options(source, options, &allocator)?;
+    parse::parse_comments(source, options, &allocator)?;
+    parse::parse_hash_directives(source, options.dialect(), &allocator)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn validate_valid_source() {
+        assert_eq!(validate_source("(foo  bar)", &Options::new()), Ok(()));
+    }
+
+    #[test]
+    fn validate_invalid_source() {
+        assert!(validate_source("(foo", &Options::new()).is_err());
+        assert!(validate_source("#|foo", &Options::new()).is_err());
+    }
+
+    #[test]
+    fn format_symbols_with_escaped_delimiters() {
+        for source in ["a\\(b\n", "a\\)b\n", "a\\;b\n", "(\\( \\) \\;)\n"] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn format_broken_list_with_escaped_delimiters() {
+        let source = "(foo a\\(\n  b\\;\n  c\\))\n";
+
+        assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+    }
+
+    #[test]
+    fn format_block_comments_with_ambiguous_delimiters() {
+        for source in [
+            "(foo #|#|# bar)\n",
+            "(foo #||# bar)\n",
+            "(foo #|||# #|a||# bar)\n",
+        ] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn collapse_spaces_between_elements() {
+        for source in ["(a    b)", "(a\tb)", "(a \t b)"] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), "(a b)\n");
+        }
+    }
+
+    #[test]
+    fn collapse_spaces_around_comments() {
+        assert_eq!(
+            format_source("(a  #|c|#   b)", &Options::new()).unwrap(),
+            "(a #|c|# b)\n"
+        );
+        assert_eq!(
+            format_source("(a    ; c\n  b)", &Options::new()).unwrap(),
+            "(a ; c\n  b)\n"
+        );
+    }
+
+    #[test]
+    fn keep_order_of_block_and_line_comments_on_same_line() {
+        for (source, formatted) in [
+            ("#|b|# ;c\n(foo)\n", "#|\nb\n|#\n;c\n(foo)\n"),
+            ("(foo) #|b|# ;c\n(bar)\n", "(foo)\n#|\nb\n|#\n;c\n(bar)\n"),
+            (
+                "(foo\n  bar #|b|# ;c\n  baz)\n",
+                "(foo\n  bar\n  #|\n  b\n  |#\n  ;c\n  baz)\n",
+            ),
+            (
+                "#|a|# #|b|# ;c ;d\n(foo)\n",
+                "#|\na\n|#\n#|\nb\n|#\n;c ;d\n(foo)\n",
+            ),
+        ] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), formatted);
+        }
+    }
+
+    #[test]
+    fn keep_empty_line_comments() {
+        for source in [
+            ";\n",
+            "foo ;\n",
+            "(foo ;\n  bar)\n",
+            ";\nfoo\n",
+            "(foo\n  ;\n  bar)\n",
+        ] {
+            for options in [Options::new(), Options::new().set_comment_space(true)] {
+                assert_eq!(format_source(source, &options).unwrap(), source);
+            }
+        }
+    }
+
+    mod indent_block_comments {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_indented(source: &str) -> String {
+            format_source(source, &Options::new().set_indent_block_comments(true)).unwrap()
+        }
+
+        #[test]
+        fn align_comment_in_nested_list() {
+            let formatted = "(foo\n  (bar\n    #|\n    a\n    b\n\n      c\n    |#\n    qux))\n";
+
+            assert_eq!(
+                format_indented("(foo\n  (bar #| a\n      b\n\n        c |# qux))"),
+                formatted
+            );
+            assert_eq!(format_indented(formatted), formatted);
+        }
+
+        #[test]
+        fn keep_comment_unindented_by_default() {
+            assert_eq!(
+                format_source("(foo\n  (bar #| a\n b |# qux))", &Options::new()).unwrap(),
+                "(foo\n  (bar\n    #|\n    a\n b\n    |#\n    qux))\n"
+            );
+        }
+
+        #[test]
+        fn indent_and_trim_comment() {
+            assert_eq!(
+                format_source(
+                    "(foo\n  #| a  \n  b  |#\n  bar)",
+                    &Options::new()
+                        .set_indent_block_comments(true)
+                        .set_trim_block_comments(true)
+                )
+                .unwrap(),
+                "(foo\n  #|\n  a\n  b\n  |#\n  bar)\n"
+            );
+        }
+    }
+
+    mod verbatim_block_comments {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_verbatim(source: &str) -> String {
+            format_source(source, &Options::new().set_verbatim_block_comments(true)).unwrap()
+        }
+
+        #[test]
+        fn keep_comment_indented_in_list() {
+            let formatted = format_verbatim("(foo\n  #|\n  comment\n  |#\n  bar)");
+
+            assert_eq!(formatted, "(foo\n  #|\n  comment\n  |#\n  bar)\n");
+            assert_eq!(format_verbatim(&formatted), formatted);
+        }
+
+        #[test]
+        fn keep_relative_indentation_in_nested_list() {
+            let formatted = format_verbatim(
+                "(foo\n  (bar\n        #| a \n          b\n\n        c  |#\n    baz))",
+            );
+
+            assert_eq!(
+                formatted,
+                "(foo\n  (bar\n    #|\n     a \n      b\n\n    c  \n    |#\n    baz))\n"
+            );
+            assert_eq!(format_verbatim(&formatted), formatted);
+        }
+    }
+
+    #[test]
+    fn format_whitespace_only_sources() {
+        for source in ["", " ", "\n", "   \n\n", "\t\n  \n", "\n\n\n"] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), "\n");
+        }
+    }
+
+    #[test]
+    fn format_brackets_strictly() {
+        assert_eq!(format_source("[a]", &Options::new()).unwrap(), "[a]\n");
+        assert!(matches!(
+            format_source("[a]", &Options::new().set_strict(true)),
+            Err(FormatError::Parse(_))
+        ));
+        assert!(validate_source("(a [b])", &Options::new().set_strict(true)).is_err());
+    }
+
+    #[test]
+    fn format_reader_objects() {
+        for source in [
+            "#!eof\n",
+            "#!default\n(foo)\n",
+            "(foo #!eof #!unspecified)\n",
+            "#!/bin/sh\n(foo #!eof)\n",
+        ] {
+            assert_eq!(
+                format_source(source, &Options::new().set_safe(true)).unwrap(),
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn format_comments_in_hash_directives() {
+        for source in [
+            "#lang racket ;note\n(foo)\n",
+            "#!/bin/sh ;note\n\n;foo\n(foo)\n",
+            "#lang racket\n(foo) ;note\n",
+        ] {
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+    }
+
+    mod head_only {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        const SOURCE: &str = "(module\n  name\n  (foo)\n  (bar))\n";
+
+        #[test]
+        fn keep_head_on_first_line() {
+            assert_eq!(format_source(SOURCE, &Options::new()).unwrap(), SOURCE);
+        }
+
+        #[test]
+        fn indent_arguments_consistently() {
+            assert_eq!(
+                format_source("(module\nname\n    (foo)\n  (bar))\n", &Options::new()).unwrap(),
+                SOURCE
+            );
+        }
+
+        #[test]
+        fn split_arguments_on_same_line() {
+            assert_eq!(
+                format_source("(module\n  name (foo)\n  (bar))\n", &Options::new()).unwrap(),
+                SOURCE
+            );
+        }
+
+        #[test]
+        fn keep_head_with_comment() {
+            let source = "(module ; foo\n  name\n  (foo))\n";
+
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+
+        #[test]
+        fn indent_nested_list() {
+            assert_eq!(
+                format_source("(foo (module\n name\n (foo)))\n", &Options::new()).unwrap(),
+                "(foo (module\n      name\n      (foo)))\n"
+            );
+        }
+
+        #[test]
+        fn keep_head_with_style() {
+            for source in [SOURCE, "(define\n  x\n  (foo)\n  (bar))\n"] {
+                assert_eq!(
+                    format_source(source, &Options::new().set_style(Some(Style::R7rs))).unwrap(),
+                    source
+                );
+            }
+        }
+    }
+
+    mod safe {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn format_safely() {
+            assert_eq!(
+                format_source(
+                    "(define (foo)\n(let ((x 1))\n'x))",
+                    &Options::new().set_safe(true)
+                )
+                .unwrap(),
+                "(define (foo)\n  (let ((x 1))\n    'x))\n"
+            );
+        }
+
+        #[test]
+        fn verify_same_expressions() {
+            let allocator = Bump::new();
+            let expressions = parse::parse("(foo  bar)", Dialect::Scheme, &allocator).unwrap();
+
+            assert_eq!(
+                verify_expressions(&expressions, "(foo bar)\n", &Options::new()),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_verify_changed_expressions() {
+            let allocator = Bump::new();
+            let expressions = parse::parse("(foo bar)", Dialect::Scheme, &allocator).unwrap();
+
+            for formatted in [
+                "(foo baz)\n",
+                "(foo) bar\n",
+                "(foo bar)\nbaz\n",
+                "(foo bar\n",
+            ] {
+                assert_eq!(
+                    verify_expressions(&expressions, formatted, &Options::new()),
+                    Err(FormatError::Mismatch)
+                );
+            }
+        }
+
+        #[test]
+        fn fail_to_format_safely_with_quote_style() {
+            assert_eq!(
+                format_source(
+                    "'foo",
+                    &Options::new()
+                        .set_safe(true)
+                        .set_quote_style(QuoteStyle::Full)
+                ),
+                Err(FormatError::Mismatch)
+            );
+        }
+    }
+
+    mod minimal_symbol_escapes {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_symbol(source: &str) -> String {
+            format_source(source, &Options::new().set_minimal_symbol_escapes(true)).unwrap()
+        }
+
+        #[test]
+        fn unescape_characters() {
+            assert_eq!(format_symbol(r"|\x41;|"), "|A|\n");
+            assert_eq!(format_symbol(r"|foo\x2d;bar|"), "|foo-bar|\n");
+            assert_eq!(format_symbol(r"|\;|"), "|;|\n");
+            assert_eq!(format_symbol(r"|\'|"), "|'|\n");
+        }
+
+        #[test]
+        fn keep_tab() {
+            assert_eq!(format_symbol(r"|\t|"), "|\\t|\n");
+            assert_eq!(format_symbol(r"|\x09;|"), "|\\x09;|\n");
+        }
+
+        #[test]
+        fn keep_bar() {
+            assert_eq!(format_symbol(r"|\||"), "|\\||\n");
+            assert_eq!(format_symbol(r"|\x7c;|"), "|\\x7c;|\n");
+        }
+
+        #[test]
+        fn keep_backslash() {
+            assert_eq!(format_symbol(r"|\\|"), "|\\\\|\n");
+            assert_eq!(format_symbol(r"|\\x41;|"), "|\\\\x41;|\n");
+        }
+
+        #[test]
+        fn keep_whitespace() {
+            for source in [r"|\n|", r"|\r|", r"|\x20;|", "|\\\n|"] {
+                assert_eq!(format_symbol(source), format!("{}\n", source));
+            }
+        }
+
+        #[test]
+        fn keep_escapes_by_default() {
+            assert_eq!(
+                format_source(r"|\x41;|", &Options::new()).unwrap(),
+                "|\\x41;|\n"
+            );
+        }
+    }
+
+    mod definition_blank_lines {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_definitions(source: &str, count: usize) -> String {
+            format_source(
+                source,
+                &Options::new().set_definition_blank_lines(Some(count)),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn insert_blank_line_between_adjacent_definitions() {
+            assert_eq!(
+                format_definitions("(define x 1)\n(define-syntax y 2)\n", 1),
+                "(define x 1)\n\n(define-syntax y 2)\n"
+            );
+        }
+
+        #[test]
+        fn insert_blank_lines_beyond_maximum() {
+            assert_eq!(
+                format_definitions("(define x 1)\n(define y 2)\n", 2),
+                "(define x 1)\n\n\n(define y 2)\n"
+            );
+        }
+
+        #[test]
+        fn remove_blank_lines_between_definitions() {
+            assert_eq!(
+                format_definitions("(define x 1)\n\n(define y 2)\n", 0),
+                "(define x 1)\n(define y 2)\n"
+            );
+        }
+
+        #[test]
+        fn keep_comment_attached_to_definition() {
+            assert_eq!(
+                format_definitions("(define x 1)\n\n\n;; foo\n(define y 2)\n", 1),
+                "(define x 1)\n\n;; foo\n(define y 2)\n"
+            );
+        }
+
+        #[test]
+        fn keep_blank_lines_around_other_expressions() {
+            assert_eq!(
+                format_definitions("(define x 1)\n(foo)\n(define y 2)\n", 1),
+                "(define x 1)\n(foo)\n(define y 2)\n"
+            );
+        }
+
+        #[test]
+        fn keep_blank_lines_between_nested_definitions() {
+            assert_eq!(
+                format_definitions("(define (foo)\n  (define x 1)\n  (define y 2)\n  x)\n", 1),
+                "(define (foo)\n  (define x 1)\n  (define y 2)\n  x)\n"
+            );
+        }
+
+        #[test]
+        fn use_custom_definition_symbols() {
+            assert_eq!(
+                format_source(
+                    "(define x 1)\n(define y 2)\n(def z 3)\n",
+                    &Options::new()
+                        .set_definition_blank_lines(Some(1))
+                        .set_definition_symbols(vec!["def".into(), "define".into()])
+                )
+                .unwrap(),
+                "(define x 1)\n\n(define y 2)\n\n(def z 3)\n"
+            );
+        }
+    }
+
+    mod keep_arguments {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_kept(source: &str) -> String {
+            format_source(
+                source,
+                &Options::new()
+                    .set_style(Some(Style::R7rs))
+                    .set_keep_arguments(true)
+                    .set_safe(true),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn keep_if_condition() {
+            for source in [
+                "(if foo\n  bar\n  baz)",
+                "(if\n  foo\n  bar\n  baz)",
+                "(if foo bar\n  baz)",
+            ] {
+                assert_eq!(format_kept(source), "(if foo\n  bar\n  baz)\n");
+            }
+        }
+
+        #[test]
+        fn keep_when_condition() {
+            assert_eq!(
+                format_kept("(when\n  foo\n  bar\n  baz)"),
+                "(when foo\n  bar\n  baz)\n"
+            );
+        }
+
+        #[test]
+        fn keep_define_name() {
+            assert_eq!(format_kept("(define\n  x\n  1)"), "(define x\n  1)\n");
+            assert_eq!(
+                format_kept("(define (foo x) x\n  x)"),
+                "(define (foo x)\n  x\n  x)\n"
+            );
+        }
+
+        #[test]
+        fn keep_no_arguments() {
+            assert_eq!(format_kept("(begin foo\n  bar)"), "(begin\n  foo\n  bar)\n");
+        }
+
+        #[test]
+        fn keep_inline_list() {
+            assert_eq!(format_kept("(if foo bar baz)"), "(if foo bar baz)\n");
+        }
+
+        #[test]
+        fn keep_list_without_special_form() {
+            assert_eq!(format_kept("(foo\n  bar\n  baz)"), "(foo\n  bar\n  baz)\n");
+        }
+
+        #[test]
+        fn keep_comment_before_argument() {
+            let source = "(if ; foo\n  bar\n  baz)\n";
+
+            assert_eq!(format_kept(source), source);
+        }
+
+        #[test]
+        fn keep_arguments_without_option() {
+            let source = "(if\n  foo\n  bar)\n";
+
+            assert_eq!(
+                format_source(source, &Options::new().set_style(Some(Style::R7rs))).unwrap(),
+                source
+            );
+        }
+    }
+
+    mod reflow {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_let(source: &str) -> String {
+            format_source(
+                source,
+                &Options::new()
+                    .set_reflow(true)
+                    .set_safe(true)
+                    .set_max_width(24)
+                    .set_style(Some(Style::R7rs)),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn keep_long_string() {
+            let string = format!("\"{}\"", "a".repeat(198));
+
+            assert_eq!(
+                format_let(&format!("(foo {} bar)", string)),
+                format!("(foo\n  {}\n  bar)\n", string)
+            );
+        }
+
+        #[test]
+        fn keep_short_let() {
+            assert_eq!(format_let("(let ((x 1)) x)"), "(let ((x 1)) x)\n");
+        }
+
+        #[test]
+        fn join_short_let() {
+            assert_eq!(format_let("(let ((x 1))\n  x)"), "(let ((x 1)) x)\n");
+        }
+
+        #[test]
+        fn break_long_let() {
+            assert_eq!(
+                format_let("(let ((x 1) (y 2)) (+ x y))"),
+                "(let ((x 1) (y 2))\n  (+ x y))\n"
+            );
+        }
+
+        #[test]
+        fn break_long_let_bindings() {
+            assert_eq!(
+                format_let("(let ((foo 1) (bar 2) (baz 3)) foo)"),
+                "(let ((foo 1)\n      (bar 2)\n      (baz 3))\n  foo)\n"
+            );
+        }
+
+        fn format_reflowed(source: &str) -> String {
+            format_source(source, &Options::new().set_reflow(true)).unwrap()
+        }
+
+        #[test]
+        fn break_long_list_with_comment_before_right_delimiter() {
+            let formatted = format_reflowed(&format!(
+                "(display \"{}\" \"{}\" ; note\n)",
+                "a".repeat(43),
+                "b".repeat(37)
+            ));
+
+            assert_eq!(
+                formatted,
+                format!(
+                    "(display ; note\n  \"{}\"\n  \"{}\")\n",
+                    "a".repeat(43),
+                    "b".repeat(37)
+                )
+            );
+            assert_eq!(format_reflowed(&formatted), formatted);
+        }
+
+        #[test]
+        fn keep_list_with_nested_blank_line_broken() {
+            let formatted = format_reflowed("(foo\n  (bar\n\n    baz))");
+
+            assert_eq!(formatted, "(foo\n  (bar\n\n    baz))\n");
+            assert_eq!(format_reflowed(&formatted), formatted);
+        }
+
+        #[test]
+        fn keep_list_with_comment_on_last_line_broken() {
+            let formatted = format_reflowed("(() b\n( ; note\n))");
+
+            assert_eq!(formatted, "(() b\n  ()) ; note\n");
+            assert_eq!(format_reflowed(&formatted), formatted);
+        }
+
+        #[test]
+        fn keep_nested_list_within_width() {
+            let formatted =
+                format_let("(define (foo)\n  (let ((x 1))\n    (bar baz\n      qux quuux)))");
+
+            assert_eq!(
+                formatted,
+                "(define (foo)\n  (let ((x 1))\n    (bar baz\n         qux\n         quuux)))\n"
+            );
+            assert!(formatted.lines().all(|line| line.len() <= 24));
+        }
+
+        #[test]
+        fn join_nested_list_within_width() {
+            assert_eq!(
+                format_let("(define (foo)\n  (let ((x 1))\n    (bar baz\n      qux)))"),
+                "(define (foo)\n  (let ((x 1))\n    (bar baz qux)))\n"
+            );
+        }
+    }
+
+    mod byte_order_mark {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn format_source_with_byte_order_mark() {
+            assert_eq!(
+                format_source("\u{FEFF}(foo  bar) ; baz\n", &Options::new()).unwrap(),
+                "\u{FEFF}(foo bar) ; baz\n"
+            );
+        }
+
+        #[test]
+        fn format_hash_directive_after_byte_order_mark() {
+            let source = "\u{FEFF}#!r6rs\n(foo)\n";
+
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+
+        #[test]
+        fn validate_source_with_byte_order_mark() {
+            assert_eq!(validate_source("\u{FEFF}(foo)", &Options::new()), Ok(()));
+        }
+
+        #[test]
+        fn check_source_with_byte_order_mark() {
+            assert_eq!(
+                check_sources(&[("foo.scm", "\u{FEFF}(foo)\n")], &Options::new()),
+                [("foo.scm", CheckResult::Formatted)]
+            );
+        }
+    }
+
+    #[test]
+    fn keep_comments_in_datum_comment() {
+        let source = "#;(a\n   ;inner\n   b)\n";
+
+        assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+    }
+
+    #[test]
+    fn format_escaped_semicolon_followed_by_comment() {
+        let source = "(foo a\\; ; bar\n  b)\n";
+
+        assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+    }
+
+    mod string_delimiters {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn format_delimited(source: &str) -> Result<String, FormatError> {
+            format_source(
+                source,
+                &Options::new()
+                    .set_string_delimiters(Some(("«".into(), "»".into())))
+                    .set_safe(true),
+            )
+        }
+
+        #[test]
+        fn keep_delimited_string() {
+            let source = "(foo «bar \"baz\" \\qux»)\n";
+
+            assert_eq!(format_delimited(source).unwrap(), source);
+        }
+
+        #[test]
+        fn keep_multi_line_delimited_string() {
+            let source = "(foo\n  «bar\n baz»\n  qux)\n";
+
+            assert_eq!(format_delimited(source).unwrap(), source);
+        }
+
+        #[test]
+        fn keep_comment_characters_in_delimited_string() {
+            let source = "(foo «bar ;baz #|qux») ; quux\n";
+
+            assert_eq!(format_delimited(source).unwrap(), source);
+        }
+
+        #[test]
+        fn format_around_delimited_string() {
+            assert_eq!(
+                format_delimited("(foo   «bar  baz»\n\"qux\")").unwrap(),
+                "(foo «bar  baz»\n  \"qux\")\n"
+            );
+        }
+    }
+
+    mod max_inline_comments {
+        use super::*;
+        use indoc::indoc;
+        use pretty_assertions::assert_eq;
+
+        fn format_limited(source: &str) -> String {
+            format_source(source, &Options::new().set_max_inline_comments(Some(2))).unwrap()
+        }
+
+        #[test]
+        fn keep_inline_comments_without_limit() {
+            let source = "(foo #|a|# #|b|# #|c|# bar)\n";
+
+            assert_eq!(format_source(source, &Options::new()).unwrap(), source);
+        }
+
+        #[test]
+        fn keep_inline_comments_at_limit() {
+            let source = "(foo #|a|# #|b|# bar)\n";
+
+            assert_eq!(format_limited(source), source);
+        }
+
+        #[test]
+        fn wrap_inline_comments_before_expression() {
+            let formatted = format_limited("(foo #|a|# #|b|# #|c|# bar)");
+
+            assert_eq!(
+                formatted,
+                indoc!(
+                    "
+                    (foo
+                      #|a|#
+                      #|b|# #|c|# bar)
+                    "
+                )
+            );
+            assert_eq!(format_limited(&formatted), formatted);
+        }
+
+        #[test]
+        fn wrap_inline_comments_before_right_delimiter() {
+            let formatted = format_limited("(foo bar #|a|# #|b|# #|c|# #|d|# #|e|#)");
+
+            assert_eq!(
+                formatted,
+                indoc!(
+                    "
+                    (foo bar #|a|# #|b|#
+                      #|c|# #|d|#
+                      #|e|#)
+                    "
+                )
+            );
+            assert_eq!(format_limited(&formatted), formatted);
+        }
+
+        #[test]
+        fn wrap_top_level_inline_comments() {
+            let formatted = format_limited("#|a|# #|b|# #|c|# foo");
+
+            assert_eq!(formatted, "#|a|#\n#|b|# #|c|# foo\n");
+            assert_eq!(format_limited(&formatted), formatted);
+        }
+
+        fn format_one(source: &str) -> String {
+            format_source(source, &Options::new().set_max_inline_comments(Some(1))).unwrap()
+        }
+
+        #[test]
+        fn wrap_one_inline_comment_per_line_before_expression() {
+            let formatted = format_one("(foo #|a|# #|b|# #|c|# bar)");
+
+            assert_eq!(formatted, "(foo\n  #|a|#\n  #|b|#\n  #|c|# bar)\n");
+            assert_eq!(format_one(&formatted), formatted);
+        }
+
+        #[test]
+        fn wrap_one_inline_comment_per_line_before_right_delimiter() {
+            let formatted = format_one("(foo bar #|a|# #|b|# #|c|#)");
+
+            assert_eq!(formatted, "(foo bar #|a|#\n  #|b|#\n  #|c|#)\n");
+            assert_eq!(format_one(&formatted), formatted);
+        }
+
+        #[test]
+        fn wrap_excess_inline_comments_in_groups() {
+            let formatted = format_limited("(foo #|a|# #|b|# #|c|# #|d|# #|e|# bar)");
+
+            assert_eq!(
+                formatted,
+                "(foo\n  #|a|# #|b|#\n  #|c|#\n  #|d|# #|e|# bar)\n"
+            );
+            assert_eq!(format_limited(&formatted), formatted);
+        }
+
+        #[test]
+        fn expand_multi_line_comment_with_limit() {
+            let formatted = format_one("(foo\n  #|a\nb|#\n  bar)");
+
+            assert_eq!(formatted, "(foo\n  #|\n  a\nb\n  |#\n  bar)\n");
+            assert_eq!(format_one(&formatted), formatted);
+        }
+    }
+}
@@ -1,51 +1,335 @@
-#![feature(allocator_api, iter_intersperse)]
-
-mod ast;
-mod context;
+mod edits;
 mod error;
-mod format;
-mod parse;
-mod position;
-mod position_map;
+mod file;
+mod git;
+mod github;
+mod json;
+mod manifest;
+mod output_format;
+mod sarif;
+mod server;
+mod unparseable_policy;
+mod version;
 
 use crate::{
-    format::format,
-    parse::{parse, parse_comments, parse_hash_directives, ParseError},
-    position_map::PositionMap,
+    edits::render_text_edits,
+    file::{
+        infer_dialect, mirror_path, read_paths, read_source, set_modified_time, top_directory,
+        write_file,
+    },
+    github::render_github,
+    manifest::{render_manifest, ManifestEntry},
+    output_format::OutputFormat,
+    sarif::{render_sarif, SarifResult},
+    server::{serve, DEFAULT_MAX_REQUEST_SIZE},
+    unparseable_policy::UnparseablePolicy,
+    version::satisfies,
 };
-use bumpalo::Bump;
-use clap::Parser;
+use clap::{builder::RangedU64ValueParser, Parser};
 use colored::Colorize;
-use error::ApplicationError;
+use error::{is_broken_pipe, ApplicationError};
 use futures::future::try_join_all;
+use schemat::{
+    find_changed_regions, find_head_symbols, find_long_lines, find_long_strings,
+    find_tab_indented_lines, find_text_edits, format_source_with_timings, is_equivalent,
+    normalize_line_endings, render_diff, validate_source, Dialect, FormatError, HexCase, Options,
+    QuoteIndent, QuoteStyle, Style, Timings,
+};
 use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::BTreeMap,
+    env::set_current_dir,
     error::Error,
+    io::{self, Write},
+    num::NonZeroUsize,
+    ops::Range,
     path::{Path, PathBuf},
     process::ExitCode,
+    sync::Arc,
 };
 use tokio::{
-    fs::{read_to_string, write},
-    io::{stdin, stdout, AsyncReadExt, AsyncWriteExt},
-    spawn,
+    fs::{create_dir_all, metadata, write},
+    io::{stdin, stdout, AsyncReadExt, AsyncWriteExt, BufReader},
+    runtime, spawn,
 };
 
 #[derive(clap::Parser)]
 #[command(about, version)]
 struct Arguments {
     /// Glob patterns of files to format or check the format of.
+    ///
+    /// Directories are searched recursively for Scheme and Lisp files.
     #[arg()]
     paths: Vec<String>,
+    /// A Git reference to restrict files to ones changed since it.
+    ///
+    /// Untracked files are not included.
+    #[arg(long)]
+    since: Option<String>,
+    /// Glob patterns of files to ignore.
+    ///
+    /// By default, they also apply to paths listed explicitly without globs.
+    #[arg(long)]
+    ignore: Vec<String>,
+    /// Do not apply ignore patterns to paths listed explicitly without globs.
+    #[arg(long)]
+    no_ignore_explicit: bool,
+    /// Follow symbolic links to directories while searching directories.
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// A file name of stdin used in messages and to infer a dialect from its
+    /// extension.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["paths", "eval"])]
+    assume_filename: Option<PathBuf>,
+    /// Format sources sent in length-prefixed requests on stdin until its end.
+    ///
+    /// Requests larger than `--max-file-size` or 64 MiB by default are
+    /// rejected.
+    #[arg(long, conflicts_with_all = ["paths", "eval", "check", "assume_filename"])]
+    server: bool,
+    /// Source code to format instead of files or stdin.
+    #[arg(short, long, conflicts_with_all = ["paths", "check"])]
+    eval: Option<String>,
     /// Check if files are formatted correctly.
     #[arg(short, long)]
     check: bool,
+    /// Write formatted files when they are not formatted correctly on check.
+    ///
+    /// Such files are still reported as failures.
+    #[arg(long, requires = "check")]
+    fix: bool,
+    /// Report unformatted files on check without failing.
+    ///
+    /// Files that fail to be read or parsed are still errors.
+    #[arg(long, requires = "check")]
+    tolerate_unformatted: bool,
+    /// A maximum percentage of unformatted files to tolerate on check.
+    ///
+    /// Files that fail to be read or parsed are still errors.
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "check",
+        conflicts_with = "tolerate_unformatted"
+    )]
+    max_unformatted_percent: Option<f64>,
+    /// Treat files as formatted on check if they differ only in whitespace
+    /// outside strings and comments.
+    #[arg(long, requires = "check")]
+    loose_check: bool,
+    /// Head symbols of lists to report as errors on check.
+    ///
+    /// Lists in quoted data and datum comments are not reported.
+    #[arg(long, value_name = "NAME", requires = "check")]
+    forbid_symbol: Vec<String>,
+    /// A file of expected output to compare formatted code of a single file or
+    /// stdin with.
+    ///
+    /// Differences are printed as errors.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["check", "eval", "server", "print", "parse_only", "warn_long_lines", "out_dir"])]
+    expect: Option<PathBuf>,
+    /// Print edits transforming a file or stdin into formatted code as JSON
+    /// without modifying it.
+    ///
+    /// Edits replace only changed lines so that editors can apply them with
+    /// minimal changes.
+    #[arg(long, conflicts_with_all = ["check", "eval", "server", "print", "parse_only", "warn_long_lines", "out_dir", "expect"])]
+    edits: bool,
+    /// Print a formatted file to stdout without modifying it.
+    #[arg(long, requires = "paths", conflicts_with_all = ["check", "eval", "out_dir", "warn_long_lines"])]
+    print: bool,
+    /// Print counts of failed files per top-level directory on check.
+    #[arg(long, requires = "check")]
+    group_by_dir: bool,
+    /// Print a summary of a check also to stdout.
+    ///
+    /// Failures are still reported to stderr.
+    #[arg(long, requires = "check", conflicts_with = "format")]
+    summary_stdout: bool,
+    /// An output format of check results written to stdout.
+    #[arg(long, value_enum, default_value_t, requires = "check")]
+    format: OutputFormat,
+    /// Only check if files are parseable without formatting them.
+    #[arg(long, conflicts_with_all = ["check", "out_dir", "print", "warn_long_lines"])]
+    parse_only: bool,
+    /// A maximum size of files in bytes.
+    ///
+    /// Larger files are skipped with warnings and excluded from file counts.
+    #[arg(long, value_name = "BYTES")]
+    max_file_size: Option<u64>,
+    /// A policy for files that fail to be parsed.
+    ///
+    /// Skipped files are reported as warnings and excluded from file counts.
+    #[arg(long, value_enum, default_value_t)]
+    on_unparseable: UnparseablePolicy,
+    /// Report lines longer than a width in formatted files without formatting them.
+    ///
+    /// Lines with string literals longer than the width are reported as
+    /// unavoidable because string literals are never split.
+    #[arg(long, value_name = "WIDTH", conflicts_with_all = ["check", "out_dir"])]
+    warn_long_lines: Option<usize>,
+    /// Fail files with tabs in indentation without formatting them.
+    ///
+    /// Tabs in indentation of lines inside string literals are ignored.
+    #[arg(long, conflicts_with_all = ["check", "out_dir", "parse_only", "print", "warn_long_lines"])]
+    no_tabs: bool,
+    /// A version requirement of schemat itself, such as `0.2` or `>=0.2.14, <0.4`.
+    ///
+    /// It fails before formatting any file if a running version does not
+    /// satisfy it.
+    #[arg(long, value_name = "REQUIREMENT")]
+    required_version: Option<String>,
     /// Be verbose.
     #[arg(short, long)]
     verbose: bool,
+    /// Preserve modification times of formatted files.
+    #[arg(long)]
+    preserve_mtime: bool,
+    /// Print durations of parsing and formatting files from the slowest ones.
+    #[arg(long)]
+    timings: bool,
+    /// Print a summary of file counts in a machine-readable format.
+    #[arg(long)]
+    porcelain: bool,
+    /// Insert spaces inside braces.
+    #[arg(long)]
+    brace_space: bool,
+    /// Insert exactly one space after semicolons of line comments.
+    #[arg(long)]
+    comment_space: bool,
+    /// A quote style.
+    #[arg(long, value_enum, default_value_t)]
+    quote_style: QuoteStyle,
+    /// A base column of lists prefixed by quote signs like `,@` when they are
+    /// broken.
+    #[arg(long, value_enum, default_value_t)]
+    quote_indent: QuoteIndent,
+    /// Left and right delimiters of strings in addition to double quotes, such
+    /// as `«` and `»`.
+    ///
+    /// Contents of such strings are kept verbatim without escapes.
+    #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"])]
+    string_delimiters: Option<Vec<String>>,
+    /// A letter case of hexadecimal digits in character literals like `#\x41`.
+    #[arg(long, value_enum, default_value_t)]
+    hex_case: HexCase,
+    /// Replace unnecessary escapes in quoted symbols like `|\x41;|` with literal
+    /// characters.
+    #[arg(long)]
+    minimal_symbol_escapes: bool,
+    /// Insert a blank line before `;;;` section header comments.
+    #[arg(long)]
+    header_blank_line: bool,
+    /// Remove trailing whitespace from lines in block comments.
+    #[arg(long)]
+    trim_block_comments: bool,
+    /// Indent lines in block comments to their columns.
+    #[arg(long, conflicts_with = "verbatim_block_comments")]
+    indent_block_comments: bool,
+    /// Keep bodies of block comments verbatim except blank lines around them.
+    #[arg(long)]
+    verbatim_block_comments: bool,
+    /// A maximum number of consecutive blank lines.
+    #[arg(long, default_value_t = 1)]
+    max_blank_lines: usize,
+    /// A number of blank lines between consecutive top-level definitions.
+    #[arg(long, value_name = "COUNT")]
+    blank_between_defines: Option<usize>,
+    /// Head symbols of top-level definitions for `--blank-between-defines`.
+    ///
+    /// It defaults to `define`, `define-syntax`, and other common ones.
+    #[arg(long, value_name = "NAME", requires = "blank_between_defines")]
+    definition_symbol: Vec<String>,
+    /// A maximum number of inline block comments on a single line.
+    ///
+    /// More consecutive block comments are wrapped onto following lines. One-line
+    /// block comments on their own lines are kept on single lines.
+    #[arg(long, value_name = "COUNT", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    max_inline_comments: Option<usize>,
+    /// A maximum number of elements in a list on a single line.
+    ///
+    /// Lists with more elements are always broken.
+    #[arg(long, value_name = "COUNT")]
+    max_inline_elements: Option<usize>,
+    /// A maximum nesting depth of lists and quotes.
+    ///
+    /// Files nested deeper fail to be parsed.
+    #[arg(long, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+    /// Reject brackets and braces not in R7RS as parse errors.
+    #[arg(long)]
+    strict: bool,
+    /// Verify that formatted code has the same expressions as source code.
+    #[arg(long, conflicts_with_all = ["quote_style", "hex_case", "minimal_symbol_escapes"])]
+    safe: bool,
+    /// Keep line breaks between elements of lists as they are.
+    ///
+    /// Only indentation and spacing are normalized.
+    #[arg(long, conflicts_with_all = ["reflow", "max_inline_elements", "keep_arguments"])]
+    preserve_breaks: bool,
+    /// Break lists longer than a maximum width and join short ones.
+    #[arg(long)]
+    reflow: bool,
+    /// A maximum width of lines in columns for reflow.
+    #[arg(long, default_value_t = 80)]
+    max_width: usize,
+    /// A tab width in columns.
+    #[arg(long, default_value_t = 8)]
+    tab_width: usize,
+    /// A style preset of special forms.
+    ///
+    /// Lists not headed by special forms align their arguments.
+    #[arg(long, value_enum)]
+    style: Option<Style>,
+    /// Keep distinguished arguments of special forms on the same lines as their
+    /// heads, such as a condition of `if`.
+    #[arg(long, requires = "style")]
+    keep_arguments: bool,
+    /// A Lisp dialect.
+    ///
+    /// It defaults to one inferred from `--assume-filename` or Scheme.
+    #[arg(long, value_enum)]
+    dialect: Option<Dialect>,
+    /// A number of threads.
+    ///
+    /// It defaults to `TOKIO_WORKER_THREADS` or available parallelism.
+    #[arg(long, visible_alias = "jobs")]
+    threads: Option<NonZeroUsize>,
+    /// A directory to run in instead of a current directory.
+    ///
+    /// Paths, ignore patterns, and output directories are resolved relative to it.
+    #[arg(long, value_name = "DIR")]
+    cwd: Option<PathBuf>,
+    /// A directory to write formatted files into instead of overwriting them.
+    ///
+    /// Relative paths of files from a current directory are preserved.
+    #[arg(long, conflicts_with = "check")]
+    out_dir: Option<PathBuf>,
+    /// A file to write a JSON manifest of formatted files into.
+    ///
+    /// It lists whether each file is changed and its new byte length, or an
+    /// error of it.
+    #[arg(long, value_name = "FILE", requires = "paths", conflicts_with_all = ["check", "print", "parse_only", "warn_long_lines", "expect"])]
+    manifest: Option<PathBuf>,
 }
 
-#[tokio::main]
-async fn main() -> ExitCode {
-    if let Err(error) = run(Arguments::parse()).await {
+fn main() -> ExitCode {
+    let arguments = Arguments::parse();
+
+    if let Err(error) = arguments
+        .cwd
+        .as_ref()
+        .map_or(Ok(()), set_current_dir)
+        .and_then(|_| build_runtime(&arguments))
+        .map_err(Box::from)
+        .and_then(|runtime| runtime.block_on(run(arguments)))
+    {
+        // Exit successfully on a closed stdout like `schemat < foo.scm | head`.
+        if is_broken_pipe(&*error) {
+            return ExitCode::SUCCESS;
+        }
+
         eprintln!("{}", error);
         ExitCode::FAILURE
     } else {
@@ -53,73 +337,400 @@ async fn main() -> ExitCode {
     }
 }
 
+fn build_runtime(arguments: &Arguments) -> io::Result<runtime::Runtime> {
+    let mut builder = runtime::Builder::new_multi_thread();
+
+    if let Some(threads) = arguments.threads {
+        builder
+            .worker_threads(threads.get())
+            .max_blocking_threads(threads.get());
+    }
+
+    builder.enable_all().build()
+}
+
 async fn run(arguments: Arguments) -> Result<(), Box<dyn Error>> {
-    if arguments.paths.is_empty() && arguments.check {
+    if let Some(requirement) = &arguments.required_version {
+        check_version(requirement)?;
+    }
+
+    let options = Options::new()
+        .set_brace_space(arguments.brace_space)
+        .set_comment_space(arguments.comment_space)
+        .set_quote_style(arguments.quote_style)
+        .set_quote_indent(arguments.quote_indent)
+        .set_dialect(
+            arguments
+                .dialect
+                .or_else(|| arguments.assume_filename.as_deref().and_then(infer_dialect))
+                .unwrap_or_default(),
+        )
+        .set_tab_width(arguments.tab_width)
+        .set_reflow(arguments.reflow)
+        .set_preserve_breaks(arguments.preserve_breaks)
+        .set_safe(arguments.safe)
+        .set_strict(arguments.strict)
+        .set_max_depth(arguments.max_depth)
+        .set_max_width(arguments.max_width)
+        .set_header_blank_line(arguments.header_blank_line)
+        .set_hex_case(arguments.hex_case)
+        .set_minimal_symbol_escapes(arguments.minimal_symbol_escapes)
+        .set_style(arguments.style)
+        .set_keep_arguments(arguments.keep_arguments)
+        .set_max_blank_lines(arguments.max_blank_lines)
+        .set_max_inline_comments(arguments.max_inline_comments)
+        .set_max_inline_elements(arguments.max_inline_elements)
+        .set_definition_blank_lines(arguments.blank_between_defines)
+        .set_trim_block_comments(arguments.trim_block_comments)
+        .set_indent_block_comments(arguments.indent_block_comments)
+        .set_verbatim_block_comments(arguments.verbatim_block_comments);
+
+    let options = match arguments.string_delimiters.as_deref() {
+        Some([left, right]) if left.is_empty() || right.is_empty() => {
+            return Err("string delimiters cannot be empty".into())
+        }
+        Some([left, right]) => options.set_string_delimiters(Some((left.clone(), right.clone()))),
+        _ => options,
+    };
+
+    let options = if arguments.definition_symbol.is_empty() {
+        options
+    } else {
+        options.set_definition_symbols(arguments.definition_symbol.clone())
+    };
+
+    if arguments.server {
+        serve(
+            BufReader::new(stdin()),
+            stdout(),
+            arguments.max_file_size.unwrap_or(DEFAULT_MAX_REQUEST_SIZE),
+            &options,
+        )
+        .await
+    } else if let Some(source) = &arguments.eval {
+        print_formatted(source, "<eval>", &options).await
+    } else if let Some(expected) = &arguments.expect {
+        expect_formatted(
+            &arguments.paths,
+            &stdin_name(&arguments),
+            expected,
+            arguments.max_file_size,
+            &options,
+        )
+        .await
+    } else if arguments.edits {
+        print_edits(
+            &arguments.paths,
+            &stdin_name(&arguments),
+            arguments.max_file_size,
+            &options,
+        )
+        .await
+    } else if arguments.paths.is_empty()
+        && (arguments.check
+            || arguments.parse_only
+            || arguments.no_tabs
+            || arguments.warn_long_lines.is_some())
+    {
         Err("cannot check stdin".into())
     } else if arguments.paths.is_empty() {
-        format_stdin().await
+        format_stdin(&stdin_name(&arguments), &options).await
+    } else if arguments.print {
+        print_path(&arguments.paths, arguments.max_file_size, &options).await
+    } else if arguments.parse_only {
+        validate_paths(&arguments, options).await
+    } else if let Some(width) = arguments.warn_long_lines {
+        warn_long_lines(&arguments, options, width).await
+    } else if arguments.no_tabs {
+        check_tabs(&arguments, options).await
     } else if arguments.check {
-        check_paths(&arguments.paths, arguments.verbose).await
+        check_paths(&arguments, options).await
     } else {
-        format_paths(&arguments.paths, arguments.verbose).await
+        format_paths(&arguments, options).await
     }
 }
 
-async fn check_paths(paths: &[String], verbose: bool) -> Result<(), Box<dyn Error>> {
+fn check_version(requirement: &str) -> Result<(), Box<dyn Error>> {
+    let version = env!("CARGO_PKG_VERSION");
+
+    match satisfies(requirement, version) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!(
+            "schemat {} does not satisfy required version {}",
+            version, requirement
+        )
+        .into()),
+        Err(error) => {
+            Err(format!("invalid version requirement: {}: {}", requirement, error).into())
+        }
+    }
+}
+
+async fn check_paths(arguments: &Arguments, options: Options) -> Result<(), Box<dyn Error>> {
+    let paths = read_paths(
+        &arguments.paths,
+        &arguments.ignore,
+        !arguments.no_ignore_explicit,
+        arguments.follow_symlinks,
+        arguments.since.as_deref(),
+    )
+    .await?;
+
+    let fix = arguments.fix;
+    let loose = arguments.loose_check;
+    let max_file_size = arguments.max_file_size;
+    let forbidden_symbols = Arc::new(arguments.forbid_symbol.clone());
     let mut count = 0;
+    let mut failure_count = 0;
     let mut error_count = 0;
 
-    for result in try_join_all(read_paths(paths)?.map(|path| {
-        spawn(async {
-            let success = check_path(&path).await?;
-            Ok::<_, ApplicationError>((path, success))
+    let mut results = vec![];
+    let mut directory_counts = BTreeMap::<PathBuf, usize>::new();
+    let mut annotations = vec![];
+    let mut timings = vec![];
+
+    for (path, result) in try_join_all(paths.map(|path| {
+        let forbidden_symbols = forbidden_symbols.clone();
+        let options = options.clone();
+
+        spawn(async move {
+            let result = check_path(
+                &path,
+                &options,
+                fix,
+                loose,
+                max_file_size,
+                &forbidden_symbols,
+            )
+            .await;
+            (path, result)
         })
     }))
     .await?
     {
+        let result = result.map(|(lines, file_timings)| {
+            timings.push((path.clone(), file_timings));
+            lines
+        });
+
+        if skip_file(arguments.on_unparseable, &result) {
+            continue;
+        }
+
         count += 1;
 
+        if !matches!(result, Ok(None)) {
+            *directory_counts.entry(top_directory(&path)).or_default() += 1;
+        }
+
         match result {
-            Ok((path, path_success)) => {
-                if !path_success {
-                    eprintln!("{}\t{}", "FAIL".yellow(), path.display());
-                    error_count += 1;
-                } else if verbose {
+            Ok(None) => {
+                if arguments.verbose {
                     eprintln!("{}\t{}", "OK".green(), path.display());
                 }
             }
+            Ok(Some(regions)) => {
+                eprintln!("{}\t{}", "FAIL".yellow(), path.display());
+                annotations.extend(regions.iter().map(|lines| {
+                    format!("{}:{}: needs formatting", path.display(), lines.start + 1)
+                }));
+                results.push(SarifResult::unformatted(
+                    &path.display().to_string(),
+                    regions[0].start..regions[regions.len() - 1].end,
+                ));
+                failure_count += 1;
+            }
             Err(error) => {
                 eprintln!("{}\t{}", "ERROR".red(), error);
+                let result = SarifResult::error(&path.display().to_string(), &error);
+                annotations.push(format!(
+                    "{}:{}: {}",
+                    result.path(),
+                    result.lines().start + 1,
+                    result.message()
+                ));
+                results.push(result);
                 error_count += 1;
             }
         }
     }
 
+    match arguments.format {
+        OutputFormat::Text => {}
+        OutputFormat::Sarif => writeln!(io::stdout(), "{}", render_sarif(&results))?,
+        OutputFormat::Github => {
+            for result in &results {
+                writeln!(io::stdout(), "{}", render_github(result))?;
+            }
+        }
+        OutputFormat::Annotations => {
+            for annotation in &annotations {
+                writeln!(io::stdout(), "{}", annotation)?;
+            }
+        }
+    }
+
+    if arguments.group_by_dir {
+        for (directory, count) in directory_counts {
+            eprintln!("{}/: {} failed", directory.display(), count);
+        }
+    }
+
+    if arguments.porcelain {
+        print_porcelain(count, failure_count, error_count)?;
+    }
+
+    if arguments.timings {
+        print_timings(timings);
+    }
+
+    // Errors always fail a check while unformatted files can be tolerated.
+    let failure_count = if arguments.tolerate_unformatted
+        || arguments
+            .max_unformatted_percent
+            .is_some_and(|percent| failure_count as f64 * 100.0 <= percent * count as f64)
+    {
+        error_count
+    } else {
+        failure_count + error_count
+    };
+
+    let summary = format!("{} / {} file(s) failed", failure_count, count);
+
+    if arguments.summary_stdout {
+        writeln!(io::stdout(), "{}", summary)?;
+    }
+
+    if failure_count == 0 {
+        Ok(())
+    } else {
+        Err(summary.into())
+    }
+}
+
+async fn format_paths(arguments: &Arguments, options: Options) -> Result<(), Box<dyn Error>> {
+    let paths = read_paths(
+        &arguments.paths,
+        &arguments.ignore,
+        !arguments.no_ignore_explicit,
+        arguments.follow_symlinks,
+        arguments.since.as_deref(),
+    )
+    .await?;
+
+    let preserve_mtime = arguments.preserve_mtime;
+    let max_file_size = arguments.max_file_size;
+    let out_dir = arguments.out_dir.clone();
+    let mut count = 0;
+    let mut change_count = 0;
+    let mut error_count = 0;
+    let mut timings = vec![];
+    let mut entries = vec![];
+
+    for (path, result) in try_join_all(paths.map(|path| {
+        let out_dir = out_dir.clone();
+        let options = options.clone();
+
+        spawn(async move {
+            let result = format_path(
+                &path,
+                &options,
+                preserve_mtime,
+                max_file_size,
+                out_dir.as_deref(),
+            )
+            .await;
+            (path, result)
+        })
+    }))
+    .await?
+    {
+        if skip_file(arguments.on_unparseable, &result) {
+            continue;
+        }
+
+        count += 1;
+
+        match result {
+            Ok((changed, length, file_timings)) => {
+                timings.push((path.clone(), file_timings));
+                entries.push(ManifestEntry::formatted(
+                    &path.display().to_string(),
+                    changed,
+                    length,
+                ));
+
+                if changed {
+                    change_count += 1;
+                }
+
+                if arguments.verbose {
+                    eprintln!("{}\t{}", "FORMAT".blue(), path.display());
+                }
+            }
+            Err(error) => {
+                eprintln!("{}\t{}", "ERROR".red(), error);
+                entries.push(ManifestEntry::error(&path.display().to_string(), &error));
+                error_count += 1;
+            }
+        }
+    }
+
+    if let Some(path) = &arguments.manifest {
+        write(path, render_manifest(&entries) + "\n").await?;
+    }
+
+    if arguments.porcelain {
+        print_porcelain(count, change_count, error_count)?;
+    }
+
+    if arguments.timings {
+        print_timings(timings);
+    }
+
     if error_count == 0 {
         Ok(())
     } else {
-        Err(format!("{} / {} file(s) failed", error_count, count).into())
+        Err(format!("{} / {} file(s) failed to format", error_count, count).into())
     }
 }
 
-async fn format_paths(paths: &[String], verbose: bool) -> Result<(), Box<dyn Error>> {
+async fn validate_paths(arguments: &Arguments, options: Options) -> Result<(), Box<dyn Error>> {
+    let paths = read_paths(
+        &arguments.paths,
+        &arguments.ignore,
+        !arguments.no_ignore_explicit,
+        arguments.follow_symlinks,
+        arguments.since.as_deref(),
+    )
+    .await?;
+
+    let max_file_size = arguments.max_file_size;
     let mut count = 0;
     let mut error_count = 0;
 
-    for result in try_join_all(read_paths(paths)?.map(|path| {
-        spawn(async {
-            format_path(&path).await?;
+    for result in try_join_all(paths.map(|path| {
+        let options = options.clone();
+
+        spawn(async move {
+            let source = read_source(&path, max_file_size).await?;
+            validate_string(&source, &path.display().to_string(), &options)?;
+
             Ok::<_, ApplicationError>(path)
         })
     }))
     .await?
     {
+        if skip_file(arguments.on_unparseable, &result) {
+            continue;
+        }
+
         count += 1;
 
         match result {
             Ok(path) => {
-                if verbose {
-                    eprintln!("{}\t{}", "FORMAT".blue(), path.display());
+                if arguments.verbose {
+                    eprintln!("{}\t{}", "OK".green(), path.display());
                 }
             }
             Err(error) => {
@@ -132,83 +743,470 @@ async fn format_paths(paths: &[String], verbose: bool) -> Result<(), Box<dyn Err
     if error_count == 0 {
         Ok(())
     } else {
-        Err(format!("{} / {} file(s) failed to format", error_count, count).into())
+        Err(format!("{} / {} file(s) failed", error_count, count).into())
+    }
+}
+
+async fn warn_long_lines(
+    arguments: &Arguments,
+    options: Options,
+    width: usize,
+) -> Result<(), Box<dyn Error>> {
+    let paths = read_paths(
+        &arguments.paths,
+        &arguments.ignore,
+        !arguments.no_ignore_explicit,
+        arguments.follow_symlinks,
+        arguments.since.as_deref(),
+    )
+    .await?;
+
+    let max_file_size = arguments.max_file_size;
+    let mut count = 0;
+    let mut error_count = 0;
+
+    for result in try_join_all(paths.map(|path| {
+        let options = options.clone();
+
+        spawn(async move {
+            let source = read_source(&path, max_file_size).await?;
+            let name = path.display().to_string();
+            let formatted = format_string(&source, &name, &options)?;
+            let strings = find_long_strings(&formatted, width, &options).map_err(|error| {
+                ApplicationError::parse(error, &name, &formatted, options.tab_width())
+            })?;
+            let lines = find_long_lines(&formatted, width, options.tab_width())
+                .map(|(index, line_width)| (index, line_width, strings.contains(&index)))
+                .collect::<Vec<_>>();
+
+            Ok::<_, ApplicationError>((path, lines))
+        })
+    }))
+    .await?
+    {
+        if skip_file(arguments.on_unparseable, &result) {
+            continue;
+        }
+
+        count += 1;
+
+        match result {
+            Ok((path, lines)) => {
+                for (index, line_width, unavoidable) in lines {
+                    eprintln!(
+                        "{}\t{}:{}: {} columns exceed {}{}",
+                        "WARN".yellow(),
+                        path.display(),
+                        index + 1,
+                        line_width,
+                        width,
+                        if unavoidable {
+                            " (unavoidable due to a long string)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+            }
+            Err(error) => {
+                eprintln!("{}\t{}", "ERROR".red(), error);
+                error_count += 1;
+            }
+        }
+    }
+
+    if error_count == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} / {} file(s) failed", error_count, count).into())
+    }
+}
+
+async fn check_tabs(arguments: &Arguments, options: Options) -> Result<(), Box<dyn Error>> {
+    let paths = read_paths(
+        &arguments.paths,
+        &arguments.ignore,
+        !arguments.no_ignore_explicit,
+        arguments.follow_symlinks,
+        arguments.since.as_deref(),
+    )
+    .await?;
+
+    let max_file_size = arguments.max_file_size;
+    let mut count = 0;
+    let mut failure_count = 0;
+    let mut error_count = 0;
+
+    for result in try_join_all(paths.map(|path| {
+        let options = options.clone();
+
+        spawn(async move {
+            let source = read_source(&path, max_file_size).await?;
+            let name = path.display().to_string();
+            let source = normalize_source(&source, &name);
+            let lines = find_tab_indented_lines(&source, &options).map_err(|error| {
+                ApplicationError::parse(error, &name, &source, options.tab_width())
+            })?;
+
+            Ok::<_, ApplicationError>((path, lines))
+        })
+    }))
+    .await?
+    {
+        if skip_file(arguments.on_unparseable, &result) {
+            continue;
+        }
+
+        count += 1;
+
+        match result {
+            Ok((path, lines)) => {
+                if lines.is_empty() {
+                    if arguments.verbose {
+                        eprintln!("{}\t{}", "OK".green(), path.display());
+                    }
+                } else {
+                    for index in lines {
+                        eprintln!(
+                            "{}\t{}:{}: tabs in indentation",
+                            "FAIL".yellow(),
+                            path.display(),
+                            index + 1,
+                        );
+                    }
+
+                    failure_count += 1;
+                }
+            }
+            Err(error) => {
+                eprintln!("{}\t{}", "ERROR".red(), error);
+                error_count += 1;
+            }
+        }
+    }
+
+    if failure_count + error_count == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} / {} file(s) failed", failure_count + error_count, count).into())
     }
 }
 
-fn read_paths(paths: &[String]) -> Result<impl Iterator<Item = PathBuf>, ApplicationError> {
-    Ok(paths
-        .iter()
-        .map(|path| Ok::<_, ApplicationError>(glob::glob(path)?.collect::<Result<Vec<_>, _>>()?))
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .flatten())
+fn skip_file<T>(policy: UnparseablePolicy, result: &Result<T, ApplicationError>) -> bool {
+    match result {
+        Err(error @ ApplicationError::FileTooLarge { .. }) => {
+            eprintln!("{}\t{}", "SKIP".yellow(), error);
+            true
+        }
+        Err(error @ ApplicationError::Parse { .. }) if policy == UnparseablePolicy::Skip => {
+            eprintln!("{}\t{}", "SKIP".yellow(), error);
+            true
+        }
+        _ => false,
+    }
 }
 
-async fn format_stdin() -> Result<(), Box<dyn Error>> {
+fn print_timings(mut timings: Vec<(PathBuf, Timings)>) {
+    timings.sort_by_key(|(_, timings)| Reverse(timings.total()));
+
+    for (path, timings) in &timings {
+        print_timing(&path.display().to_string(), timings);
+    }
+
+    print_timing(
+        "total",
+        &timings
+            .iter()
+            .fold(Timings::default(), |total, (_, timings)| total + *timings),
+    );
+}
+
+fn print_timing(name: &str, timings: &Timings) {
+    eprintln!(
+        "{}\t{}: {:.2?} parse, {:.2?} format",
+        "TIME".blue(),
+        name,
+        timings.parse(),
+        timings.format()
+    );
+}
+
+fn print_porcelain(count: usize, change_count: usize, error_count: usize) -> io::Result<()> {
+    writeln!(
+        io::stdout(),
+        "ok={} changed={} error={}",
+        count - change_count - error_count,
+        change_count,
+        error_count
+    )
+}
+
+async fn format_stdin(name: &str, options: &Options) -> Result<(), Box<dyn Error>> {
     let mut source = Default::default();
     stdin().read_to_string(&mut source).await?;
-    let position_map = PositionMap::new(&source);
-    let convert_error =
-        |error: ParseError| convert_parse_error(error, "<stdin>", &source, &position_map);
-    let allocator = Bump::new();
-
-    stdout()
-        .write_all(
-            format(
-                &parse(&source, &allocator).map_err(convert_error)?,
-                &parse_comments(&source, &allocator).map_err(convert_error)?,
-                &parse_hash_directives(&source, &allocator).map_err(convert_error)?,
-                &position_map,
-                &allocator,
-            )?
-            .as_bytes(),
-        )
-        .await?;
 
-    Ok(())
+    print_formatted(&source, name, options).await
 }
 
-async fn check_path(path: &Path) -> Result<bool, ApplicationError> {
-    let source = read_to_string(path).await?;
+async fn print_path(
+    paths: &[String],
+    max_file_size: Option<u64>,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let [path] = paths else {
+        return Err("cannot print multiple files".into());
+    };
 
-    Ok(source == format_string(&source, &path.display().to_string())?)
+    print_formatted(
+        &read_source(Path::new(path), max_file_size).await?,
+        path,
+        options,
+    )
+    .await
 }
 
-async fn format_path(path: &Path) -> Result<(), ApplicationError> {
-    let source = read_to_string(path).await?;
-    let formatted = format_string(&source, &path.display().to_string())?;
+// Returns a name of stdin used in messages.
+fn stdin_name(arguments: &Arguments) -> String {
+    arguments
+        .assume_filename
+        .as_ref()
+        .map_or("<stdin>".into(), |path| path.display().to_string())
+}
+
+async fn expect_formatted(
+    paths: &[String],
+    stdin_name: &str,
+    expected: &Path,
+    max_file_size: Option<u64>,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let (source, name) = match paths {
+        [] => {
+            let mut source = Default::default();
+            stdin().read_to_string(&mut source).await?;
+            (source, stdin_name.into())
+        }
+        [path] => (
+            read_source(Path::new(path), max_file_size).await?,
+            path.clone(),
+        ),
+        _ => return Err("cannot expect formatted code of multiple files".into()),
+    };
+    let formatted = format_string(&source, &name, options)?;
+    let expected = read_source(expected, None).await?;
 
-    // Skip write to a file to improve performance and reduce workload to a file
-    // system if the file is formatted already.
-    if source != formatted {
-        write(path, formatted).await?;
+    if formatted == expected {
+        Ok(())
+    } else {
+        Err(ApplicationError::Unexpected {
+            name,
+            diff: render_diff(&expected, &formatted),
+        }
+        .into())
     }
+}
+
+async fn print_edits(
+    paths: &[String],
+    stdin_name: &str,
+    max_file_size: Option<u64>,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let (source, name) = match paths {
+        [] => {
+            let mut source = Default::default();
+            stdin().read_to_string(&mut source).await?;
+            (source, stdin_name.into())
+        }
+        [path] => (
+            read_source(Path::new(path), max_file_size).await?,
+            path.clone(),
+        ),
+        _ => return Err("cannot print edits of multiple files".into()),
+    };
+    let formatted = format_string(&source, &name, options)?;
+
+    writeln!(
+        io::stdout(),
+        "{}",
+        render_text_edits(&find_text_edits(&source, &formatted))
+    )?;
 
     Ok(())
 }
 
-fn format_string(source: &str, name: &str) -> Result<String, ApplicationError> {
-    let position_map = PositionMap::new(source);
-    let convert_error = |error: ParseError| convert_parse_error(error, name, source, &position_map);
-    let allocator = Bump::new();
+async fn print_formatted(
+    source: &str,
+    name: &str,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let mut stdout = stdout();
 
-    let source = format(
-        &parse(source, &allocator).map_err(convert_error)?,
-        &parse_comments(source, &allocator).map_err(convert_error)?,
-        &parse_hash_directives(source, &allocator).map_err(convert_error)?,
-        &position_map,
-        &allocator,
-    )?;
+    stdout
+        .write_all(format_string(source, name, options)?.as_bytes())
+        .await?;
+    stdout.flush().await?;
 
-    Ok(source)
+    Ok(())
 }
 
-fn convert_parse_error(
-    error: ParseError,
+async fn check_path(
+    path: &Path,
+    options: &Options,
+    fix: bool,
+    loose: bool,
+    max_file_size: Option<u64>,
+    forbidden_symbols: &[String],
+) -> Result<(Option<Vec<Range<usize>>>, Timings), ApplicationError> {
+    let source = read_source(path, max_file_size).await?;
+    let name = path.display().to_string();
+    let (formatted, timings) = format_string_with_timings(&source, &name, options)?;
+
+    if !forbidden_symbols.is_empty() {
+        check_forbidden_symbols(&source, &name, options, forbidden_symbols)?;
+    }
+
+    if source == formatted
+        || loose
+            && is_equivalent(&source, &formatted, options).map_err(|error| {
+                ApplicationError::parse(error, &name, &source, options.tab_width())
+            })?
+    {
+        return Ok((None, timings));
+    } else if fix {
+        write_file(path, &formatted).await?;
+    }
+
+    Ok((Some(find_changed_regions(&source, &formatted)), timings))
+}
+
+fn check_forbidden_symbols(
+    source: &str,
     name: &str,
+    options: &Options,
+    forbidden_symbols: &[String],
+) -> Result<(), ApplicationError> {
+    let source = normalize_line_endings(source).unwrap_or_else(|| source.into());
+    let positions = find_head_symbols(
+        &source,
+        &forbidden_symbols
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+        options,
+    )
+    .map_err(|error| ApplicationError::parse(error, name, &source, options.tab_width()))?;
+
+    if positions.is_empty() {
+        Ok(())
+    } else {
+        Err(ApplicationError::ForbiddenSymbols {
+            name: name.into(),
+            source,
+            positions,
+            tab_width: options.tab_width(),
+        })
+    }
+}
+
+async fn format_path(
+    path: &Path,
+    options: &Options,
+    preserve_mtime: bool,
+    max_file_size: Option<u64>,
+    out_dir: Option<&Path>,
+) -> Result<(bool, usize, Timings), ApplicationError> {
+    let source = read_source(path, max_file_size).await?;
+    let (formatted, timings) =
+        format_string_with_timings(&source, &path.display().to_string(), options)?;
+
+    if let Some(directory) = out_dir {
+        let path = mirror_path(directory, path)?;
+
+        if let Some(directory) = path.parent() {
+            create_dir_all(directory).await?;
+        }
+
+        write(path, &formatted).await?;
+    } else if source != formatted {
+        // Skip write to a file to improve performance and reduce workload to a
+        // file system if the file is formatted already.
+        let modified_time = if preserve_mtime {
+            Some(metadata(path).await?.modified()?)
+        } else {
+            None
+        };
+
+        write_file(path, &formatted).await?;
+
+        if let Some(time) = modified_time {
+            set_modified_time(path, time).await?;
+        }
+    }
+
+    Ok((source != formatted, formatted.len(), timings))
+}
+
+fn format_string(source: &str, name: &str, options: &Options) -> Result<String, ApplicationError> {
+    format_string_with_timings(source, name, options).map(|(string, _)| string)
+}
+
+fn format_string_with_timings(
     source: &str,
-    position_map: &PositionMap,
-) -> ApplicationError {
-    ApplicationError::Parse(error.to_string(name, source, position_map))
+    name: &str,
+    options: &Options,
+) -> Result<(String, Timings), ApplicationError> {
+    let source = normalize_source(source, name);
+
+    format_source_with_timings(&source, options).map_err(|error| match error {
+        FormatError::Format(error) => error.into(),
+        FormatError::Mismatch => ApplicationError::Mismatch(name.into()),
+        FormatError::Parse(error) => {
+            ApplicationError::parse(error, name, &source, options.tab_width())
+        }
+    })
+}
+
+fn validate_string(source: &str, name: &str, options: &Options) -> Result<(), ApplicationError> {
+    let source = normalize_source(source, name);
+
+    validate_source(&source, options)
+        .map_err(|error| ApplicationError::parse(error, name, &source, options.tab_width()))
+}
+
+fn normalize_source<'a>(source: &'a str, name: &str) -> Cow<'a, str> {
+    if let Some(source) = normalize_line_endings(source) {
+        eprintln!(
+            "{}\t{}: converted CR line endings to LF",
+            "WARN".yellow(),
+            name
+        );
+
+        source.into()
+    } else {
+        source.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tokio::runtime::Handle;
+
+    fn count_workers(arguments: &[&str]) -> usize {
+        build_runtime(&Arguments::parse_from(arguments))
+            .unwrap()
+            .block_on(async { Handle::current().metrics().num_workers() })
+    }
+
+    #[test]
+    fn build_runtime_with_threads() {
+        assert_eq!(count_workers(&["schemat", "--threads", "3"]), 3);
+    }
+
+    #[test]
+    fn build_runtime_with_jobs() {
+        assert_eq!(count_workers(&["schemat", "--jobs", "2"]), 2);
+    }
 }
@@ -0,0 +1,81 @@
+use crate::{error::ApplicationError, json::escape};
+
+/// An entry of a file in a manifest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ManifestEntry {
+    path: String,
+    result: Result<(bool, usize), String>,
+}
+
+impl ManifestEntry {
+    /// Creates an entry of a formatted file with a byte length of formatted
+    /// code.
+    pub fn formatted(path: &str, changed: bool, length: usize) -> Self {
+        Self {
+            path: path.into(),
+            result: Ok((changed, length)),
+        }
+    }
+
+    /// Creates an entry of a file failed to be formatted.
+    pub fn error(path: &str, error: &ApplicationError) -> Self {
+        Self {
+            path: path.into(),
+            result: Err(match error {
+                ApplicationError::Parse { error, .. } => error.message().into(),
+                error => error.to_string(),
+            }),
+        }
+    }
+}
+
+/// Renders entries as a JSON manifest.
+pub fn render_manifest(entries: &[ManifestEntry]) -> String {
+    format!(
+        r#"{{"files":[{}]}}"#,
+        entries
+            .iter()
+            .map(render_entry)
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+fn render_entry(entry: &ManifestEntry) -> String {
+    format!(
+        r#"{{"path":"{}",{}}}"#,
+        escape(&entry.path),
+        match &entry.result {
+            Ok((changed, length)) => format!(r#""changed":{},"length":{}"#, changed, length),
+            Err(message) => format!(r#""error":"{}""#, escape(message)),
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io;
+
+    #[test]
+    fn render_no_entry() {
+        assert_eq!(render_manifest(&[]), r#"{"files":[]}"#);
+    }
+
+    #[test]
+    fn render_entries() {
+        assert_eq!(
+            render_manifest(&[
+                ManifestEntry::formatted("foo.scm", true, 4),
+                ManifestEntry::formatted("bar.scm", false, 0),
+                ManifestEntry::error("baz.scm", &io::Error::other("qux \"quux\"").into()),
+            ]),
+            concat!(
+                r#"{"files":[{"path":"foo.scm","changed":true,"length":4},"#,
+                r#"{"path":"bar.scm","changed":false,"length":0},"#,
+                r#"{"path":"baz.scm","error":"qux \"quux\""}]}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,427 @@
+mod dialect;
+mod hex_case;
+mod quote_indent;
+mod quote_style;
+mod style;
+
+pub use self::{
+    dialect::Dialect, hex_case::HexCase, quote_indent::QuoteIndent, quote_style::QuoteStyle,
+    style::Style,
+};
+
+const DEFAULT_DEFINITION_SYMBOLS: &[&str] = &[
+    "define",
+    "define-library",
+    "define-record-type",
+    "define-syntax",
+    "define-values",
+    "defmacro",
+    "defun",
+    "defvar",
+];
+
+/// Format options.
+#[derive(Clone, Debug)]
+pub struct Options {
+    brace_space: bool,
+    comment_space: bool,
+    definition_blank_lines: Option<usize>,
+    definition_symbols: Vec<String>,
+    dialect: Dialect,
+    header_blank_line: bool,
+    hex_case: HexCase,
+    indent_block_comments: bool,
+    keep_arguments: bool,
+    max_blank_lines: usize,
+    max_depth: Option<usize>,
+    max_inline_comments: Option<usize>,
+    max_inline_elements: Option<usize>,
+    max_width: usize,
+    minimal_symbol_escapes: bool,
+    preserve_breaks: bool,
+    quote_indent: QuoteIndent,
+    quote_style: QuoteStyle,
+    reflow: bool,
+    safe: bool,
+    strict: bool,
+    string_delimiters: Option<(String, String)>,
+    style: Option<Style>,
+    tab_width: usize,
+    trim_block_comments: bool,
+    verbatim_block_comments: bool,
+}
+
+impl Options {
+    /// Creates default options.
+    pub fn new() -> Self {
+        Self {
+            brace_space: false,
+            comment_space: false,
+            definition_blank_lines: None,
+            definition_symbols: DEFAULT_DEFINITION_SYMBOLS
+                .iter()
+                .map(|&symbol| symbol.into())
+                .collect(),
+            dialect: Dialect::Scheme,
+            header_blank_line: false,
+            hex_case: HexCase::Preserve,
+            indent_block_comments: false,
+            keep_arguments: false,
+            max_blank_lines: 1,
+            max_depth: None,
+            max_inline_comments: None,
+            max_inline_elements: None,
+            max_width: 80,
+            minimal_symbol_escapes: false,
+            preserve_breaks: false,
+            quote_indent: QuoteIndent::Datum,
+            quote_style: QuoteStyle::Preserve,
+            reflow: false,
+            safe: false,
+            strict: false,
+            string_delimiters: None,
+            style: None,
+            tab_width: 8,
+            trim_block_comments: false,
+            verbatim_block_comments: false,
+        }
+    }
+
+    /// Returns `true` if spaces are inserted inside braces.
+    pub const fn brace_space(&self) -> bool {
+        self.brace_space
+    }
+
+    /// Returns `true` if exactly one space is inserted after semicolons of line
+    /// comments.
+    pub const fn comment_space(&self) -> bool {
+        self.comment_space
+    }
+
+    /// Returns a number of blank lines between consecutive top-level
+    /// definitions.
+    pub const fn definition_blank_lines(&self) -> Option<usize> {
+        self.definition_blank_lines
+    }
+
+    /// Returns head symbols of top-level definitions.
+    pub const fn definition_symbols(&self) -> &[String] {
+        self.definition_symbols.as_slice()
+    }
+
+    /// Returns a dialect.
+    pub const fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Returns `true` if a blank line is inserted before section header comments.
+    pub const fn header_blank_line(&self) -> bool {
+        self.header_blank_line
+    }
+
+    /// Returns a letter case of hexadecimal digits in character literals.
+    pub const fn hex_case(&self) -> HexCase {
+        self.hex_case
+    }
+
+    /// Returns `true` if lines in block comments are indented to their columns.
+    pub const fn indent_block_comments(&self) -> bool {
+        self.indent_block_comments
+    }
+
+    /// Returns `true` if special forms keep their distinguished arguments on
+    /// the same lines as their heads.
+    pub const fn keep_arguments(&self) -> bool {
+        self.keep_arguments
+    }
+
+    /// Returns a maximum number of consecutive blank lines.
+    pub const fn max_blank_lines(&self) -> usize {
+        self.max_blank_lines
+    }
+
+    /// Returns a maximum nesting depth of lists and quotes in a source.
+    pub const fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Returns a maximum number of inline block comments on a single line.
+    pub const fn max_inline_comments(&self) -> Option<usize> {
+        self.max_inline_comments
+    }
+
+    /// Returns a maximum number of elements in a list on a single line.
+    pub const fn max_inline_elements(&self) -> Option<usize> {
+        self.max_inline_elements
+    }
+
+    /// Returns a maximum width of lines in columns.
+    pub const fn max_width(&self) -> usize {
+        self.max_width
+    }
+
+    /// Returns `true` if unnecessary escapes in quoted symbols are replaced with
+    /// literal characters.
+    pub const fn minimal_symbol_escapes(&self) -> bool {
+        self.minimal_symbol_escapes
+    }
+
+    /// Returns `true` if line breaks between elements of lists are kept as
+    /// they are.
+    pub const fn preserve_breaks(&self) -> bool {
+        self.preserve_breaks
+    }
+
+    /// Returns a base column of indentation of quoted data.
+    pub const fn quote_indent(&self) -> QuoteIndent {
+        self.quote_indent
+    }
+
+    /// Returns a quote style.
+    pub const fn quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+
+    /// Returns `true` if lists are reflowed by their widths.
+    pub const fn reflow(&self) -> bool {
+        self.reflow
+    }
+
+    /// Returns `true` if formatted code is verified to have the same expressions.
+    pub const fn safe(&self) -> bool {
+        self.safe
+    }
+
+    /// Returns `true` if brackets and braces are rejected as in R7RS.
+    pub const fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns a pair of left and right delimiters of strings in addition to
+    /// double quotes.
+    pub const fn string_delimiters(&self) -> Option<&(String, String)> {
+        self.string_delimiters.as_ref()
+    }
+
+    /// Returns a style preset of special forms.
+    pub const fn style(&self) -> Option<Style> {
+        self.style
+    }
+
+    /// Returns a tab width in columns.
+    pub const fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Returns `true` if trailing whitespace is removed from lines in block comments.
+    pub const fn trim_block_comments(&self) -> bool {
+        self.trim_block_comments
+    }
+
+    /// Returns `true` if bodies of block comments are kept verbatim.
+    pub const fn verbatim_block_comments(&self) -> bool {
+        self.verbatim_block_comments
+    }
+
+    /// Sets whether spaces are inserted inside braces.
+    pub fn set_brace_space(self, brace_space: bool) -> Self {
+        Self {
+            brace_space,
+            ..self
+        }
+    }
+
+    /// Sets whether exactly one space is inserted after semicolons of line
+    /// comments.
+    pub fn set_comment_space(self, comment_space: bool) -> Self {
+        Self {
+            comment_space,
+            ..self
+        }
+    }
+
+    /// Sets a number of blank lines between consecutive top-level definitions.
+    ///
+    /// It overrides a maximum number of blank lines between them.
+    pub fn set_definition_blank_lines(self, definition_blank_lines: Option<usize>) -> Self {
+        Self {
+            definition_blank_lines,
+            ..self
+        }
+    }
+
+    /// Sets head symbols of top-level definitions.
+    pub fn set_definition_symbols(self, definition_symbols: Vec<String>) -> Self {
+        Self {
+            definition_symbols,
+            ..self
+        }
+    }
+
+    /// Sets a dialect.
+    pub fn set_dialect(self, dialect: Dialect) -> Self {
+        Self { dialect, ..self }
+    }
+
+    /// Sets whether a blank line is inserted before section header comments.
+    pub fn set_header_blank_line(self, header_blank_line: bool) -> Self {
+        Self {
+            header_blank_line,
+            ..self
+        }
+    }
+
+    /// Sets a letter case of hexadecimal digits in character literals.
+    pub fn set_hex_case(self, hex_case: HexCase) -> Self {
+        Self { hex_case, ..self }
+    }
+
+    /// Sets whether lines in block comments are indented to their columns.
+    pub fn set_indent_block_comments(self, indent_block_comments: bool) -> Self {
+        Self {
+            indent_block_comments,
+            ..self
+        }
+    }
+
+    /// Sets whether special forms keep their distinguished arguments on the
+    /// same lines as their heads.
+    pub fn set_keep_arguments(self, keep_arguments: bool) -> Self {
+        Self {
+            keep_arguments,
+            ..self
+        }
+    }
+
+    /// Sets a maximum number of consecutive blank lines.
+    pub fn set_max_blank_lines(self, max_blank_lines: usize) -> Self {
+        Self {
+            max_blank_lines,
+            ..self
+        }
+    }
+
+    /// Sets a maximum nesting depth of lists and quotes in a source.
+    ///
+    /// Sources nested deeper fail to be parsed.
+    pub fn set_max_depth(self, max_depth: Option<usize>) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Sets a maximum number of inline block comments on a single line.
+    pub fn set_max_inline_comments(self, max_inline_comments: Option<usize>) -> Self {
+        Self {
+            max_inline_comments,
+            ..self
+        }
+    }
+
+    /// Sets a maximum number of elements in a list on a single line.
+    pub fn set_max_inline_elements(self, max_inline_elements: Option<usize>) -> Self {
+        Self {
+            max_inline_elements,
+            ..self
+        }
+    }
+
+    /// Sets a maximum width of lines in columns.
+    pub fn set_max_width(self, max_width: usize) -> Self {
+        Self { max_width, ..self }
+    }
+
+    /// Sets whether unnecessary escapes in quoted symbols are replaced with
+    /// literal characters.
+    pub fn set_minimal_symbol_escapes(self, minimal_symbol_escapes: bool) -> Self {
+        Self {
+            minimal_symbol_escapes,
+            ..self
+        }
+    }
+
+    /// Sets whether line breaks between elements of lists are kept as they
+    /// are.
+    ///
+    /// It overrides reflow and other options changing line breaks.
+    pub fn set_preserve_breaks(self, preserve_breaks: bool) -> Self {
+        Self {
+            preserve_breaks,
+            ..self
+        }
+    }
+
+    /// Sets a base column of indentation of quoted data.
+    pub fn set_quote_indent(self, quote_indent: QuoteIndent) -> Self {
+        Self {
+            quote_indent,
+            ..self
+        }
+    }
+
+    /// Sets a quote style.
+    pub fn set_quote_style(self, quote_style: QuoteStyle) -> Self {
+        Self {
+            quote_style,
+            ..self
+        }
+    }
+
+    /// Sets whether lists are reflowed by their widths.
+    pub fn set_reflow(self, reflow: bool) -> Self {
+        Self { reflow, ..self }
+    }
+
+    /// Sets whether formatted code is verified to have the same expressions.
+    ///
+    /// Options changing expressions, such as quote styles, fail the verification.
+    pub fn set_safe(self, safe: bool) -> Self {
+        Self { safe, ..self }
+    }
+
+    /// Sets whether brackets and braces are rejected as in R7RS.
+    pub fn set_strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Sets a pair of left and right delimiters of strings in addition to double
+    /// quotes.
+    ///
+    /// Contents of such strings are kept verbatim without escapes.
+    pub fn set_string_delimiters(self, string_delimiters: Option<(String, String)>) -> Self {
+        Self {
+            string_delimiters,
+            ..self
+        }
+    }
+
+    /// Sets a style preset of special forms.
+    pub fn set_style(self, style: Option<Style>) -> Self {
+        Self { style, ..self }
+    }
+
+    /// Sets a tab width in columns.
+    pub fn set_tab_width(self, tab_width: usize) -> Self {
+        Self { tab_width, ..self }
+    }
+
+    /// Sets whether trailing whitespace is removed from lines in block comments.
+    pub fn set_trim_block_comments(self, trim_block_comments: bool) -> Self {
+        Self {
+            trim_block_comments,
+            ..self
+        }
+    }
+
+    /// Sets whether bodies of block comments are kept verbatim.
+    pub fn set_verbatim_block_comments(self, verbatim_block_comments: bool) -> Self {
+        Self {
+            verbatim_block_comments,
+            ..self
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
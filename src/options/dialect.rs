@@ -0,0 +1,21 @@
+/// A Lisp dialect.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Dialect {
+    /// Scheme.
+    #[default]
+    Scheme,
+    /// Emacs Lisp.
+    Elisp,
+}
+
+impl Dialect {
+    /// Returns `true` if braces are list delimiters.
+    pub const fn brace_delimiter(self) -> bool {
+        matches!(self, Self::Scheme)
+    }
+
+    /// Returns `true` if `#|` starts a block comment.
+    pub const fn block_comment(self) -> bool {
+        matches!(self, Self::Scheme)
+    }
+}
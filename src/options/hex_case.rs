@@ -0,0 +1,11 @@
+/// A letter case of hexadecimal digits in character literals.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum HexCase {
+    /// Preserves digits as they are.
+    #[default]
+    Preserve,
+    /// Lowercases digits, such as `#\xAB` into `#\xab`.
+    Lower,
+    /// Uppercases digits, such as `#\xab` into `#\xAB`.
+    Upper,
+}
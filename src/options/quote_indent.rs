@@ -0,0 +1,16 @@
+/// A base column of indentation of quoted data broken into multiple lines.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum QuoteIndent {
+    /// Indents data from their left delimiters after quote signs, such as
+    /// `,@(foo` followed by `   bar)`.
+    ///
+    /// Data are indented as they are without quote signs while multi-character
+    /// signs shift them further.
+    #[default]
+    Datum,
+    /// Indents data from quote signs, such as `,@(foo` followed by `  bar)`.
+    ///
+    /// Data are indented by the same width regardless of quote signs while
+    /// their elements are not aligned with their left delimiters.
+    Prefix,
+}
@@ -0,0 +1,12 @@
+/// A quote style.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum QuoteStyle {
+    /// Preserves quotes as they are.
+    #[default]
+    Preserve,
+    /// Abbreviates quotes, such as `(quote x)` into `'x`.
+    #[value(name = "abbrev")]
+    Abbreviation,
+    /// Expands quotes, such as `'x` into `(quote x)`.
+    Full,
+}
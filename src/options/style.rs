@@ -0,0 +1,129 @@
+const R7RS_FORMS: &[(&str, usize)] = &[
+    ("begin", 0),
+    ("case", 1),
+    ("case-lambda", 0),
+    ("cond", 0),
+    ("cond-expand", 0),
+    ("define", 1),
+    ("define-library", 1),
+    ("define-record-type", 1),
+    ("define-syntax", 1),
+    ("define-values", 1),
+    ("delay", 0),
+    ("delay-force", 0),
+    ("do", 2),
+    ("guard", 1),
+    ("if", 1),
+    ("lambda", 1),
+    ("let", 1),
+    ("let*", 1),
+    ("let*-values", 1),
+    ("let-syntax", 1),
+    ("let-values", 1),
+    ("letrec", 1),
+    ("letrec*", 1),
+    ("letrec-syntax", 1),
+    ("parameterize", 1),
+    ("syntax-rules", 1),
+    ("unless", 1),
+    ("when", 1),
+];
+const GUILE_FORMS: &[(&str, usize)] = &[
+    ("define*", 1),
+    ("define-module", 1),
+    ("define-public", 1),
+    ("define-syntax-rule", 1),
+    ("eval-when", 1),
+    ("lambda*", 1),
+    ("let-optional", 2),
+    ("match", 1),
+    ("match-lambda", 0),
+    ("syntax-case", 2),
+    ("with-fluids", 1),
+    ("with-syntax", 1),
+];
+const RACKET_FORMS: &[(&str, usize)] = &[
+    ("define-struct", 1),
+    ("define-syntax-rule", 1),
+    ("for", 1),
+    ("for*", 1),
+    ("for/fold", 2),
+    ("for/list", 1),
+    ("match", 1),
+    ("match-define", 1),
+    ("module", 2),
+    ("module+", 1),
+    ("struct", 1),
+    ("syntax-parse", 1),
+    ("with-handlers", 1),
+];
+
+/// A style preset of special forms.
+///
+/// Lists headed by special forms indent their bodies while the other lists
+/// align their arguments to their first arguments.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum Style {
+    /// Special forms in R7RS, such as `define`, `lambda`, and `let`.
+    R7rs,
+    /// Special forms in R7RS and Guile, such as `define-public` and `lambda*`.
+    Guile,
+    /// Special forms in R7RS and Racket, such as `struct` and `for/list`.
+    Racket,
+}
+
+impl Style {
+    /// Returns `true` if a symbol is a special form.
+    pub fn is_special_form(self, symbol: &str) -> bool {
+        self.argument_count(symbol).is_some()
+    }
+
+    /// Returns a number of distinguished arguments of a special form.
+    ///
+    /// They are kept on the same line as a head symbol while the rest are
+    /// bodies.
+    pub fn argument_count(self, symbol: &str) -> Option<usize> {
+        R7RS_FORMS
+            .iter()
+            .chain(match self {
+                Self::R7rs => &[],
+                Self::Guile => GUILE_FORMS,
+                Self::Racket => RACKET_FORMS,
+            })
+            .find(|(name, _)| *name == symbol)
+            .map(|(_, count)| *count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_special_forms() {
+        assert!(Style::R7rs.is_special_form("define"));
+        assert!(Style::Guile.is_special_form("define"));
+        assert!(Style::Racket.is_special_form("define"));
+        assert!(!Style::R7rs.is_special_form("foo"));
+    }
+
+    #[test]
+    fn check_preset_special_forms() {
+        assert!(!Style::R7rs.is_special_form("define-public"));
+        assert!(Style::Guile.is_special_form("define-public"));
+        assert!(!Style::Racket.is_special_form("define-public"));
+        assert!(!Style::R7rs.is_special_form("for/list"));
+        assert!(Style::Racket.is_special_form("for/list"));
+    }
+
+    #[test]
+    fn get_argument_counts() {
+        assert_eq!(Style::R7rs.argument_count("begin"), Some(0));
+        assert_eq!(Style::R7rs.argument_count("if"), Some(1));
+        assert_eq!(Style::R7rs.argument_count("do"), Some(2));
+        assert_eq!(Style::R7rs.argument_count("foo"), None);
+        assert_eq!(Style::R7rs.argument_count("module"), None);
+        assert_eq!(Style::Racket.argument_count("module"), Some(2));
+        assert_eq!(Style::Guile.argument_count("syntax-case"), Some(2));
+    }
+}
@@ -0,0 +1,13 @@
+/// An output format of check results.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Plain text.
+    #[default]
+    Text,
+    /// SARIF JSON for code scanning tools.
+    Sarif,
+    /// Workflow commands of GitHub Actions.
+    Github,
+    /// Lines of `PATH:LINE: MESSAGE` for each region of changed lines.
+    Annotations,
+}
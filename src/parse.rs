@@ -4,31 +4,67 @@ mod parser;
 
 pub use self::error::ParseError;
 use self::{
-    input::Input,
+    input::{Extra, Input},
     parser::{comments, hash_directives, module, IResult},
 };
-use crate::ast::{Comment, Expression, HashDirective};
+use crate::{
+    ast::{Comment, Expression, HashDirective},
+    options::{Dialect, Options},
+};
 use std::alloc::Allocator;
 
 pub fn parse<A: Allocator + Clone>(
     source: &str,
+    dialect: Dialect,
     allocator: A,
-) -> Result<Vec<Expression<A>, A>, ParseError> {
-    convert_result(module(Input::new_extra(source, allocator)), source)
+) -> Result<Vec<Expression<'_, A>, A>, ParseError> {
+    convert_result(
+        module(Input::new_extra(source, Extra::new(allocator, dialect))),
+        source,
+    )
 }
 
-pub fn parse_comments<A: Allocator + Clone>(
-    source: &str,
+pub fn parse_with_options<'a, A: Allocator + Clone>(
+    source: &'a str,
+    options: &'a Options,
+    allocator: A,
+) -> Result<Vec<Expression<'a, A>, A>, ParseError> {
+    convert_result(
+        module(Input::new_extra(
+            source,
+            Extra::new(allocator, options.dialect())
+                .set_strict(options.strict())
+                .set_max_depth(options.max_depth())
+                .set_string_delimiters(options.string_delimiters()),
+        )),
+        source,
+    )
+}
+
+pub fn parse_comments<'a, A: Allocator + Clone>(
+    source: &'a str,
+    options: &'a Options,
     allocator: A,
-) -> Result<Vec<Comment, A>, ParseError> {
-    convert_result(comments(Input::new_extra(source, allocator)), source)
+) -> Result<Vec<Comment<'a>, A>, ParseError> {
+    convert_result(
+        comments(Input::new_extra(
+            source,
+            Extra::new(allocator, options.dialect())
+                .set_string_delimiters(options.string_delimiters()),
+        )),
+        source,
+    )
 }
 
 pub fn parse_hash_directives<A: Allocator + Clone>(
     source: &str,
+    dialect: Dialect,
     allocator: A,
-) -> Result<Vec<HashDirective, A>, ParseError> {
-    convert_result(hash_directives(Input::new_extra(source, allocator)), source)
+) -> Result<Vec<HashDirective<'_>, A>, ParseError> {
+    convert_result(
+        hash_directives(Input::new_extra(source, Extra::new(allocator, dialect))),
+        source,
+    )
 }
 
 fn convert_result<T, A: Allocator + Clone>(
@@ -43,19 +79,25 @@ fn convert_result<T, A: Allocator + Clone>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::position::Position;
+    use crate::{ast::OwnedExpression, position::Position, position_map::PositionMap};
     use pretty_assertions::assert_eq;
     use std::alloc::Global;
 
+    // Options are dropped before parsed expressions are compared.
+    fn parse_owned(source: &str, options: &Options) -> Result<Vec<OwnedExpression>, ParseError> {
+        parse_with_options(source, options, Global)
+            .map(|expressions| expressions.iter().map(From::from).collect())
+    }
+
     #[test]
     fn parse_nothing() {
-        assert_eq!(parse("", Global), Ok(vec![]));
+        assert_eq!(parse("", Dialect::Scheme, Global), Ok(vec![]));
     }
 
     #[test]
     fn parse_symbol() {
         assert_eq!(
-            parse("foo", Global),
+            parse("foo", Dialect::Scheme, Global),
             Ok(vec![Expression::Symbol("foo", Position::new(0, 3))])
         );
     }
@@ -63,7 +105,7 @@ mod tests {
     #[test]
     fn parse_shebang() {
         assert_eq!(
-            parse("#!/bin/sh\n#t", Global),
+            parse("#!/bin/sh\n#t", Dialect::Scheme, Global),
             Ok(vec![Expression::Quote(
                 "#",
                 Expression::Symbol("t", Position::new(11, 12)).into(),
@@ -75,7 +117,7 @@ mod tests {
     #[test]
     fn parse_lang_directive() {
         assert_eq!(
-            parse("#lang racket\n#t", Global),
+            parse("#lang racket\n#t", Dialect::Scheme, Global),
             Ok(vec![Expression::Quote(
                 "#",
                 Expression::Symbol("t", Position::new(14, 15)).into(),
@@ -84,10 +126,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_hash_semicolon_quote_with_comment() {
+        assert_eq!(
+            parse("#;(a ;note\n b)", Dialect::Scheme, Global),
+            Ok(vec![Expression::Quote(
+                "#;",
+                Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("a", Position::new(3, 4)),
+                        Expression::Symbol("b", Position::new(12, 13))
+                    ],
+                    Position::new(2, 14)
+                )
+                .into(),
+                Position::new(0, 14)
+            )])
+        );
+    }
+
     #[test]
     fn parse_empty_list() {
         assert_eq!(
-            parse("()", Global),
+            parse("()", Dialect::Scheme, Global),
             Ok(vec![Expression::List(
                 "(",
                 ")",
@@ -100,7 +163,7 @@ mod tests {
     #[test]
     fn parse_list_with_element() {
         assert_eq!(
-            parse("(foo)", Global),
+            parse("(foo)", Dialect::Scheme, Global),
             Ok(vec![Expression::List(
                 "(",
                 ")",
@@ -113,7 +176,7 @@ mod tests {
     #[test]
     fn parse_list_with_elements() {
         assert_eq!(
-            parse("(foo bar)", Global),
+            parse("(foo bar)", Dialect::Scheme, Global),
             Ok(vec![Expression::List(
                 "(",
                 ")",
@@ -129,7 +192,7 @@ mod tests {
     #[test]
     fn parse_vector() {
         assert_eq!(
-            parse("#()", Global),
+            parse("#()", Dialect::Scheme, Global),
             Ok(vec![Expression::Quote(
                 "#",
                 Expression::List("(", ")", vec![], Position::new(1, 3)).into(),
@@ -141,7 +204,7 @@ mod tests {
     #[test]
     fn parse_symbol_starting_with_escaped_hash() {
         assert_eq!(
-            parse("\\#foo", Global),
+            parse("\\#foo", Dialect::Scheme, Global),
             Ok(vec![Expression::Symbol("\\#foo", Position::new(0, 5)),])
         );
     }
@@ -149,7 +212,7 @@ mod tests {
     #[test]
     fn parse_symbol_quoted_by_hash_and_single_quote() {
         assert_eq!(
-            parse("#'foo", Global),
+            parse("#'foo", Dialect::Scheme, Global),
             Ok(vec![Expression::Quote(
                 "#",
                 Expression::Quote(
@@ -163,10 +226,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_unterminated_block_comment() {
+        let source = "#| foo";
+
+        assert_eq!(
+            parse(source, Dialect::Scheme, Global)
+                .unwrap_err()
+                .to_string("foo.scm", source, &PositionMap::new(source), 8),
+            "unterminated block comment foo.scm:1:1: #| foo"
+        );
+    }
+
+    #[test]
+    fn parse_unterminated_block_comment_after_expression() {
+        let source = "(foo)\n  #| bar\nbaz";
+
+        assert_eq!(
+            parse(source, Dialect::Scheme, Global)
+                .unwrap_err()
+                .to_string("foo.scm", source, &PositionMap::new(source), 8),
+            "unterminated block comment foo.scm:2:3:   #| bar"
+        );
+        assert_eq!(
+            parse_comments(source, &Options::new(), Global)
+                .unwrap_err()
+                .to_string("foo.scm", source, &PositionMap::new(source), 8),
+            "unterminated block comment foo.scm:2:3:   #| bar"
+        );
+    }
+
     #[test]
     fn parse_last_boolean_in_list() {
         assert_eq!(
-            parse("(#f)", Global),
+            parse("(#f)", Dialect::Scheme, Global),
             Ok(vec![Expression::List(
                 "(",
                 ")",
@@ -179,4 +272,272 @@ mod tests {
             )])
         );
     }
+
+    mod strict {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn parse_strict(source: &str) -> Result<Vec<OwnedExpression>, ParseError> {
+            parse_owned(source, &Options::new().set_strict(true))
+        }
+
+        fn parse_error(source: &str) -> (&'static str, usize) {
+            let error = parse_strict(source).unwrap_err();
+
+            (error.message(), error.offset())
+        }
+
+        #[test]
+        fn parse_list() {
+            assert_eq!(parse_strict("(a)"), parse_owned("(a)", &Options::new()));
+        }
+
+        #[test]
+        fn fail_to_parse_brackets() {
+            assert_eq!(
+                parse_error("[a]"),
+                ("brackets not allowed in strict mode", 0)
+            );
+            assert_eq!(
+                parse_error("(foo ;bar\n  [a])"),
+                ("brackets not allowed in strict mode", 12)
+            );
+            assert_eq!(
+                parse_error("#[a]"),
+                ("brackets not allowed in strict mode", 1)
+            );
+        }
+
+        #[test]
+        fn fail_to_parse_unclosed_brackets() {
+            assert_eq!(
+                parse_error("[a"),
+                ("brackets not allowed in strict mode", 0)
+            );
+        }
+
+        #[test]
+        fn fail_to_parse_braces() {
+            assert_eq!(
+                parse_error("(foo {a})"),
+                ("braces not allowed in strict mode", 5)
+            );
+        }
+
+        #[test]
+        fn parse_brackets_without_strict_mode() {
+            assert!(parse("[a]", Dialect::Scheme, Global).is_ok());
+            assert!(parse("{a}", Dialect::Scheme, Global).is_ok());
+        }
+    }
+
+    mod max_depth {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn parse_limited(
+            source: &str,
+            max_depth: usize,
+        ) -> Result<Vec<OwnedExpression>, ParseError> {
+            parse_owned(source, &Options::new().set_max_depth(Some(max_depth)))
+        }
+
+        fn parse_error(source: &str, max_depth: usize) -> (&'static str, usize) {
+            let error = parse_limited(source, max_depth).unwrap_err();
+
+            (error.message(), error.offset())
+        }
+
+        #[test]
+        fn parse_lists_within_max_depth() {
+            for source in ["a", "(a)", "((a) b)", "(a (b) (c))", "(a)\n(b)", "#(a)"] {
+                assert_eq!(
+                    parse_limited(source, 2),
+                    parse_owned(source, &Options::new())
+                );
+            }
+        }
+
+        #[test]
+        fn parse_quotes_within_max_depth() {
+            for source in ["'a", "'(a)", "(a 'b)", "#u8(1 2)", "`(a b)"] {
+                assert_eq!(
+                    parse_limited(source, 2),
+                    parse_owned(source, &Options::new())
+                );
+            }
+        }
+
+        #[test]
+        fn fail_to_parse_lists_beyond_max_depth() {
+            assert_eq!(
+                parse_error("(a (b (c)))", 2),
+                ("maximum nesting depth exceeded", 6)
+            );
+            assert_eq!(
+                parse_error("(a [b {c}])", 2),
+                ("maximum nesting depth exceeded", 6)
+            );
+            assert_eq!(parse_error("(a)", 0), ("maximum nesting depth exceeded", 0));
+        }
+
+        #[test]
+        fn fail_to_parse_quotes_beyond_max_depth() {
+            assert_eq!(
+                parse_error("('''a)", 2),
+                ("maximum nesting depth exceeded", 2)
+            );
+            assert_eq!(
+                parse_error("(a '(b))", 2),
+                ("maximum nesting depth exceeded", 4)
+            );
+        }
+
+        #[test]
+        fn fail_to_parse_deeply_nested_lists() {
+            let source = "(".repeat(100_000) + &")".repeat(100_000);
+
+            assert_eq!(
+                parse_error(&source, 64),
+                ("maximum nesting depth exceeded", 64)
+            );
+        }
+    }
+
+    mod reader_object {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parse_reader_object_in_list() {
+            assert_eq!(
+                parse("(foo #!eof)", Dialect::Scheme, Global),
+                Ok(vec![Expression::List(
+                    "(",
+                    ")",
+                    vec![
+                        Expression::Symbol("foo", Position::new(1, 4)),
+                        Expression::Quote(
+                            "#",
+                            Expression::Symbol("!eof", Position::new(6, 10)).into(),
+                            Position::new(5, 10)
+                        )
+                    ],
+                    Position::new(0, 11)
+                )])
+            );
+        }
+
+        #[test]
+        fn parse_reader_object_on_first_line() {
+            assert_eq!(
+                parse("#!unspecified\n", Dialect::Scheme, Global),
+                Ok(vec![Expression::Quote(
+                    "#",
+                    Expression::Symbol("!unspecified", Position::new(1, 13)).into(),
+                    Position::new(0, 13)
+                )])
+            );
+            assert_eq!(
+                parse_hash_directives("#!unspecified\n", Dialect::Scheme, Global),
+                Ok(vec![])
+            );
+        }
+
+        #[test]
+        fn parse_shebang_on_first_line() {
+            assert_eq!(
+                parse("#!/bin/sh\nfoo\n", Dialect::Scheme, Global),
+                Ok(vec![Expression::Symbol("foo", Position::new(10, 13))])
+            );
+            assert_eq!(
+                parse_hash_directives("#!/bin/sh\nfoo\n", Dialect::Scheme, Global),
+                Ok(vec![HashDirective::new("!/bin/sh", Position::new(0, 9))])
+            );
+        }
+    }
+
+    mod error {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn parse_context(source: &str) -> Option<&'static str> {
+            parse(source, Dialect::Scheme, Global)
+                .unwrap_err()
+                .context()
+        }
+
+        #[test]
+        fn expect_list() {
+            assert_eq!(parse_context("(foo"), Some("list"));
+            assert_eq!(parse_context("((foo)"), Some("list"));
+        }
+
+        #[test]
+        fn expect_vector() {
+            assert_eq!(parse_context("[foo"), Some("vector"));
+        }
+
+        #[test]
+        fn expect_string() {
+            assert_eq!(parse_context("\"foo"), Some("string"));
+        }
+
+        #[test]
+        fn expect_innermost_string() {
+            assert_eq!(parse_context("(foo \"bar"), Some("string"));
+        }
+
+        #[test]
+        fn expect_quoted_symbol() {
+            assert_eq!(parse_context("(foo |bar"), Some("quoted symbol"));
+        }
+
+        #[test]
+        fn expect_nothing() {
+            assert_eq!(parse_context(")"), None);
+        }
+
+        #[test]
+        fn display_context() {
+            let source = "(foo";
+
+            assert_eq!(
+                parse(source, Dialect::Scheme, Global)
+                    .unwrap_err()
+                    .to_string("foo.scm", source, &PositionMap::new(source), 8),
+                "failed to parse (expected list) foo.scm:1:4: (foo"
+            );
+        }
+
+        #[test]
+        fn fail_to_parse_hash_without_expression() {
+            for (source, offset) in [
+                ("#", 0),
+                ("# ", 0),
+                ("#\n", 0),
+                ("(foo #)", 5),
+                ("(foo #", 5),
+                ("foo\n#\n", 4),
+            ] {
+                let error = parse(source, Dialect::Scheme, Global).unwrap_err();
+
+                assert_eq!(error.message(), "missing quoted expression");
+                assert_eq!(error.context(), None);
+                assert_eq!(error.offset(), offset);
+            }
+        }
+
+        #[test]
+        fn display_hash_without_expression() {
+            let source = "(foo #)";
+
+            assert_eq!(
+                parse(source, Dialect::Scheme, Global)
+                    .unwrap_err()
+                    .to_string("foo.scm", source, &PositionMap::new(source), 8),
+                "missing quoted expression foo.scm:1:6: (foo #)"
+            );
+        }
+    }
 }
@@ -1,14 +1,56 @@
 use super::input::Input;
 use crate::position_map::PositionMap;
 use core::str;
-use nom::error::Error;
+use nom::error::{ContextError, ErrorKind};
 use std::alloc::Allocator;
 
-pub type NomError<'a, A> = Error<Input<'a, A>>;
+const DEFAULT_MESSAGE: &str = "failed to parse";
+
+#[derive(Debug)]
+pub struct NomError<'a, A: Allocator> {
+    input: Input<'a, A>,
+    message: Option<&'static str>,
+    context: Option<&'static str>,
+}
+
+impl<'a, A: Allocator> NomError<'a, A> {
+    pub fn new(input: Input<'a, A>, message: &'static str) -> Self {
+        Self {
+            input,
+            message: Some(message),
+            context: None,
+        }
+    }
+}
+
+impl<'a, A: Allocator> nom::error::ParseError<Input<'a, A>> for NomError<'a, A> {
+    fn from_error_kind(input: Input<'a, A>, _: ErrorKind) -> Self {
+        Self {
+            input,
+            message: None,
+            context: None,
+        }
+    }
+
+    fn append(_: Input<'a, A>, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a, A: Allocator> ContextError<Input<'a, A>> for NomError<'a, A> {
+    fn add_context(_: Input<'a, A>, context: &'static str, other: Self) -> Self {
+        // Keep the innermost context.
+        Self {
+            context: other.context.or(Some(context)),
+            ..other
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParseError {
     message: &'static str,
+    context: Option<&'static str>,
     offset: usize,
 }
 
@@ -19,25 +61,54 @@ impl ParseError {
         match error {
             nom::Err::Incomplete(_) => Self {
                 message: "parsing requires more data",
+                context: None,
                 offset: end_offset,
             },
             nom::Err::Error(error) | nom::Err::Failure(error) => Self {
-                message: "failed to parse",
+                message: error.message.unwrap_or(DEFAULT_MESSAGE),
+                context: error.message.is_none().then_some(error.context).flatten(),
                 offset: error.input.location_offset().min(end_offset),
             },
         }
     }
 
-    pub fn to_string(&self, name: &str, source: &str, position_map: &PositionMap) -> String {
-        let bytes = &source.as_bytes()[position_map.line_range(self.offset).expect("valid offset")];
+    pub const fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// Returns a syntax context expected at an error location.
+    pub const fn context(&self) -> Option<&'static str> {
+        self.context
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn to_string(
+        &self,
+        name: &str,
+        source: &str,
+        position_map: &PositionMap,
+        tab_width: usize,
+    ) -> String {
+        let bytes = &source.as_bytes()[position_map
+            .line_range(self.offset())
+            .expect("valid offset")];
 
         format!(
-            "{} {}:{}:{}: {}",
-            &self.message,
+            "{}{} {}:{}:{}: {}",
+            self.message(),
+            self.context()
+                .map(|context| format!(" (expected {})", context))
+                .unwrap_or_default(),
             name,
-            &position_map.line_index(self.offset).expect("valid offset") + 1,
             &position_map
-                .column_index(self.offset)
+                .line_index(self.offset())
+                .expect("valid offset")
+                + 1,
+            &position_map
+                .column_index(source, self.offset(), tab_width)
                 .expect("valid offset")
                 + 1,
             String::from_utf8_lossy(bytes).trim_end(),
@@ -48,7 +119,7 @@ impl ParseError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nom::error::ErrorKind;
+    use crate::{options::Dialect, parse::input::Extra};
     use pretty_assertions::assert_eq;
     use std::alloc::Global;
 
@@ -59,15 +130,34 @@ mod tests {
 
         let error = ParseError::new(
             "foo",
-            nom::Err::Error(Error {
-                input: Input::new_extra("foo", Global),
-                code: ErrorKind::Tag,
-            }),
+            nom::Err::Error(nom::error::ParseError::from_error_kind(
+                Input::new_extra("foo", Extra::new(Global, Dialect::Scheme)),
+                ErrorKind::Tag,
+            )),
         );
 
         assert_eq!(
-            error.to_string("foo.scm", source, &position_map),
+            error.to_string("foo.scm", source, &position_map, 8),
             "failed to parse foo.scm:1:1: foo"
         );
     }
+
+    #[test]
+    fn to_string_with_message() {
+        let source = "foo";
+        let position_map = PositionMap::new(source);
+
+        let error = ParseError::new(
+            "foo",
+            nom::Err::Failure(NomError::new(
+                Input::new_extra("foo", Extra::new(Global, Dialect::Scheme)),
+                "bar",
+            )),
+        );
+
+        assert_eq!(
+            error.to_string("foo.scm", source, &position_map, 8),
+            "bar foo.scm:1:1: foo"
+        );
+    }
 }
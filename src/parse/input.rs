@@ -1,3 +1,74 @@
+use crate::options::Dialect;
 use nom_locate::LocatedSpan;
 
-pub type Input<'a, A> = LocatedSpan<&'a str, A>;
+pub type Input<'a, A> = LocatedSpan<&'a str, Extra<'a, A>>;
+
+#[derive(Clone, Debug)]
+pub struct Extra<'a, A> {
+    allocator: A,
+    dialect: Dialect,
+    strict: bool,
+    depth: usize,
+    max_depth: Option<usize>,
+    string_delimiters: Option<&'a (String, String)>,
+}
+
+impl<'a, A: Clone> Extra<'a, A> {
+    pub const fn new(allocator: A, dialect: Dialect) -> Self {
+        Self {
+            allocator,
+            dialect,
+            strict: false,
+            depth: 0,
+            max_depth: None,
+            string_delimiters: None,
+        }
+    }
+
+    pub fn set_strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    pub fn set_depth(self, depth: usize) -> Self {
+        Self { depth, ..self }
+    }
+
+    pub fn set_max_depth(self, max_depth: Option<usize>) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    pub fn set_string_delimiters(self, string_delimiters: Option<&'a (String, String)>) -> Self {
+        Self {
+            string_delimiters,
+            ..self
+        }
+    }
+
+    pub fn allocator(&self) -> A {
+        self.allocator.clone()
+    }
+
+    pub const fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Returns `true` if only syntax in R7RS is allowed.
+    pub const fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns a number of lists and quotes enclosing a current position.
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub const fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Returns a pair of left and right delimiters of strings in addition to
+    /// double quotes.
+    pub const fn string_delimiters(&self) -> Option<&'a (String, String)> {
+        self.string_delimiters
+    }
+}
@@ -1,7 +1,9 @@
 use super::{error::NomError, input::Input};
 use crate::{
     ast::{BlockComment, Comment, Expression, HashDirective, LineComment},
+    options::Dialect,
     position::Position,
+    source::BYTE_ORDER_MARK,
 };
 use nom::{
     branch::alt,
@@ -9,7 +11,7 @@ use nom::{
     character::complete::{
         anychar, char, multispace0, multispace1, none_of, one_of, satisfy, space0,
     },
-    combinator::{all_consuming, cut, eof, map, not, peek, recognize, value},
+    combinator::{all_consuming, cut, eof, fail, map, not, opt, peek, recognize, value, verify},
     error::context,
     multi::{fold_many0, many0_count, many1, many1_count},
     sequence::{delimited, preceded, terminated, tuple},
@@ -19,42 +21,52 @@ use std::alloc::Allocator;
 
 const SYMBOL_SIGNS: &str = "+-*/<>=!?$@%_&~^.:";
 const SPECIAL_SIGNS: &str = ";";
+const BRACE_SIGNS: &str = "{}";
 
 pub type IResult<'a, T, A> = nom::IResult<Input<'a, A>, T, NomError<'a, A>>;
 
 pub fn module<A: Allocator + Clone>(input: Input<A>) -> IResult<Vec<Expression<A>, A>, A> {
     all_consuming(delimited(
-        many0_count(hash_directive),
+        tuple((byte_order_mark, many0_count(hash_directive))),
         many0(expression),
         blank,
     ))(input)
 }
 
 pub fn comments<A: Allocator + Clone>(input: Input<A>) -> IResult<Vec<Comment, A>, A> {
-    let allocator = input.extra.clone();
-
-    all_consuming(fold_many0(
-        alt((
-            map(none_of("\"|;#\\"), |_| None),
-            map(raw_string, |_| None),
-            map(raw_quoted_symbol, |_| None),
-            map(raw_symbol, |_| None),
-            map(comment, Some),
-            map(quote, |_| None),
-        )),
-        move || Vec::new_in(allocator.clone()),
-        |mut all, comment| {
-            if let Some(comment) = comment {
-                all.push(comment);
-            }
+    let allocator = input.extra.allocator();
+
+    all_consuming(preceded(
+        // Comments in hash directives are parts of them.
+        tuple((byte_order_mark, many0_count(hash_directive))),
+        fold_many0(
+            alt((
+                map(raw_delimited_string, |_| None),
+                map(none_of("\"|;#\\"), |_| None),
+                map(raw_string, |_| None),
+                map(raw_quoted_symbol, |_| None),
+                map(raw_symbol, |_| None),
+                map(comment, Some),
+                map(quote, |_| None),
+            )),
+            move || Vec::new_in(allocator.clone()),
+            |mut all, comment| {
+                if let Some(comment) = comment {
+                    all.push(comment);
+                }
 
-            all
-        },
+                all
+            },
+        ),
     ))(input)
 }
 
 pub fn hash_directives<A: Allocator + Clone>(input: Input<A>) -> IResult<Vec<HashDirective, A>, A> {
-    many0(hash_directive)(input)
+    preceded(byte_order_mark, many0(hash_directive))(input)
+}
+
+fn byte_order_mark<A: Allocator + Clone>(input: Input<A>) -> IResult<(), A> {
+    value((), opt(tag(BYTE_ORDER_MARK)))(input)
 }
 
 fn symbol<A: Allocator + Clone>(input: Input<A>) -> IResult<Expression<A>, A> {
@@ -82,7 +94,7 @@ fn raw_quoted_symbol<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>,
             recognize(tuple((char('\\'), one_of(SPECIAL_SIGNS)))),
             escaped_character,
         )))),
-        char('|'),
+        cut(char('|')),
     )(input)
 }
 
@@ -113,10 +125,16 @@ fn escaped_character<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>,
 }
 
 fn head_symbol_character<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>, A> {
+    let brace_delimiter = input.extra.dialect().brace_delimiter();
+
     recognize(alt((
         value(
             (),
-            satisfy(|character| character.is_alphanumeric() || SYMBOL_SIGNS.contains(character)),
+            satisfy(move |character| {
+                character.is_alphanumeric()
+                    || SYMBOL_SIGNS.contains(character)
+                    || !brace_delimiter && BRACE_SIGNS.contains(character)
+            }),
         ),
         value((), tuple((char('\\'), anychar))),
     )))(input)
@@ -127,7 +145,7 @@ fn tail_symbol_character<A: Allocator + Clone>(input: Input<A>) -> IResult<Input
 }
 
 fn expression<A: Allocator + Clone>(input: Input<A>) -> IResult<Expression<A>, A> {
-    let allocator = input.extra.clone();
+    let allocator = input.extra.allocator();
 
     alt((
         context("list", list_like("(", ")")),
@@ -135,7 +153,15 @@ fn expression<A: Allocator + Clone>(input: Input<A>) -> IResult<Expression<A>, A
         context(
             "quote",
             map(
-                token(positioned(tuple((quote, expression)))),
+                token(positioned(alt((
+                    preceded(
+                        peek(quote_sign),
+                        fail_with("missing quoted expression", nested(quote_sign, expression)),
+                    ),
+                    // Nested expressions of a symbol prefix always start with other
+                    // nested expressions.
+                    tuple((quote, expression)),
+                )))),
                 move |((sign, expression), position)| {
                     Expression::Quote(&sign, Box::new_in(expression, allocator.clone()), position)
                 },
@@ -143,33 +169,49 @@ fn expression<A: Allocator + Clone>(input: Input<A>) -> IResult<Expression<A>, A
         ),
         context("quoted symbol", quoted_symbol),
         context("symbol", symbol),
-        context("vector", list_like("[", "]")),
-        context("map", list_like("{", "}")),
+        context(
+            "vector",
+            forbid_strictly(
+                "[",
+                "brackets not allowed in strict mode",
+                list_like("[", "]"),
+            ),
+        ),
+        context(
+            "map",
+            when(
+                Dialect::brace_delimiter,
+                forbid_strictly(
+                    "{",
+                    "braces not allowed in strict mode",
+                    list_like("{", "}"),
+                ),
+            ),
+        ),
     ))(input)
 }
 
 fn quote<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>, A> {
     alt((
-        tag("'"),
-        tag("`"),
-        tag(","),
-        tag("@"),
-        tag("#;"),
-        tag("#"),
+        quote_sign,
         terminated(raw_symbol, peek(not(alt((multispace1, eof))))),
     ))(input)
 }
 
+fn quote_sign<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>, A> {
+    alt((tag("'"), tag("`"), tag(",@"), tag(","), tag("#;"), tag("#")))(input)
+}
+
 fn list_like<A: Allocator + Clone>(
     left: &'static str,
     right: &'static str,
 ) -> impl FnMut(Input<A>) -> IResult<Expression<A>, A> {
     move |input| {
         map(
-            token(positioned(tuple((
+            token(positioned(nested(
                 sign(left),
                 cut(tuple((many0(expression), sign(right)))),
-            )))),
+            ))),
             |((left, (expressions, right)), position)| {
                 Expression::List(&left, &right, expressions, position)
             },
@@ -178,9 +220,17 @@ fn list_like<A: Allocator + Clone>(
 }
 
 fn string<A: Allocator + Clone>(input: Input<A>) -> IResult<Expression<A>, A> {
-    map(token(positioned(raw_string)), |(input, position)| {
-        Expression::String(*input, position)
-    })(input)
+    alt((
+        map(token(positioned(raw_string)), |(input, position)| {
+            Expression::String(*input, position)
+        }),
+        map(
+            token(positioned(raw_delimited_string)),
+            |((left, string, right), position)| {
+                Expression::DelimitedString(left, right, string, position)
+            },
+        ),
+    ))(input)
 }
 
 fn raw_string<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>, A> {
@@ -191,7 +241,25 @@ fn raw_string<A: Allocator + Clone>(input: Input<A>) -> IResult<Input<A>, A> {
             tag("\\\""),
             escaped_character,
         )))),
-        char('"'),
+        cut(char('"')),
+    )(input)
+}
+
+// Strings with configured delimiters keep their contents verbatim without
+// escapes.
+fn raw_delimited_string<'a, A: Allocator + Clone>(
+    input: Input<'a, A>,
+) -> IResult<'a, (&'a str, &'a str, &'a str), A> {
+    let Some((left, right)) = input.extra.string_delimiters() else {
+        return fail(input);
+    };
+
+    map(
+        tuple((
+            tag(left.as_str()),
+            cut(tuple((take_until(right.as_str()), tag(right.as_str())))),
+        )),
+        |(left, (string, right)): (Input<A>, (Input<A>, Input<A>))| (*left, *string, *right),
     )(input)
 }
 
@@ -275,10 +343,16 @@ fn line_comment<A: Allocator + Clone>(input: Input<A>) -> IResult<LineComment, A
 
 fn block_comment<A: Allocator + Clone>(input: Input<A>) -> IResult<BlockComment, A> {
     map(
-        positioned_meta(delimited(
-            tag("#|"),
-            recognize(many0(tuple((not(tag("|#")), anychar)))),
-            tag("|#"),
+        positioned_meta(preceded(
+            when(Dialect::block_comment, peek(tag("#|"))),
+            fail_with(
+                "unterminated block comment",
+                delimited(
+                    tag("#|"),
+                    recognize(many0(tuple((not(tag("|#")), anychar)))),
+                    tag("|#"),
+                ),
+            ),
         )),
         |(input, position)| BlockComment::new(&input, position),
     )(input)
@@ -288,8 +362,15 @@ fn hash_directive<A: Allocator + Clone>(input: Input<A>) -> IResult<HashDirectiv
     map(
         terminated(
             positioned_meta(preceded(
-                tuple((char('#'), not(peek(char('|'))))),
-                take_until("\n"),
+                tuple((
+                    char('#'),
+                    not(peek(one_of("|;"))),
+                    // Distinguish reader objects like `#!eof` from shebangs.
+                    not(peek(tuple((char('!'), none_of("/ "))))),
+                )),
+                verify(take_until("\n"), |content: &Input<A>| {
+                    !content.trim().is_empty()
+                }),
             )),
             newline,
         ),
@@ -304,11 +385,80 @@ fn newline<A: Allocator + Clone>(input: Input<A>) -> IResult<(), A> {
     )(input)
 }
 
+fn when<'a, T, A: Allocator + Clone>(
+    predicate: fn(Dialect) -> bool,
+    mut parser: impl Parser<Input<'a, A>, T, NomError<'a, A>>,
+) -> impl FnMut(Input<'a, A>) -> IResult<'a, T, A> {
+    move |input| {
+        if predicate(input.extra.dialect()) {
+            parser.parse(input)
+        } else {
+            fail(input)
+        }
+    }
+}
+
+// Fails at a left delimiter of a parser in a strict mode.
+fn forbid_strictly<'a, T, A: Allocator + Clone>(
+    left: &'static str,
+    message: &'static str,
+    mut parser: impl Parser<Input<'a, A>, T, NomError<'a, A>>,
+) -> impl FnMut(Input<'a, A>) -> IResult<'a, T, A> {
+    move |input: Input<'a, A>| {
+        if input.extra.strict() {
+            let (input, _) = blank(input)?;
+            peek(tag(left))(input.clone())?;
+
+            Err(nom::Err::Failure(NomError::new(input, message)))
+        } else {
+            parser.parse(input)
+        }
+    }
+}
+
+// Parses an expression nested in another one within a maximum depth.
+fn nested<'a, T, U, A: Allocator + Clone>(
+    mut left: impl Parser<Input<'a, A>, T, NomError<'a, A>>,
+    mut parser: impl Parser<Input<'a, A>, U, NomError<'a, A>>,
+) -> impl FnMut(Input<'a, A>) -> IResult<'a, (T, U), A> {
+    move |input: Input<'a, A>| {
+        let depth = input.extra.depth();
+        let (mut rest, left) = left.parse(input.clone())?;
+
+        if input
+            .extra
+            .max_depth()
+            .is_some_and(|max_depth| depth >= max_depth)
+        {
+            return Err(nom::Err::Failure(NomError::new(
+                input,
+                "maximum nesting depth exceeded",
+            )));
+        }
+
+        rest.extra = rest.extra.set_depth(depth + 1);
+        let (mut rest, value) = parser.parse(rest)?;
+        rest.extra = rest.extra.set_depth(depth);
+
+        Ok((rest, (left, value)))
+    }
+}
+
+fn fail_with<'a, T, A: Allocator + Clone>(
+    message: &'static str,
+    mut parser: impl Parser<Input<'a, A>, T, NomError<'a, A>>,
+) -> impl FnMut(Input<'a, A>) -> IResult<'a, T, A> {
+    move |input| match parser.parse(input.clone()) {
+        Err(nom::Err::Error(_)) => Err(nom::Err::Failure(NomError::new(input, message))),
+        result => result,
+    }
+}
+
 fn many0<'a, T, A: Allocator + Clone>(
     mut parser: impl Parser<Input<'a, A>, T, NomError<'a, A>>,
 ) -> impl FnMut(Input<'a, A>) -> IResult<'a, Vec<T, A>, A> {
     move |input| {
-        let allocator = input.extra.clone();
+        let allocator = input.extra.allocator();
 
         fold_many0(
             |input| parser.parse(input),
@@ -324,43 +474,46 @@ fn many0<'a, T, A: Allocator + Clone>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parse::input::Extra;
     use pretty_assertions::assert_eq;
     use std::alloc::Global;
 
+    fn input(source: &str) -> Input<'_, Global> {
+        Input::new_extra(source, Extra::new(Global, Dialect::Scheme))
+    }
+
     #[test]
     fn parse_symbol() {
         assert_eq!(
-            expression(Input::new_extra("x", Global)).unwrap().1,
+            expression(input("x")).unwrap().1,
             Expression::Symbol("x", Position::new(0, 1))
         );
         assert_eq!(
-            expression(Input::new_extra("foo", Global)).unwrap().1,
+            expression(input("foo")).unwrap().1,
             Expression::Symbol("foo", Position::new(0, 3))
         );
         assert_eq!(
-            expression(Input::new_extra("1", Global)).unwrap().1,
+            expression(input("1")).unwrap().1,
             Expression::Symbol("1", Position::new(0, 1))
         );
         assert_eq!(
-            expression(Input::new_extra("42", Global)).unwrap().1,
+            expression(input("42")).unwrap().1,
             Expression::Symbol("42", Position::new(0, 2))
         );
         assert_eq!(
-            expression(Input::new_extra("3.14", Global)).unwrap().1,
+            expression(input("3.14")).unwrap().1,
             Expression::Symbol("3.14", Position::new(0, 4))
         );
         assert_eq!(
-            expression(Input::new_extra("a#a", Global)).unwrap().1,
+            expression(input("a#a")).unwrap().1,
             Expression::Symbol("a#a", Position::new(0, 3))
         );
         assert_eq!(
-            expression(Input::new_extra("\\#", Global)).unwrap().1,
+            expression(input("\\#")).unwrap().1,
             Expression::Symbol("\\#", Position::new(0, 2))
         );
         assert_eq!(
-            expression(Input::new_extra("あいうえお", Global))
-                .unwrap()
-                .1,
+            expression(input("あいうえお")).unwrap().1,
             Expression::Symbol("あいうえお", Position::new(0, 15))
         );
     }
@@ -368,40 +521,72 @@ mod tests {
     #[test]
     fn parse_quoted_symbol() {
         assert_eq!(
-            expression(Input::new_extra("|a|", Global)).unwrap().1,
+            expression(input("|a|")).unwrap().1,
             Expression::QuotedSymbol("a", Position::new(0, 3))
         );
         assert_eq!(
-            expression(Input::new_extra("|a b|", Global)).unwrap().1,
+            expression(input("|a b|")).unwrap().1,
             Expression::QuotedSymbol("a b", Position::new(0, 5))
         );
         assert_eq!(
-            expression(Input::new_extra("|\\||", Global)).unwrap().1,
+            expression(input("|\\||")).unwrap().1,
             Expression::QuotedSymbol("\\|", Position::new(0, 4))
         );
         assert_eq!(
-            expression(Input::new_extra("|\t\n|", Global)).unwrap().1,
+            expression(input("|\t\n|")).unwrap().1,
             Expression::QuotedSymbol("\t\n", Position::new(0, 4))
         );
         assert_eq!(
-            expression(Input::new_extra("|\\t\\n|", Global)).unwrap().1,
+            expression(input("|\\t\\n|")).unwrap().1,
             Expression::QuotedSymbol("\\t\\n", Position::new(0, 6))
         );
         assert_eq!(
-            expression(Input::new_extra("|\\;|", Global)).unwrap().1,
+            expression(input("|\\;|")).unwrap().1,
             Expression::QuotedSymbol("\\;", Position::new(0, 4))
         );
     }
 
+    #[test]
+    fn parse_symbol_with_escaped_delimiters() {
+        assert_eq!(
+            expression(input("a\\(b")).unwrap().1,
+            Expression::Symbol("a\\(b", Position::new(0, 4))
+        );
+        assert_eq!(
+            expression(input("a\\)b")).unwrap().1,
+            Expression::Symbol("a\\)b", Position::new(0, 4))
+        );
+        assert_eq!(
+            expression(input("a\\;b")).unwrap().1,
+            Expression::Symbol("a\\;b", Position::new(0, 4))
+        );
+    }
+
+    #[test]
+    fn parse_list_with_escaped_delimiters() {
+        assert_eq!(
+            expression(input("(\\( \\))")).unwrap().1,
+            Expression::List(
+                "(",
+                ")",
+                vec![
+                    Expression::Symbol("\\(", Position::new(1, 3)),
+                    Expression::Symbol("\\)", Position::new(4, 6))
+                ],
+                Position::new(0, 7)
+            )
+        );
+    }
+
     #[test]
     fn parse_invalid_symbol() {
-        assert!(expression(Input::new_extra("#", Global)).is_err());
+        assert!(expression(input("#")).is_err());
     }
 
     #[test]
     fn parse_list() {
         assert_eq!(
-            expression(Input::new_extra("(1 2 3)", Global)).unwrap().1,
+            expression(input("(1 2 3)")).unwrap().1,
             Expression::List(
                 "(",
                 ")",
@@ -418,7 +603,7 @@ mod tests {
     #[test]
     fn parse_list_with_correct_position() {
         assert_eq!(
-            expression(Input::new_extra(" ()", Global)).unwrap().1,
+            expression(input(" ()")).unwrap().1,
             Expression::List("(", ")", vec![], Position::new(1, 3))
         );
     }
@@ -426,7 +611,7 @@ mod tests {
     #[test]
     fn parse_character() {
         assert_eq!(
-            expression(Input::new_extra("#\\a", Global)).unwrap().1,
+            expression(input("#\\a")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\a", Position::new(1, 3)).into(),
@@ -434,7 +619,7 @@ mod tests {
             )
         );
         assert_eq!(
-            expression(Input::new_extra("#\\(", Global)).unwrap().1,
+            expression(input("#\\(")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\(", Position::new(1, 3)).into(),
@@ -442,7 +627,7 @@ mod tests {
             )
         );
         assert_eq!(
-            expression(Input::new_extra("#\\;", Global)).unwrap().1,
+            expression(input("#\\;")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\;", Position::new(1, 3)).into(),
@@ -450,7 +635,7 @@ mod tests {
             )
         );
         assert_eq!(
-            expression(Input::new_extra("#\\ ", Global)).unwrap().1,
+            expression(input("#\\ ")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\ ", Position::new(1, 3)).into(),
@@ -458,7 +643,7 @@ mod tests {
             )
         );
         assert_eq!(
-            expression(Input::new_extra("#\\space", Global)).unwrap().1,
+            expression(input("#\\space")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\space", Position::new(1, 7)).into(),
@@ -466,7 +651,7 @@ mod tests {
             )
         );
         assert_eq!(
-            expression(Input::new_extra("#\\\n", Global)).unwrap().1,
+            expression(input("#\\\n")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Symbol("\\\n", Position::new(1, 3)).into(),
@@ -478,7 +663,7 @@ mod tests {
     #[test]
     fn parse_vector() {
         assert_eq!(
-            expression(Input::new_extra("#(1 2 3)", Global)).unwrap().1,
+            expression(input("#(1 2 3)")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::List(
@@ -500,9 +685,7 @@ mod tests {
     #[test]
     fn parse_byte_vector() {
         assert_eq!(
-            expression(Input::new_extra("#u8(1 2 3)", Global))
-                .unwrap()
-                .1,
+            expression(input("#u8(1 2 3)")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::Quote(
@@ -529,7 +712,7 @@ mod tests {
     #[test]
     fn parse_bracket_vector() {
         assert_eq!(
-            expression(Input::new_extra("[1 2 3]", Global)).unwrap().1,
+            expression(input("[1 2 3]")).unwrap().1,
             Expression::List(
                 "[",
                 "]",
@@ -546,7 +729,7 @@ mod tests {
     #[test]
     fn parse_map() {
         assert_eq!(
-            expression(Input::new_extra("#{1 2 3}", Global)).unwrap().1,
+            expression(input("#{1 2 3}")).unwrap().1,
             Expression::Quote(
                 "#",
                 Expression::List(
@@ -572,7 +755,7 @@ mod tests {
         #[test]
         fn parse_false() {
             assert_eq!(
-                expression(Input::new_extra("#f", Global)).unwrap().1,
+                expression(input("#f")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("f", Position::new(1, 2)).into(),
@@ -580,7 +763,7 @@ mod tests {
                 )
             );
             assert_eq!(
-                expression(Input::new_extra("#false", Global)).unwrap().1,
+                expression(input("#false")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("false", Position::new(1, 6)).into(),
@@ -592,7 +775,7 @@ mod tests {
         #[test]
         fn parse_true() {
             assert_eq!(
-                expression(Input::new_extra("#t", Global)).unwrap().1,
+                expression(input("#t")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("t", Position::new(1, 2)).into(),
@@ -600,7 +783,7 @@ mod tests {
                 )
             );
             assert_eq!(
-                expression(Input::new_extra("#true", Global)).unwrap().1,
+                expression(input("#true")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("true", Position::new(1, 5)).into(),
@@ -612,7 +795,7 @@ mod tests {
         #[test]
         fn parse_boolean_followed_by_comment() {
             assert_eq!(
-                expression(Input::new_extra("#f;", Global)).unwrap().1,
+                expression(input("#f;")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("f", Position::new(1, 2)).into(),
@@ -624,7 +807,7 @@ mod tests {
         #[test]
         fn parse_boolean_followed_by_right_parenthesis() {
             assert_eq!(
-                expression(Input::new_extra("#f)", Global)).unwrap().1,
+                expression(input("#f)")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::Symbol("f", Position::new(1, 2)).into(),
@@ -641,7 +824,7 @@ mod tests {
         #[test]
         fn parse_quote() {
             assert_eq!(
-                expression(Input::new_extra("'foo", Global)).unwrap().1,
+                expression(input("'foo")).unwrap().1,
                 Expression::Quote(
                     "'",
                     Expression::Symbol("foo", Position::new(1, 4)).into(),
@@ -653,7 +836,7 @@ mod tests {
         #[test]
         fn parse_quote_with_correct_position() {
             assert_eq!(
-                expression(Input::new_extra(" 'foo", Global)).unwrap().1,
+                expression(input(" 'foo")).unwrap().1,
                 Expression::Quote(
                     "'",
                     Expression::Symbol("foo", Position::new(2, 5)).into(),
@@ -665,7 +848,7 @@ mod tests {
         #[test]
         fn parse_unquote() {
             assert_eq!(
-                expression(Input::new_extra(",foo", Global)).unwrap().1,
+                expression(input(",foo")).unwrap().1,
                 Expression::Quote(
                     ",",
                     Expression::Symbol("foo", Position::new(1, 4)).into(),
@@ -677,7 +860,7 @@ mod tests {
         #[test]
         fn parse_hash_quote() {
             assert_eq!(
-                expression(Input::new_extra("#()", Global)).unwrap().1,
+                expression(input("#()")).unwrap().1,
                 Expression::Quote(
                     "#",
                     Expression::List("(", ")", vec![], Position::new(1, 3)).into(),
@@ -689,7 +872,7 @@ mod tests {
         #[test]
         fn parse_hash_semicolon_quote() {
             assert_eq!(
-                expression(Input::new_extra("#;()", Global)).unwrap().1,
+                expression(input("#;()")).unwrap().1,
                 Expression::Quote(
                     "#;",
                     Expression::List("(", ")", vec![], Position::new(2, 4)).into(),
@@ -701,7 +884,7 @@ mod tests {
         #[test]
         fn parse_quasi_quote() {
             assert_eq!(
-                expression(Input::new_extra("`foo", Global)).unwrap().1,
+                expression(input("`foo")).unwrap().1,
                 Expression::Quote(
                     "`",
                     Expression::Symbol("foo", Position::new(1, 4)).into(),
@@ -713,26 +896,43 @@ mod tests {
         #[test]
         fn parse_splicing_unquote() {
             assert_eq!(
-                expression(Input::new_extra(",@foo", Global)).unwrap().1,
+                expression(input(",@foo")).unwrap().1,
                 Expression::Quote(
-                    ",",
-                    Expression::Quote(
-                        "@",
-                        Expression::Symbol("foo", Position::new(2, 5)).into(),
-                        Position::new(1, 5)
-                    )
-                    .into(),
+                    ",@",
+                    Expression::Symbol("foo", Position::new(2, 5)).into(),
                     Position::new(0, 5)
                 )
             );
         }
 
+        #[test]
+        fn parse_symbol_with_at_sign() {
+            assert_eq!(
+                expression(input("@foo")).unwrap().1,
+                Expression::Symbol("@foo", Position::new(0, 4))
+            );
+            assert_eq!(
+                expression(input("@")).unwrap().1,
+                Expression::Symbol("@", Position::new(0, 1))
+            );
+        }
+
+        #[test]
+        fn parse_unquote_of_symbol_with_at_sign() {
+            assert_eq!(
+                expression(input(", @foo")).unwrap().1,
+                Expression::Quote(
+                    ",",
+                    Expression::Symbol("@foo", Position::new(2, 6)).into(),
+                    Position::new(0, 6)
+                )
+            );
+        }
+
         #[test]
         fn parse_symbol_and_quoted_list() {
             assert_eq!(
-                tuple((expression, expression))(Input::new_extra("#u8 ()", Global))
-                    .unwrap()
-                    .1,
+                tuple((expression, expression))(input("#u8 ()")).unwrap().1,
                 (
                     Expression::Quote(
                         "#",
@@ -747,7 +947,7 @@ mod tests {
         #[test]
         fn parse_quote_with_space() {
             assert_eq!(
-                expression(Input::new_extra("' foo", Global)).unwrap().1,
+                expression(input("' foo")).unwrap().1,
                 Expression::Quote(
                     "'",
                     Expression::Symbol("foo", Position::new(2, 5)).into(),
@@ -764,9 +964,7 @@ mod tests {
         #[test]
         fn parse_shebang() {
             assert_eq!(
-                hash_directive(Input::new_extra("#!/bin/sh\n", Global))
-                    .unwrap()
-                    .1,
+                hash_directive(input("#!/bin/sh\n")).unwrap().1,
                 HashDirective::new("!/bin/sh", Position::new(0, 9))
             );
         }
@@ -774,22 +972,43 @@ mod tests {
         #[test]
         fn parse_lang_directive() {
             assert_eq!(
-                hash_directive(Input::new_extra("#lang r7rs\n", Global))
-                    .unwrap()
-                    .1,
+                hash_directive(input("#lang r7rs\n")).unwrap().1,
                 HashDirective::new("lang r7rs", Position::new(0, 10))
             );
         }
 
         #[test]
-        fn parse_comment() {
+        fn parse_shebang_with_space() {
             assert_eq!(
-                hash_directives(Input::new_extra("#||#\n", Global))
-                    .unwrap()
-                    .1,
+                hash_directive(input("#! /bin/sh\n")).unwrap().1,
+                HashDirective::new("! /bin/sh", Position::new(0, 10))
+            );
+        }
+
+        #[test]
+        fn parse_reader_object() {
+            assert_eq!(hash_directives(input("#!eof\n")).unwrap().1, vec![]);
+            assert_eq!(
+                hash_directives(input("#!default (foo)\n")).unwrap().1,
                 vec![]
             );
         }
+
+        #[test]
+        fn parse_hash_followed_by_whitespace() {
+            assert_eq!(hash_directives(input("#\n")).unwrap().1, vec![]);
+            assert_eq!(hash_directives(input("# \n")).unwrap().1, vec![]);
+        }
+
+        #[test]
+        fn parse_comment() {
+            assert_eq!(hash_directives(input("#||#\n")).unwrap().1, vec![]);
+        }
+
+        #[test]
+        fn parse_hash_semicolon_quote() {
+            assert_eq!(hash_directives(input("#;(a ;b\nc)\n")).unwrap().1, vec![]);
+        }
     }
 
     mod string {
@@ -799,7 +1018,7 @@ mod tests {
         #[test]
         fn parse_empty() {
             assert_eq!(
-                string(Input::new_extra("\"\"", Global)).unwrap().1,
+                string(input("\"\"")).unwrap().1,
                 Expression::String("", Position::new(0, 2))
             );
         }
@@ -807,7 +1026,7 @@ mod tests {
         #[test]
         fn parse_non_empty() {
             assert_eq!(
-                string(Input::new_extra("\"foo\"", Global)).unwrap().1,
+                string(input("\"foo\"")).unwrap().1,
                 Expression::String("foo", Position::new(0, 5))
             );
         }
@@ -815,7 +1034,7 @@ mod tests {
         #[test]
         fn parse_escaped_double_quote() {
             assert_eq!(
-                string(Input::new_extra("\"\\\"\"", Global)).unwrap().1,
+                string(input("\"\\\"\"")).unwrap().1,
                 Expression::String("\\\"", Position::new(0, 4))
             );
         }
@@ -823,7 +1042,7 @@ mod tests {
         #[test]
         fn parse_escaped_single_quote() {
             assert_eq!(
-                string(Input::new_extra("\"\\'\"", Global)).unwrap().1,
+                string(input("\"\\'\"")).unwrap().1,
                 Expression::String("\\'", Position::new(0, 4))
             );
         }
@@ -831,9 +1050,7 @@ mod tests {
         #[test]
         fn parse_escaped_characters() {
             assert_eq!(
-                string(Input::new_extra("\"\\\\\\n\\r\\t\"", Global))
-                    .unwrap()
-                    .1,
+                string(input("\"\\\\\\n\\r\\t\"")).unwrap().1,
                 Expression::String("\\\\\\n\\r\\t", Position::new(0, 10))
             );
         }
@@ -841,11 +1058,11 @@ mod tests {
         #[test]
         fn parse_scheme_hexadecimal_bytes() {
             assert_eq!(
-                string(Input::new_extra("\"\\x0F;\"", Global)).unwrap().1,
+                string(input("\"\\x0F;\"")).unwrap().1,
                 Expression::String("\\x0F;", Position::new(0, 7))
             );
             assert_eq!(
-                string(Input::new_extra("\"\\xABCD;\"", Global)).unwrap().1,
+                string(input("\"\\xABCD;\"")).unwrap().1,
                 Expression::String("\\xABCD;", Position::new(0, 9))
             );
         }
@@ -854,7 +1071,7 @@ mod tests {
         #[test]
         fn parse_wasm_hexadecimal_bytes() {
             assert_eq!(
-                string(Input::new_extra("\"\\00\\FF\"", Global)).unwrap().1,
+                string(input("\"\\00\\FF\"")).unwrap().1,
                 Expression::String("\\00\\FF", Position::new(0, 8))
             );
         }
@@ -862,7 +1079,7 @@ mod tests {
         #[test]
         fn parse_multi_line() {
             assert_eq!(
-                string(Input::new_extra("\"a\\\nb\"", Global)).unwrap().1,
+                string(input("\"a\\\nb\"")).unwrap().1,
                 Expression::String("a\\\nb", Position::new(0, 6))
             );
         }
@@ -870,10 +1087,77 @@ mod tests {
         #[test]
         fn parse_escaped_unicode() {
             assert_eq!(
-                string(Input::new_extra("\"\\ubeef\"", Global)).unwrap().1,
+                string(input("\"\\ubeef\"")).unwrap().1,
                 Expression::String("\\ubeef", Position::new(0, 8))
             );
         }
+
+        fn delimited_input<'a>(
+            source: &'a str,
+            left: &'static str,
+            right: &'static str,
+        ) -> Input<'a, Global> {
+            Input::new_extra(
+                source,
+                Extra::new(Global, Dialect::Scheme)
+                    .set_string_delimiters(Some(Box::leak(Box::new((left.into(), right.into()))))),
+            )
+        }
+
+        #[test]
+        fn parse_delimited_string() {
+            assert_eq!(
+                string(delimited_input("«foo»", "«", "»")).unwrap().1,
+                Expression::DelimitedString("«", "»", "foo", Position::new(0, 7))
+            );
+        }
+
+        #[test]
+        fn parse_delimited_string_verbatim() {
+            assert_eq!(
+                string(delimited_input("«\"f\\o;o\n»", "«", "»")).unwrap().1,
+                Expression::DelimitedString("«", "»", "\"f\\o;o\n", Position::new(0, 11))
+            );
+        }
+
+        #[test]
+        fn parse_delimited_string_with_multi_character_delimiters() {
+            assert_eq!(
+                string(delimited_input("#<foo>#", "#<", ">#")).unwrap().1,
+                Expression::DelimitedString("#<", ">#", "foo", Position::new(0, 7))
+            );
+        }
+
+        #[test]
+        fn parse_double_quoted_string_with_delimiters() {
+            assert_eq!(
+                string(delimited_input("\"«foo»\"", "«", "»")).unwrap().1,
+                Expression::String("«foo»", Position::new(0, 9))
+            );
+        }
+
+        #[test]
+        fn fail_to_parse_unterminated_delimited_string() {
+            assert!(matches!(
+                string(delimited_input("«foo", "«", "»")),
+                Err(nom::Err::Failure(_))
+            ));
+        }
+
+        #[test]
+        fn fail_to_parse_delimited_string_without_delimiters() {
+            assert!(string(input("«foo»")).is_err());
+        }
+
+        #[test]
+        fn skip_delimited_string_in_comments() {
+            assert_eq!(
+                comments(delimited_input("«foo ;bar» ;baz\n", "«", "»"))
+                    .unwrap()
+                    .1,
+                vec![LineComment::new("baz", Position::new(13, 17)).into()]
+            );
+        }
     }
 
     mod comment {
@@ -883,7 +1167,7 @@ mod tests {
         #[test]
         fn parse_empty() {
             assert_eq!(
-                comment(Input::new_extra(";\n", Global)).unwrap().1,
+                comment(input(";\n")).unwrap().1,
                 LineComment::new("", Position::new(0, 1)).into()
             );
         }
@@ -891,7 +1175,7 @@ mod tests {
         #[test]
         fn parse_comment() {
             assert_eq!(
-                comment(Input::new_extra(";foo\n", Global)).unwrap().1,
+                comment(input(";foo\n")).unwrap().1,
                 LineComment::new("foo", Position::new(0, 4)).into()
             );
         }
@@ -899,9 +1183,7 @@ mod tests {
         #[test]
         fn parse_comments() {
             assert_eq!(
-                comments(Input::new_extra(";foo\n;bar\n", Global))
-                    .unwrap()
-                    .1,
+                comments(input(";foo\n;bar\n")).unwrap().1,
                 vec![
                     LineComment::new("foo", Position::new(0, 4)).into(),
                     LineComment::new("bar", Position::new(5, 9)).into()
@@ -912,9 +1194,7 @@ mod tests {
         #[test]
         fn parse_comments_with_blank_lines() {
             assert_eq!(
-                comments(Input::new_extra(";foo\n\n;bar\n", Global))
-                    .unwrap()
-                    .1,
+                comments(input(";foo\n\n;bar\n")).unwrap().1,
                 vec![
                     LineComment::new("foo", Position::new(0, 4)).into(),
                     LineComment::new("bar", Position::new(6, 10)).into()
@@ -922,47 +1202,72 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parse_comments_skipping_escaped_semicolon() {
+            assert_eq!(
+                comments(input("a\\;b\n;foo\n")).unwrap().1,
+                vec![LineComment::new("foo", Position::new(5, 9)).into()]
+            );
+        }
+
         #[test]
         fn parse_comments_skipping_hash_semicolon() {
             assert_eq!(
-                comments(Input::new_extra("#;foo\n;bar\n", Global))
-                    .unwrap()
-                    .1,
+                comments(input("#;foo\n;bar\n")).unwrap().1,
                 vec![LineComment::new("bar", Position::new(6, 10)).into()]
             );
         }
 
+        #[test]
+        fn parse_comment_in_hash_semicolon_quoted_list() {
+            assert_eq!(
+                comments(input("#;(a ;note\n b)")).unwrap().1,
+                vec![LineComment::new("note", Position::new(5, 10)).into()]
+            );
+        }
+
         #[test]
         fn parse_comments_skipping_hash_character() {
             assert_eq!(
-                comments(Input::new_extra("#foo\n;bar\n", Global))
-                    .unwrap()
-                    .1,
+                comments(input("#foo\n;bar\n")).unwrap().1,
                 vec![LineComment::new("bar", Position::new(5, 9)).into()]
             );
         }
 
         #[test]
-        fn parse_comment_character() {
+        fn parse_comments_skipping_hash_directive() {
             assert_eq!(
-                comments(Input::new_extra("#\\;foo\n", Global)).unwrap().1,
-                vec![]
+                comments(input("#lang racket ;foo\n;bar\n")).unwrap().1,
+                vec![LineComment::new("bar", Position::new(18, 22)).into()]
             );
         }
 
         #[test]
-        fn parse_comment_in_list() {
+        fn parse_comment_after_hash_directives() {
             assert_eq!(
-                comments(Input::new_extra("(f\n;foo\nx)", Global))
+                comments(input("#!/bin/sh\n#lang r7rs ;foo\n(a) ;bar\n"))
                     .unwrap()
                     .1,
+                vec![LineComment::new("bar", Position::new(30, 34)).into()]
+            );
+        }
+
+        #[test]
+        fn parse_comment_character() {
+            assert_eq!(comments(input("#\\;foo\n")).unwrap().1, vec![]);
+        }
+
+        #[test]
+        fn parse_comment_in_list() {
+            assert_eq!(
+                comments(input("(f\n;foo\nx)")).unwrap().1,
                 vec![LineComment::new("foo", Position::new(3, 7)).into()]
             );
         }
 
         #[test]
         fn parse_comment_with_vector() {
-            assert_eq!(comments(Input::new_extra("#()", Global)).unwrap().1, vec![]);
+            assert_eq!(comments(input("#()")).unwrap().1, vec![]);
         }
 
         mod block {
@@ -972,17 +1277,52 @@ mod tests {
             #[test]
             fn parse_empty() {
                 assert_eq!(
-                    block_comment(Input::new_extra("#||#", Global)).unwrap().1,
+                    block_comment(input("#||#")).unwrap().1,
                     BlockComment::new("", Position::new(0, 4))
                 );
             }
 
+            #[test]
+            fn parse_hash() {
+                assert_eq!(
+                    block_comment(input("#|#|#")).unwrap().1,
+                    BlockComment::new("#", Position::new(0, 5))
+                );
+            }
+
+            #[test]
+            fn parse_bar() {
+                assert_eq!(
+                    block_comment(input("#|||#")).unwrap().1,
+                    BlockComment::new("|", Position::new(0, 5))
+                );
+            }
+
+            #[test]
+            fn parse_bar_before_terminator() {
+                assert_eq!(
+                    block_comment(input("#|foo||#")).unwrap().1,
+                    BlockComment::new("foo|", Position::new(0, 8))
+                );
+            }
+
+            #[test]
+            fn parse_shortest_comment() {
+                assert_eq!(
+                    block_comment(input("#|#|# foo |#")).unwrap().1,
+                    BlockComment::new("#", Position::new(0, 5))
+                );
+            }
+
+            #[test]
+            fn fail_to_parse_start_only() {
+                assert!(block_comment(input("#|#")).is_err());
+            }
+
             #[test]
             fn parse_one_line() {
                 assert_eq!(
-                    block_comment(Input::new_extra("#|foo|#", Global))
-                        .unwrap()
-                        .1,
+                    block_comment(input("#|foo|#")).unwrap().1,
                     BlockComment::new("foo", Position::new(0, 7))
                 );
             }
@@ -991,9 +1331,7 @@ mod tests {
             fn parse_multi_line() {
                 assert_eq!(
                     // spell-checker: disable-next-line
-                    block_comment(Input::new_extra("#|\nfoo\nbar\nbaz\n|#", Global))
-                        .unwrap()
-                        .1,
+                    block_comment(input("#|\nfoo\nbar\nbaz\n|#")).unwrap().1,
                     // spell-checker: disable-next-line
                     BlockComment::new("\nfoo\nbar\nbaz\n", Position::new(0, 17))
                 );
@@ -1002,10 +1340,53 @@ mod tests {
             #[test]
             fn parse_in_comments() {
                 assert_eq!(
-                    comments(Input::new_extra("#|foo|#", Global)).unwrap().1,
+                    comments(input("#|foo|#")).unwrap().1,
                     vec![BlockComment::new("foo", Position::new(0, 7)).into()]
                 );
             }
         }
     }
+
+    mod dialect {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn elisp_input(source: &str) -> Input<'_, Global> {
+            Input::new_extra(source, Extra::new(Global, Dialect::Elisp))
+        }
+
+        #[test]
+        fn parse_braces_in_scheme() {
+            assert_eq!(
+                expression(input("{foo}")).unwrap().1,
+                Expression::List(
+                    "{",
+                    "}",
+                    vec![Expression::Symbol("foo", Position::new(1, 4))],
+                    Position::new(0, 5)
+                )
+            );
+        }
+
+        #[test]
+        fn parse_braces_in_elisp() {
+            assert_eq!(
+                expression(elisp_input("{foo}")).unwrap().1,
+                Expression::Symbol("{foo}", Position::new(0, 5))
+            );
+        }
+
+        #[test]
+        fn parse_block_comment_in_scheme() {
+            assert_eq!(
+                comment(input("#|foo|#")).unwrap().1,
+                BlockComment::new("foo", Position::new(0, 7)).into()
+            );
+        }
+
+        #[test]
+        fn parse_block_comment_in_elisp() {
+            assert!(comment(elisp_input("#|foo|#")).is_err());
+        }
+    }
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Position {
     start: usize,
     end: usize,
@@ -30,4 +30,58 @@ impl Position {
             end,
         }
     }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn span<'a>(positions: impl IntoIterator<Item = &'a Self>) -> Option<Self> {
+        positions
+            .into_iter()
+            .fold(None, |span: Option<Self>, position| {
+                Some(span.map_or_else(|| position.clone(), |span| span.merge(position)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn merge_positions() {
+        assert_eq!(
+            Position::new(1, 3).merge(&Position::new(5, 8)),
+            Position::new(1, 8)
+        );
+        assert_eq!(
+            Position::new(5, 8).merge(&Position::new(1, 3)),
+            Position::new(1, 8)
+        );
+        assert_eq!(
+            Position::new(1, 8).merge(&Position::new(2, 3)),
+            Position::new(1, 8)
+        );
+    }
+
+    #[test]
+    fn span_positions() {
+        assert_eq!(Position::span([]), None);
+        assert_eq!(
+            Position::span(&[Position::new(2, 4)]),
+            Some(Position::new(2, 4))
+        );
+        assert_eq!(
+            Position::span(&[
+                Position::new(2, 4),
+                Position::new(6, 7),
+                Position::new(9, 12)
+            ]),
+            Some(Position::new(2, 12))
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use crate::source::{measure_width, BYTE_ORDER_MARK};
 use std::ops::Range;
 
 #[derive(Debug)]
@@ -36,9 +37,31 @@ impl PositionMap {
         }
     }
 
-    pub fn column_index(&self, offset: usize) -> Option<usize> {
+    /// Returns a line index of an offset checking a given line and its next one
+    /// before searching all lines.
+    pub fn line_index_near(&self, offset: usize, line: usize) -> Option<usize> {
+        for line in [line, line + 1] {
+            if self
+                .lines
+                .get(line + 1)
+                .is_some_and(|&end| self.lines[line] <= offset && offset < end)
+            {
+                return Some(line);
+            }
+        }
+
         self.line_index(offset)
-            .map(|line| offset - self.lines[line])
+    }
+
+    pub fn column_index(&self, source: &str, offset: usize, tab_width: usize) -> Option<usize> {
+        self.line_index(offset).map(|line| {
+            measure_width(
+                // A byte order mark takes no column.
+                String::from_utf8_lossy(&source.as_bytes()[self.lines[line]..offset])
+                    .trim_start_matches(BYTE_ORDER_MARK),
+                tab_width,
+            )
+        })
     }
 
     pub fn line_range(&self, offset: usize) -> Option<Range<usize>> {
@@ -100,6 +123,40 @@ mod tests {
             assert_eq!(map.line_index(7), Some(1));
             assert_eq!(map.line_index(8), None);
         }
+
+        #[test]
+        fn get_lines_in_random_order() {
+            let source = "foo\nbar\nbaz\n";
+            let map = PositionMap::new(source);
+
+            for (offset, line) in [
+                (9, Some(2)),
+                (10, Some(2)),
+                (0, Some(0)),
+                (5, Some(1)),
+                (12, None),
+                (4, Some(1)),
+                (3, Some(0)),
+                (8, Some(2)),
+            ] {
+                assert_eq!(map.line_index(offset), line);
+            }
+        }
+
+        #[test]
+        fn get_lines_near_other_lines() {
+            let source = "foo\nbar\nbaz\n";
+            let map = PositionMap::new(source);
+
+            for near_line in 0..4 {
+                for offset in 0..13 {
+                    assert_eq!(
+                        map.line_index_near(offset, near_line),
+                        map.line_index(offset)
+                    );
+                }
+            }
+        }
     }
 
     mod line_range {
@@ -143,4 +200,38 @@ mod tests {
             assert_eq!(map.line_range(7), None);
         }
     }
+
+    mod column_index {
+        use super::*;
+
+        #[test]
+        fn get_in_line() {
+            let source = "foo";
+            let map = PositionMap::new(source);
+
+            assert_eq!(map.column_index(source, 0, 8), Some(0));
+            assert_eq!(map.column_index(source, 2, 8), Some(2));
+            assert_eq!(map.column_index(source, 3, 8), None);
+        }
+
+        #[test]
+        fn get_after_tab() {
+            let source = "foo\n\tbar\n";
+            let map = PositionMap::new(source);
+
+            assert_eq!(map.column_index(source, 4, 8), Some(0));
+            assert_eq!(map.column_index(source, 5, 8), Some(8));
+            assert_eq!(map.column_index(source, 6, 8), Some(9));
+            assert_eq!(map.column_index(source, 5, 4), Some(4));
+        }
+
+        #[test]
+        fn get_after_multi_byte_characters() {
+            let source = "あい\tu";
+            let map = PositionMap::new(source);
+
+            assert_eq!(map.column_index(source, 6, 8), Some(2));
+            assert_eq!(map.column_index(source, 7, 8), Some(8));
+        }
+    }
 }
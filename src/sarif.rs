@@ -0,0 +1,188 @@
+use crate::{error::ApplicationError, json::escape};
+use std::ops::Range;
+
+const SCHEMA_URI: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/raviqqe/schemat";
+const UNFORMATTED_RULE_ID: &str = "unformatted";
+const ERROR_RULE_ID: &str = "error";
+
+/// A result in a SARIF log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SarifResult {
+    rule_id: &'static str,
+    level: &'static str,
+    message: String,
+    path: String,
+    lines: Range<usize>,
+    column: Option<usize>,
+}
+
+impl SarifResult {
+    /// Creates a result of an unformatted file with a range of changed lines.
+    pub fn unformatted(path: &str, lines: Range<usize>) -> Self {
+        Self {
+            rule_id: UNFORMATTED_RULE_ID,
+            level: "warning",
+            message: "file not formatted".into(),
+            path: path.into(),
+            lines,
+            column: None,
+        }
+    }
+
+    /// Creates a result of a file failed to be checked.
+    pub fn error(path: &str, error: &ApplicationError) -> Self {
+        let (line, column) = error
+            .location()
+            .map_or((0, None), |(line, column)| (line, Some(column)));
+
+        Self {
+            rule_id: ERROR_RULE_ID,
+            level: "error",
+            message: match error {
+                ApplicationError::Parse { error, .. } => error.message().into(),
+                error => error.to_string(),
+            },
+            path: path.into(),
+            lines: line..line + 1,
+            column,
+        }
+    }
+
+    /// Returns a level.
+    pub const fn level(&self) -> &'static str {
+        self.level
+    }
+
+    /// Returns a message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns a path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns a range of line indexes.
+    pub fn lines(&self) -> &Range<usize> {
+        &self.lines
+    }
+
+    /// Returns a column index.
+    pub const fn column(&self) -> Option<usize> {
+        self.column
+    }
+}
+
+/// Renders results as a SARIF log.
+pub fn render_sarif(results: &[SarifResult]) -> String {
+    format!(
+        concat!(
+            r#"{{"$schema":"{}","version":"2.1.0","runs":[{{"tool":{{"driver":{{"#,
+            r#""name":"schemat","version":"{}","informationUri":"{}","rules":["#,
+            r#"{{"id":"{}","shortDescription":{{"text":"Unformatted file"}}}},"#,
+            r#"{{"id":"{}","shortDescription":{{"text":"File failed to be checked"}}}}"#,
+            r#"]}}}},"results":[{}]}}]}}"#,
+        ),
+        SCHEMA_URI,
+        env!("CARGO_PKG_VERSION"),
+        INFORMATION_URI,
+        UNFORMATTED_RULE_ID,
+        ERROR_RULE_ID,
+        results
+            .iter()
+            .map(render_result)
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+fn render_result(result: &SarifResult) -> String {
+    format!(
+        concat!(
+            r#"{{"ruleId":"{}","level":"{}","message":{{"text":"{}"}},"#,
+            r#""locations":[{{"physicalLocation":{{"artifactLocation":{{"uri":"{}"}},"#,
+            r#""region":{{"startLine":{}{},"endLine":{}}}}}}}]}}"#,
+        ),
+        result.rule_id,
+        result.level,
+        escape(&result.message),
+        escape(&result.path),
+        result.lines.start + 1,
+        result
+            .column
+            .map(|column| format!(r#","startColumn":{}"#, column + 1))
+            .unwrap_or_default(),
+        result.lines.end,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io;
+
+    #[test]
+    fn render_no_result() {
+        assert_eq!(
+            render_sarif(&[]),
+            format!(
+                concat!(
+                    r#"{{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","#,
+                    r#""version":"2.1.0","runs":[{{"tool":{{"driver":{{"name":"schemat","#,
+                    r#""version":"{}","informationUri":"https://github.com/raviqqe/schemat","#,
+                    r#""rules":[{{"id":"unformatted","#,
+                    r#""shortDescription":{{"text":"Unformatted file"}}}},"#,
+                    r#"{{"id":"error","#,
+                    r#""shortDescription":{{"text":"File failed to be checked"}}}}]}}}},"#,
+                    r#""results":[]}}]}}"#,
+                ),
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn render_unformatted_result() {
+        assert_eq!(
+            render_result(&SarifResult::unformatted("foo.scm", 1..3)),
+            concat!(
+                r#"{"ruleId":"unformatted","level":"warning","#,
+                r#""message":{"text":"file not formatted"},"#,
+                r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"foo.scm"},"#,
+                r#""region":{"startLine":2,"endLine":3}}}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn render_io_error_result() {
+        assert_eq!(
+            render_result(&SarifResult::error(
+                "foo.scm",
+                &io::Error::other("bar \"baz\"").into()
+            )),
+            concat!(
+                r#"{"ruleId":"error","level":"error","#,
+                r#""message":{"text":"bar \"baz\""},"#,
+                r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"foo.scm"},"#,
+                r#""region":{"startLine":1,"endLine":1}}}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn render_results() {
+        let result = SarifResult::unformatted("foo.scm", 0..1);
+
+        assert!(
+            render_sarif(&[result.clone(), result.clone()]).ends_with(&format!(
+                r#""results":[{},{}]}}]}}"#,
+                render_result(&result),
+                render_result(&result)
+            ))
+        );
+    }
+}
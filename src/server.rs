@@ -0,0 +1,252 @@
+//! A server mode formatting sources over stdin and stdout.
+//!
+//! A request consists of a header line of byte lengths of a path and a source,
+//! and their bytes.
+//!
+//! ```text
+//! <path length> <source length>\n<path><source>
+//! ```
+//!
+//! A header line may end with `\r\n` instead of `\n`. Requests larger than a
+//! maximum size, ones with header lines too long, and ones of invalid UTF-8
+//! are skipped with error responses.
+//!
+//! A response consists of a header line of a status and a byte length of a
+//! body, and its bytes. A body is a formatted source on success or an error
+//! message ending with a newline on failure.
+//!
+//! ```text
+//! ok <length>\n<formatted source>
+//! error <length>\n<message>
+//! ```
+
+use crate::format_string;
+use schemat::Options;
+use std::error::Error;
+use tokio::io::{
+    copy, sink, AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+
+/// A default maximum size of a request in bytes.
+pub const DEFAULT_MAX_REQUEST_SIZE: u64 = 1 << 26;
+
+// A header line has at most two 20-digit numbers, a space, and `\r\n`.
+const MAX_HEADER_SIZE: u64 = 64;
+
+/// Serves requests until the end of input.
+///
+/// Request sizes are the sums of path and source lengths.
+pub async fn serve(
+    mut reader: impl AsyncBufRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    max_size: u64,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let mut header = vec![];
+
+    loop {
+        header.clear();
+
+        if (&mut reader)
+            .take(MAX_HEADER_SIZE)
+            .read_until(b'\n', &mut header)
+            .await?
+            == 0
+        {
+            return Ok(());
+        }
+
+        let (status, body) = if header.len() as u64 == MAX_HEADER_SIZE && !header.ends_with(b"\n") {
+            skip_line(&mut reader).await?;
+
+            (
+                "error",
+                format!("request header too long (> {} bytes)\n", MAX_HEADER_SIZE),
+            )
+        } else {
+            let (path_length, source_length) = parse_header(&String::from_utf8_lossy(&header))?;
+            let size = path_length.saturating_add(source_length);
+
+            if size > max_size {
+                skip_bytes(&mut reader, size).await?;
+
+                (
+                    "error",
+                    format!("request too large ({} > {} bytes)\n", size, max_size),
+                )
+            } else {
+                let path = read_bytes(&mut reader, path_length).await?;
+                let source = read_bytes(&mut reader, source_length).await?;
+
+                match (String::from_utf8(path), String::from_utf8(source)) {
+                    (Ok(path), Ok(source)) => match format_string(&source, &path, options) {
+                        Ok(formatted) => ("ok", formatted),
+                        Err(error) => ("error", format!("{}\n", error)),
+                    },
+                    _ => ("error", "invalid UTF-8 in request\n".into()),
+                }
+            }
+        };
+
+        writer
+            .write_all(format!("{} {}\n", status, body.len()).as_bytes())
+            .await?;
+        writer.write_all(body.as_bytes()).await?;
+        writer.flush().await?;
+    }
+}
+
+fn parse_header(header: &str) -> Result<(u64, u64), Box<dyn Error>> {
+    let error = || format!("invalid request header: {}", header.trim_end());
+    let Some((path_length, source_length)) = header
+        .trim_end_matches('\n')
+        .trim_end_matches('\r')
+        .split_once(' ')
+    else {
+        return Err(error().into());
+    };
+
+    Ok((
+        path_length.parse().map_err(|_| error())?,
+        source_length.parse().map_err(|_| error())?,
+    ))
+}
+
+async fn read_bytes(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    length: u64,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes = vec![0; length.try_into()?];
+    reader.read_exact(&mut bytes).await?;
+
+    Ok(bytes)
+}
+
+// Skips the rest of a line without buffering it.
+async fn skip_line(reader: &mut (impl AsyncBufRead + Unpin)) -> Result<(), Box<dyn Error>> {
+    loop {
+        let buffer = reader.fill_buf().await?;
+
+        if buffer.is_empty() {
+            return Ok(());
+        } else if let Some(index) = buffer.iter().position(|&byte| byte == b'\n') {
+            reader.consume(index + 1);
+            return Ok(());
+        }
+
+        let length = buffer.len();
+        reader.consume(length);
+    }
+}
+
+async fn skip_bytes(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    length: u64,
+) -> Result<(), Box<dyn Error>> {
+    if copy(&mut reader.take(length), &mut sink()).await? < length {
+        return Err("truncated request".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    async fn serve_bytes(input: &[u8]) -> Result<String, Box<dyn Error>> {
+        let mut output = vec![];
+
+        serve(input, &mut output, 32, &Options::new()).await?;
+
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[tokio::test]
+    async fn serve_nothing() {
+        assert_eq!(serve_bytes(b"").await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn serve_requests() {
+        assert_eq!(
+            serve_bytes(b"7 10\nfoo.scm(foo  bar)7 3\nbar.scm  a")
+                .await
+                .unwrap(),
+            "ok 10\n(foo bar)\nok 2\na\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn serve_invalid_source() {
+        assert_eq!(
+            serve_bytes(b"7 4\nfoo.scm(foo7 3\nbar.scm  a")
+                .await
+                .unwrap(),
+            "error 50\nfailed to parse (expected list) foo.scm:1:4: (foo\nok 2\na\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn serve_request_with_carriage_return() {
+        assert_eq!(
+            serve_bytes(b"7 10\r\nfoo.scm(foo  bar)").await.unwrap(),
+            "ok 10\n(foo bar)\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_too_large_request() {
+        assert_eq!(
+            serve_bytes(b"7 28\nfoo.scm(foo bar baz qux quux quuux)7 3\nbar.scm  a")
+                .await
+                .unwrap(),
+            "error 34\nrequest too large (35 > 32 bytes)\nok 2\na\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_serve_truncated_too_large_request() {
+        assert_eq!(
+            serve_bytes(b"7 100\nfoo.scm(foo")
+                .await
+                .unwrap_err()
+                .to_string(),
+            "truncated request"
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_request_of_invalid_utf8() {
+        assert_eq!(
+            serve_bytes(b"7 3\nfoo.scm\xff\xfe)7 3\nbar.scm  a")
+                .await
+                .unwrap(),
+            "error 25\ninvalid UTF-8 in request\nok 2\na\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_request_with_too_long_header() {
+        assert_eq!(
+            serve_bytes(format!("{}\n7 3\nbar.scm  a", "1".repeat(100)).as_bytes())
+                .await
+                .unwrap(),
+            "error 37\nrequest header too long (> 64 bytes)\nok 2\na\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_serve_invalid_header() {
+        assert_eq!(
+            serve_bytes(b"foo\n").await.unwrap_err().to_string(),
+            "invalid request header: foo"
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_serve_truncated_request() {
+        assert!(serve_bytes(b"7 10\nfoo.scm(foo").await.is_err());
+    }
+}
@@ -0,0 +1,662 @@
+use crate::{position::Position, text_edit::TextEdit};
+use std::ops::Range;
+
+pub const BYTE_ORDER_MARK: &str = "\u{FEFF}";
+const MAX_EDIT_DISTANCE: usize = 2048;
+
+/// Normalizes line endings of a source if it uses only `\r` as line endings.
+///
+/// It returns `None` if a source does not need to be normalized.
+pub fn normalize_line_endings(source: &str) -> Option<String> {
+    if source.contains('\r') && !source.contains('\n') {
+        Some(source.replace('\r', "\n"))
+    } else {
+        None
+    }
+}
+
+/// Splits a byte order mark from a source if any.
+///
+/// Parsers skip a byte order mark at the beginning of a source, and formatted
+/// code keeps it as it is.
+pub fn split_byte_order_mark(source: &str) -> (&str, &str) {
+    source
+        .strip_prefix(BYTE_ORDER_MARK)
+        .map_or(("", source), |source| (BYTE_ORDER_MARK, source))
+}
+
+/// Measures a width of a text in columns.
+///
+/// Each character takes one column except that tabs advance to the next tab
+/// stop.
+pub fn measure_width(text: &str, tab_width: usize) -> usize {
+    text.chars().fold(0, |width, character| {
+        if character == '\t' && tab_width > 0 {
+            (width / tab_width + 1) * tab_width
+        } else {
+            width + 1
+        }
+    })
+}
+
+/// Finds lines longer than a maximum width in columns.
+///
+/// It returns pairs of line indexes and widths.
+pub fn find_long_lines(
+    source: &str,
+    width: usize,
+    tab_width: usize,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    source
+        .lines()
+        .map(move |line| measure_width(line, tab_width))
+        .enumerate()
+        .filter(move |(_, line_width)| *line_width > width)
+}
+
+/// Finds a range of changed lines in an original source compared with its
+/// formatted one.
+///
+/// The range is never empty so that it points to at least one line.
+pub fn find_changed_lines(source: &str, formatted: &str) -> Range<usize> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let formatted_lines = formatted.lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .zip(&formatted_lines)
+        .take_while(|(line, formatted_line)| line == formatted_line)
+        .count();
+    let end = lines.len()
+        - lines[start..]
+            .iter()
+            .rev()
+            .zip(formatted_lines[start..].iter().rev())
+            .take_while(|(line, formatted_line)| line == formatted_line)
+            .count();
+    let start = start.min(lines.len().saturating_sub(1));
+
+    start..end.max(start + 1)
+}
+
+/// Finds ranges of changed lines in an original source compared with its
+/// formatted one, one for each region of consecutive changes.
+///
+/// Each range is never empty so that it points to at least one line. It falls
+/// back to a single range of all changed lines if sources differ too much.
+pub fn find_changed_regions(source: &str, formatted: &str) -> Vec<Range<usize>> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let formatted_lines = formatted.lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .zip(&formatted_lines)
+        .take_while(|(line, formatted_line)| line == formatted_line)
+        .count();
+    let end = lines[start..]
+        .iter()
+        .rev()
+        .zip(formatted_lines[start..].iter().rev())
+        .take_while(|(line, formatted_line)| line == formatted_line)
+        .count();
+
+    let Some(edits) = find_edits(
+        &lines[start..lines.len() - end],
+        &formatted_lines[start..formatted_lines.len() - end],
+    ) else {
+        return vec![find_changed_lines(source, formatted)];
+    };
+
+    let mut regions = Vec::<Range<usize>>::new();
+
+    for (edit, _) in edits {
+        let edit = edit.start + start..edit.end + start;
+
+        match regions.last_mut() {
+            Some(region) if edit.start <= region.end => region.end = region.end.max(edit.end),
+            _ => regions.push(edit),
+        }
+    }
+
+    if regions.is_empty() {
+        return vec![find_changed_lines(source, formatted)];
+    }
+
+    let last = lines.len().saturating_sub(1);
+
+    regions
+        .into_iter()
+        .map(|region| {
+            let start = region.start.min(last);
+
+            start..region.end.max(start + 1)
+        })
+        .collect()
+}
+
+/// Finds a minimal set of edits of lines transforming an original source into
+/// its formatted one.
+///
+/// Edits are sorted by their positions and never overlap with each other. It
+/// falls back to a single edit of all changed lines if sources differ too much.
+pub fn find_text_edits(source: &str, formatted: &str) -> Vec<TextEdit> {
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let formatted_lines = formatted.split_inclusive('\n').collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .zip(&formatted_lines)
+        .take_while(|(line, formatted_line)| line == formatted_line)
+        .count();
+    let end = lines[start..]
+        .iter()
+        .rev()
+        .zip(formatted_lines[start..].iter().rev())
+        .take_while(|(line, formatted_line)| line == formatted_line)
+        .count();
+    let old_lines = &lines[start..lines.len() - end];
+    let new_lines = &formatted_lines[start..formatted_lines.len() - end];
+
+    if old_lines.is_empty() && new_lines.is_empty() {
+        return vec![];
+    }
+
+    let mut regions = Vec::<(Range<usize>, Range<usize>)>::new();
+
+    for (old, new) in find_edits(old_lines, new_lines)
+        .unwrap_or_else(|| vec![(0..old_lines.len(), 0..new_lines.len())])
+    {
+        match regions.last_mut() {
+            Some(region) if region.0.end == old.start && region.1.end == new.start => {
+                region.0.end = old.end;
+                region.1.end = new.end;
+            }
+            _ => regions.push((old, new)),
+        }
+    }
+
+    // Byte offsets of the old lines and the end of the last one.
+    let offset = lines[..start].iter().map(|line| line.len()).sum();
+    let offsets = [offset]
+        .into_iter()
+        .chain(old_lines.iter().scan(offset, |offset, line| {
+            *offset += line.len();
+            Some(*offset)
+        }))
+        .collect::<Vec<_>>();
+
+    regions
+        .into_iter()
+        .map(|(old, new)| {
+            TextEdit::new(
+                Position::new(offsets[old.start], offsets[old.end]),
+                new_lines[new].concat(),
+            )
+        })
+        .collect()
+}
+
+// Finds line ranges of deletions and insertions in old and new texts with the
+// Myers difference algorithm.
+fn find_edits(old: &[&str], new: &[&str]) -> Option<Vec<(Range<usize>, Range<usize>)>> {
+    // Furthest x coordinates on diagonals of k = x - y from -d to d by 2 for
+    // each number d of edits.
+    let mut trace = Vec::<Vec<usize>>::new();
+
+    'search: for distance in 0..=(old.len() + new.len()).min(MAX_EDIT_DISTANCE) {
+        let mut row = Vec::with_capacity(distance + 1);
+
+        for index in 0..=distance {
+            let k = 2 * index as isize - distance as isize;
+            let mut x = match trace.last() {
+                None => 0,
+                Some(previous) if is_insertion(previous, index, distance) => previous[index],
+                Some(previous) => previous[index - 1] + 1,
+            };
+            let mut y = x.wrapping_add_signed(-k);
+
+            while x < old.len() && y < new.len() && old[x] == new[y] {
+                x += 1;
+                y += 1;
+            }
+
+            row.push(x);
+
+            if x >= old.len() && y >= new.len() {
+                trace.push(row);
+                break 'search;
+            }
+        }
+
+        trace.push(row);
+    }
+
+    let (mut x, mut y) = (old.len(), new.len());
+    let distance = trace.len() - 1;
+
+    if (x + distance)
+        .checked_sub(y)
+        .filter(|index| index % 2 == 0)
+        .and_then(|index| trace[distance].get(index / 2))
+        != Some(&x)
+    {
+        return None;
+    }
+
+    let mut edits = Vec::with_capacity(distance);
+
+    for distance in (1..=distance).rev() {
+        let previous = &trace[distance - 1];
+        let index = (x + distance - y) / 2;
+        let insertion = is_insertion(previous, index, distance);
+        let index = if insertion { index } else { index - 1 };
+        let previous_x = previous[index];
+        let previous_y = previous_x.wrapping_add_signed(distance as isize - 1 - 2 * index as isize);
+
+        edits.push((
+            previous_x..previous_x + usize::from(!insertion),
+            previous_y..previous_y + usize::from(insertion),
+        ));
+
+        x = previous_x;
+        y = previous_y;
+    }
+
+    edits.reverse();
+
+    Some(edits)
+}
+
+// Returns `true` if the furthest path on a diagonal comes from an upper one.
+fn is_insertion(previous: &[usize], index: usize, distance: usize) -> bool {
+    index == 0 || index != distance && previous[index - 1] < previous[index]
+}
+
+/// Renders a difference between expected and actual texts in a unified diff
+/// format.
+///
+/// It shows a single hunk from the first to the last changed lines.
+pub fn render_diff(expected: &str, actual: &str) -> String {
+    let expected_lines = expected.split_inclusive('\n').collect::<Vec<_>>();
+    let actual_lines = actual.split_inclusive('\n').collect::<Vec<_>>();
+    let start = expected_lines
+        .iter()
+        .zip(&actual_lines)
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    let end = expected_lines[start..]
+        .iter()
+        .rev()
+        .zip(actual_lines[start..].iter().rev())
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    let mut diff = format!("@@ line {} @@\n", start + 1);
+
+    for (sign, lines) in [("-", &expected_lines), ("+", &actual_lines)] {
+        for line in &lines[start..lines.len() - end] {
+            diff.push_str(sign);
+            diff.push_str(line);
+
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::format,
+        options::Dialect,
+        parse::{parse, parse_comments, parse_hash_directives},
+        position_map::PositionMap,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn split_byte_order_mark_from_source() {
+        assert_eq!(
+            split_byte_order_mark("\u{FEFF}(foo)"),
+            ("\u{FEFF}", "(foo)")
+        );
+        assert_eq!(split_byte_order_mark("(foo)"), ("", "(foo)"));
+    }
+    use std::alloc::Global;
+
+    #[test]
+    fn normalize_carriage_returns() {
+        assert_eq!(
+            normalize_line_endings("foo\rbar\r"),
+            Some("foo\nbar\n".into())
+        );
+    }
+
+    #[test]
+    fn skip_line_feeds() {
+        assert_eq!(normalize_line_endings("foo\nbar\n"), None);
+    }
+
+    #[test]
+    fn skip_carriage_returns_and_line_feeds() {
+        assert_eq!(normalize_line_endings("foo\r\nbar\r\n"), None);
+    }
+
+    #[test]
+    fn format_normalized_source() {
+        let source = normalize_line_endings("(foo\r  bar)\r;baz\r(qux)\r").unwrap();
+
+        assert_eq!(
+            format(
+                &parse(&source, Dialect::Scheme, Global).unwrap(),
+                &parse_comments(&source, &Default::default(), Global).unwrap(),
+                &parse_hash_directives(&source, Dialect::Scheme, Global).unwrap(),
+                &PositionMap::new(&source),
+                &Default::default(),
+                Global,
+            )
+            .unwrap(),
+            "(foo\n  bar)\n;baz\n(qux)\n"
+        );
+    }
+
+    #[test]
+    fn find_no_long_line() {
+        assert_eq!(
+            find_long_lines("foo\nbar\n", 3, 8).collect::<Vec<_>>(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn find_long_line() {
+        assert_eq!(
+            find_long_lines("foo\n\"barbaz\"\nqux\n", 3, 8).collect::<Vec<_>>(),
+            vec![(1, 8)]
+        );
+    }
+
+    #[test]
+    fn find_long_lines_with_multi_byte_characters() {
+        assert_eq!(
+            find_long_lines("あいう\nあいうえ\n", 3, 8).collect::<Vec<_>>(),
+            vec![(1, 4)]
+        );
+    }
+
+    #[test]
+    fn find_long_line_with_tab() {
+        assert_eq!(
+            find_long_lines("\tfoo\n  foo\n", 8, 8).collect::<Vec<_>>(),
+            vec![(0, 11)]
+        );
+        assert_eq!(
+            find_long_lines("\tfoo\n  foo\n", 8, 2).collect::<Vec<_>>(),
+            vec![]
+        );
+    }
+
+    mod find_changed_lines {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_changed_line() {
+            assert_eq!(
+                find_changed_lines("foo\n bar\nbaz\n", "foo\nbar\nbaz\n"),
+                1..2
+            );
+        }
+
+        #[test]
+        fn find_changed_lines_in_middle() {
+            assert_eq!(
+                find_changed_lines("foo\n bar\n baz\nqux\n", "foo\nbar\nbaz\nqux\n"),
+                1..3
+            );
+        }
+
+        #[test]
+        fn find_removed_lines() {
+            assert_eq!(find_changed_lines("foo\n\n\n\nbar\n", "foo\n\nbar\n"), 2..4);
+        }
+
+        #[test]
+        fn find_missing_newline() {
+            assert_eq!(find_changed_lines("foo\nbar", "foo\nbar\n"), 1..2);
+        }
+
+        #[test]
+        fn find_changed_lines_in_empty_source() {
+            assert_eq!(find_changed_lines("", "foo\n"), 0..1);
+        }
+    }
+
+    mod find_changed_regions {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_changed_region() {
+            assert_eq!(
+                find_changed_regions("foo\n bar\nbaz\n", "foo\nbar\nbaz\n"),
+                vec![1..2]
+            );
+        }
+
+        #[test]
+        fn find_separate_regions() {
+            assert_eq!(
+                find_changed_regions(
+                    "foo\n bar\nbaz\nqux\nquux\n corge\ngrault\n",
+                    "foo\nbar\nbaz\nqux\nquux\ncorge\ngrault\n"
+                ),
+                vec![1..2, 5..6]
+            );
+        }
+
+        #[test]
+        fn find_adjacent_changes_as_region() {
+            assert_eq!(
+                find_changed_regions("foo\n bar\n baz\nqux\n", "foo\nbar\nbaz\nqux\n"),
+                vec![1..3]
+            );
+        }
+
+        #[test]
+        fn find_added_lines() {
+            assert_eq!(
+                find_changed_regions("foo\nbaz\nqux\nquux\n", "foo\nbar\nbaz\nqux\n\nquux\n"),
+                vec![1..2, 3..4]
+            );
+        }
+
+        #[test]
+        fn find_removed_lines() {
+            assert_eq!(
+                find_changed_regions("foo\n\n\n\nbar\n\n\nbaz\n", "foo\n\nbar\n\nbaz\n"),
+                vec![2..4, 5..6]
+            );
+        }
+
+        #[test]
+        fn find_missing_newline() {
+            assert_eq!(find_changed_regions("foo\nbar", "foo\nbar\n"), vec![1..2]);
+        }
+
+        #[test]
+        fn find_changed_regions_in_empty_source() {
+            assert_eq!(find_changed_regions("", "foo\n"), vec![0..1]);
+        }
+
+        #[test]
+        fn find_region_of_too_many_changes() {
+            let source = (0..MAX_EDIT_DISTANCE)
+                .map(|index| format!("foo{index}\n"))
+                .collect::<String>();
+            let formatted = (0..MAX_EDIT_DISTANCE)
+                .map(|index| format!("bar{index}\n"))
+                .collect::<String>();
+
+            assert_eq!(
+                find_changed_regions(&source, &formatted),
+                vec![0..MAX_EDIT_DISTANCE]
+            );
+        }
+    }
+
+    mod find_text_edits {
+        use super::*;
+        use crate::text_edit::apply_text_edits;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn find_no_edit() {
+            assert_eq!(find_text_edits("foo\nbar\n", "foo\nbar\n"), vec![]);
+        }
+
+        #[test]
+        fn find_edit_of_changed_line() {
+            assert_eq!(
+                find_text_edits("foo\n bar\nbaz\n", "foo\nbar\nbaz\n"),
+                vec![TextEdit::new(Position::new(4, 9), "bar\n")]
+            );
+        }
+
+        #[test]
+        fn find_separate_edits() {
+            assert_eq!(
+                find_text_edits(
+                    "foo\n bar\nbaz\nqux\nquux\n corge\ngrault\n",
+                    "foo\nbar\nbaz\nqux\nquux\ncorge\ngrault\n"
+                ),
+                vec![
+                    TextEdit::new(Position::new(4, 9), "bar\n"),
+                    TextEdit::new(Position::new(22, 29), "corge\n"),
+                ]
+            );
+        }
+
+        #[test]
+        fn find_edits_of_added_and_removed_lines() {
+            assert_eq!(
+                find_text_edits("foo\n\n\nbar\nbaz\n", "foo\n\nbar\nqux\nbaz\n"),
+                vec![
+                    TextEdit::new(Position::new(5, 6), ""),
+                    TextEdit::new(Position::new(10, 10), "qux\n"),
+                ]
+            );
+        }
+
+        #[test]
+        fn find_edit_of_missing_newline() {
+            assert_eq!(
+                find_text_edits("foo\nbar", "foo\nbar\n"),
+                vec![TextEdit::new(Position::new(4, 7), "bar\n")]
+            );
+        }
+
+        #[test]
+        fn find_edit_in_empty_source() {
+            assert_eq!(
+                find_text_edits("", "foo\n"),
+                vec![TextEdit::new(Position::new(0, 0), "foo\n")]
+            );
+        }
+
+        #[test]
+        fn find_edit_of_too_many_changes() {
+            let source = (0..MAX_EDIT_DISTANCE)
+                .map(|index| format!("foo{index}\n"))
+                .collect::<String>();
+            let formatted = (0..MAX_EDIT_DISTANCE)
+                .map(|index| format!("bar{index}\n"))
+                .collect::<String>();
+
+            assert_eq!(
+                find_text_edits(&source, &formatted),
+                vec![TextEdit::new(Position::new(0, source.len()), formatted)]
+            );
+        }
+
+        #[test]
+        fn apply_found_edits() {
+            for (source, formatted) in [
+                ("foo\nbar\nbaz\n", "bar\nfoo\nbaz\nqux\n"),
+                ("(foo\n bar\n   baz)", "(foo\n  bar\n  baz)\n"),
+                ("\n\nfoo\n\n\nbar\n\n", "foo\n\nbar\n"),
+                ("foo\n", ""),
+            ] {
+                assert_eq!(
+                    apply_text_edits(source, &find_text_edits(source, formatted)),
+                    formatted
+                );
+            }
+        }
+    }
+
+    mod render_diff {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn render_changed_line() {
+            assert_eq!(
+                render_diff("foo\nbar\nbaz\n", "foo\n bar\nbaz\n"),
+                "@@ line 2 @@\n-bar\n+ bar\n"
+            );
+        }
+
+        #[test]
+        fn render_added_lines() {
+            assert_eq!(
+                render_diff("foo\nbaz\n", "foo\nbar\nqux\nbaz\n"),
+                "@@ line 2 @@\n+bar\n+qux\n"
+            );
+        }
+
+        #[test]
+        fn render_removed_line() {
+            assert_eq!(render_diff("foo\n\n", "foo\n"), "@@ line 2 @@\n-\n");
+        }
+
+        #[test]
+        fn render_missing_newline() {
+            assert_eq!(
+                render_diff("foo\n", "foo"),
+                "@@ line 1 @@\n-foo\n+foo\n\\ No newline at end of file\n"
+            );
+        }
+    }
+
+    mod measure_width {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn measure_characters() {
+            assert_eq!(measure_width("", 8), 0);
+            assert_eq!(measure_width("foo", 8), 3);
+            assert_eq!(measure_width("あいう", 8), 3);
+        }
+
+        #[test]
+        fn measure_leading_tab() {
+            assert_eq!(measure_width("\t", 8), 8);
+            assert_eq!(measure_width("\tfoo", 8), 11);
+            assert_eq!(measure_width("\tfoo", 4), 7);
+        }
+
+        #[test]
+        fn measure_tab_after_characters() {
+            assert_eq!(measure_width("foo\t", 8), 8);
+            assert_eq!(measure_width("foo\t", 2), 4);
+            assert_eq!(measure_width("foobar\t\t", 4), 12);
+        }
+
+        #[test]
+        fn measure_tab_with_zero_width() {
+            assert_eq!(measure_width("\tfoo", 0), 4);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::position::Position;
+
+/// An edit replacing a byte range in an original source with a text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextEdit {
+    position: Position,
+    text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit.
+    pub fn new(position: Position, text: impl Into<String>) -> Self {
+        Self {
+            position,
+            text: text.into(),
+        }
+    }
+
+    /// Returns a byte range to replace in an original source.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Returns a replacement text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Applies edits to a source.
+///
+/// Edits must be sorted by their positions and must not overlap with each
+/// other.
+pub fn apply_text_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut string = String::with_capacity(source.len());
+    let mut offset = 0;
+
+    for edit in edits {
+        string.push_str(&source[offset..edit.position.start()]);
+        string.push_str(&edit.text);
+        offset = edit.position.end();
+    }
+
+    string.push_str(&source[offset..]);
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn apply_no_edit() {
+        assert_eq!(apply_text_edits("foo", &[]), "foo");
+    }
+
+    #[test]
+    fn apply_edits() {
+        assert_eq!(
+            apply_text_edits(
+                "foo\nbar\nbaz\n",
+                &[
+                    TextEdit::new(Position::new(0, 0), "qux\n"),
+                    TextEdit::new(Position::new(4, 8), ""),
+                    TextEdit::new(Position::new(12, 12), "quux\n"),
+                ]
+            ),
+            "qux\nfoo\nbaz\nquux\n"
+        );
+    }
+}
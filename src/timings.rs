@@ -0,0 +1,56 @@
+use core::time::Duration;
+
+/// Durations of parsing and formatting a source.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timings {
+    parse: Duration,
+    format: Duration,
+}
+
+impl Timings {
+    /// Creates timings.
+    pub const fn new(parse: Duration, format: Duration) -> Self {
+        Self { parse, format }
+    }
+
+    /// Returns a duration of parsing.
+    pub const fn parse(&self) -> Duration {
+        self.parse
+    }
+
+    /// Returns a duration of formatting.
+    pub const fn format(&self) -> Duration {
+        self.format
+    }
+
+    /// Returns a total duration.
+    pub fn total(&self) -> Duration {
+        self.parse + self.format
+    }
+}
+
+impl core::ops::Add for Timings {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.parse + other.parse, self.format + other.format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn add_timings() {
+        let timings = Timings::new(Duration::from_millis(1), Duration::from_millis(2))
+            + Timings::new(Duration::from_millis(3), Duration::from_millis(4));
+
+        assert_eq!(
+            timings,
+            Timings::new(Duration::from_millis(4), Duration::from_millis(6))
+        );
+        assert_eq!(timings.total(), Duration::from_millis(10));
+    }
+}
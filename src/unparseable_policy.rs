@@ -0,0 +1,9 @@
+/// A policy for files that fail to be parsed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum UnparseablePolicy {
+    /// Fail on them.
+    #[default]
+    Error,
+    /// Skip them with warnings.
+    Skip,
+}
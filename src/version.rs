@@ -0,0 +1,84 @@
+use semver::{Version, VersionReq};
+
+/// Checks if a version satisfies a requirement in the syntax of Cargo, such as
+/// `0.2`, `^0.2.14`, `~0.2`, `0.2.*`, and `>=0.2, <0.4`.
+pub fn satisfies(requirement: &str, version: &str) -> Result<bool, semver::Error> {
+    Ok(VersionReq::parse(requirement)?.matches(&Version::parse(version)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfies_ok(requirement: &str, version: &str) -> bool {
+        satisfies(requirement, version).unwrap()
+    }
+
+    #[test]
+    fn satisfy_exact_version() {
+        assert!(satisfies_ok("=0.2.14", "0.2.14"));
+        assert!(!satisfies_ok("=0.2.14", "0.2.15"));
+        assert!(satisfies_ok("=0.2", "0.2.15"));
+    }
+
+    #[test]
+    fn satisfy_caret_version() {
+        assert!(satisfies_ok("0.2.14", "0.2.14"));
+        assert!(satisfies_ok("^0.2.14", "0.2.20"));
+        assert!(!satisfies_ok("^0.2.14", "0.2.13"));
+        assert!(!satisfies_ok("^0.2.14", "0.3.0"));
+        assert!(satisfies_ok("^1.2", "1.9.0"));
+        assert!(!satisfies_ok("^1.2", "2.0.0"));
+        assert!(!satisfies_ok("^0.0.3", "0.0.4"));
+        assert!(satisfies_ok("^0", "0.9.0"));
+    }
+
+    #[test]
+    fn satisfy_tilde_version() {
+        assert!(satisfies_ok("~0.2.14", "0.2.20"));
+        assert!(!satisfies_ok("~1.2.3", "1.3.0"));
+        assert!(satisfies_ok("~1", "1.9.0"));
+    }
+
+    #[test]
+    fn satisfy_comparison() {
+        assert!(satisfies_ok(">=0.2", "0.2.0"));
+        assert!(!satisfies_ok(">0.2", "0.2.14"));
+        assert!(satisfies_ok(">0.2", "0.3.0"));
+        assert!(satisfies_ok("<0.3", "0.2.14"));
+        assert!(satisfies_ok("<=0.2", "0.2.14"));
+        assert!(!satisfies_ok("<=0.2", "0.3.0"));
+    }
+
+    #[test]
+    fn satisfy_multiple_comparators() {
+        assert!(satisfies_ok(">=0.2, <0.4", "0.3.1"));
+        assert!(!satisfies_ok(">=0.2, <0.4", "0.4.0"));
+    }
+
+    #[test]
+    fn satisfy_wildcard_version() {
+        assert!(satisfies_ok("0.2.*", "0.2.14"));
+        assert!(!satisfies_ok("0.2.*", "0.3.0"));
+        assert!(satisfies_ok("*", "0.2.14"));
+    }
+
+    #[test]
+    fn satisfy_pre_release_version() {
+        assert!(satisfies_ok(">=0.3.0-alpha.1", "0.3.0-alpha.2"));
+        assert!(!satisfies_ok(">=0.3.0-alpha.1", "0.3.0-alpha.0"));
+        assert!(!satisfies_ok(">=0.2", "0.3.0-alpha.1"));
+    }
+
+    #[test]
+    fn fail_to_parse_invalid_version() {
+        assert!(satisfies("0.2", "0.2").is_err());
+    }
+
+    #[test]
+    fn fail_to_parse_invalid_requirements() {
+        for requirement in ["foo", "1.2.3.4", ">= ", "0..1", "+1"] {
+            assert!(satisfies(requirement, "0.2.14").is_err());
+        }
+    }
+}
@@ -9,3 +9,5 @@ mkdir -p tmp
 $(dirname $0)/generate_s_expressions.sh 5 >tmp/foo.scm
 
 hyperfine 'target/release/schemat <tmp/foo.scm >/dev/null'
+
+cargo bench